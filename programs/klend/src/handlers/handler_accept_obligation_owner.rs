@@ -0,0 +1,89 @@
+use anchor_lang::{prelude::*, Accounts};
+use farms::program::Farms;
+
+use crate::{
+    lending_market::{farms_ixs, lending_checks},
+    state::{obligation::Obligation, LendingMarket},
+    utils::seeds::BASE_SEED_USER_METADATA,
+    LendingError, UserMetadata,
+};
+
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, AcceptObligationOwner<'info>>) -> Result<()> {
+    let obligation_key = ctx.accounts.obligation.key();
+    let new_owner = ctx.accounts.new_owner.key();
+    let obligation = &mut ctx.accounts.obligation.load_mut()?;
+    let new_owner_user_metadata = &ctx.accounts.new_owner_user_metadata.load()?;
+
+    let referrer = new_owner_user_metadata.referrer;
+    if referrer != obligation.referrer && !obligation.borrows_empty() {
+        msg!("Obligation referrer cannot change while the obligation has debt");
+        return err!(LendingError::ObligationReferrerChangeWithDebt);
+    }
+
+    let obligation_farm_user_states = lending_checks::obligation_farm_user_states(
+        obligation,
+        obligation_key,
+        ctx.remaining_accounts,
+    )?;
+    if !obligation_farm_user_states.is_empty() {
+        let (Some(previous_owner), Some(farms_program)) =
+            (&ctx.accounts.previous_owner, &ctx.accounts.farms_program)
+        else {
+            msg!("Previous owner and farms program are required to transfer obligation farms");
+            return err!(LendingError::InvalidAccountInput);
+        };
+
+        require_keys_eq!(
+            previous_owner.key(),
+            obligation.owner,
+            LendingError::InvalidObligationOwner
+        );
+
+        for obligation_farm_user_state_info in obligation_farm_user_states {
+            msg!(
+                "Transferring obligation farm user state {}",
+                obligation_farm_user_state_info.key()
+            );
+
+            farms_ixs::cpi_transfer_ownership(
+                &farms_program.to_account_info(),
+                &previous_owner.to_account_info(),
+                obligation_farm_user_state_info,
+                new_owner,
+            )?;
+        }
+    }
+
+    msg!(
+        "Prv owner {} referrer {}",
+        obligation.owner,
+        obligation.referrer
+    );
+    msg!("New owner {} referrer {}", new_owner, referrer);
+
+    obligation.transfer_ownership(new_owner, referrer);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptObligationOwner<'info> {
+    pub new_owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        constraint = obligation.load()?.pending_owner == new_owner.key() @ LendingError::InvalidObligationOwner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+
+    #[account(
+        seeds = [BASE_SEED_USER_METADATA, new_owner.key().as_ref()],
+        bump = new_owner_user_metadata.load()?.bump.try_into().unwrap(),
+    )]
+    pub new_owner_user_metadata: AccountLoader<'info, UserMetadata>,
+
+    pub previous_owner: Option<Signer<'info>>,
+    pub farms_program: Option<Program<'info, Farms>>,
+}
@@ -0,0 +1,650 @@
+use std::cell::RefMut;
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program,
+    },
+    Accounts,
+};
+use anchor_spl::{
+    token::Token,
+    token_interface::{self, Mint, TokenAccount, TokenInterface},
+};
+use lending_checks::validate_referrer_token_state;
+
+use super::handler_refresh_obligation_farms_for_reserve::*;
+use crate::{
+    gen_signer_seeds,
+    lending_market::{lending_checks, lending_operations},
+    refresh_farms,
+    state::{obligation::Obligation, CalculateBorrowResult, LendingMarket, Reserve},
+    utils::{seeds, token_transfer, FatAccountLoader, Fraction},
+    xmsg, DepositLiquidityResult, LendingAction, LendingError, LeverageAdjustment,
+    MaxReservesAsCollateralCheck, ReferrerTokenState, ReserveFarmKind,
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, AdjustLeverage<'info>>,
+    target_ltv_pct: u8,
+    min_swap_out_amount: u64,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let clock = Clock::get()?;
+
+    {
+        let lending_market = &accounts.lending_market.load()?;
+        if lending_market.swap_adapter_program == Pubkey::default()
+            || lending_market.swap_adapter_program != accounts.swap_adapter_program.key()
+        {
+            return err!(LendingError::SwapAdapterNotWhitelisted);
+        }
+    }
+
+    let deposits_count = accounts.obligation.load()?.deposits_count();
+    if ctx.remaining_accounts.len() < deposits_count {
+        msg!(
+            "Expected at least {} deposit reserves in remaining accounts",
+            deposits_count
+        );
+        return err!(LendingError::InvalidAccountInput);
+    }
+    // The swap accounts also carry the transfer hook extra accounts, token-2022 resolves them by key.
+    let (deposit_reserves, swap_accounts) = ctx.remaining_accounts.split_at(deposits_count);
+
+    let adjustment = lending_operations::calculate_leverage_adjustment(
+        &accounts.obligation.load()?,
+        &accounts.collateral_reserve.load()?,
+        &accounts.debt_reserve.load()?,
+        accounts.collateral_reserve.key(),
+        target_ltv_pct,
+        clock.slot,
+    )?;
+
+    msg!(
+        "Adjusting leverage to target LTV {}%: {:?}",
+        target_ltv_pct,
+        adjustment
+    );
+
+    match adjustment {
+        LeverageAdjustment::Increase {
+            borrow_liquidity_amount,
+        } => increase_leverage(
+            accounts,
+            &clock,
+            deposit_reserves,
+            swap_accounts,
+            borrow_liquidity_amount,
+            min_swap_out_amount,
+            swap_data,
+        )?,
+        LeverageAdjustment::Decrease {
+            withdraw_collateral_amount,
+        } => decrease_leverage(
+            accounts,
+            &clock,
+            deposit_reserves,
+            swap_accounts,
+            withdraw_collateral_amount,
+            min_swap_out_amount,
+            swap_data,
+        )?,
+    }
+
+    refresh_farms!(
+        ctx.accounts,
+        [
+            (
+                ctx.accounts.collateral_reserve,
+                ctx.accounts.collateral_farms_accounts,
+                Collateral,
+            ),
+            (
+                ctx.accounts.debt_reserve,
+                ctx.accounts.debt_farms_accounts,
+                Debt,
+            ),
+        ],
+    );
+
+    Ok(())
+}
+
+fn increase_leverage<'info>(
+    accounts: &AdjustLeverage<'info>,
+    clock: &Clock,
+    deposit_reserves: &[AccountInfo<'info>],
+    swap_accounts: &[AccountInfo<'info>],
+    borrow_liquidity_amount: u64,
+    min_swap_out_amount: u64,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    let lending_market_key = accounts.lending_market.key();
+    let bump_seed = accounts.lending_market.load()?.bump_seed as u8;
+    let authority_signer_seeds = gen_signer_seeds!(lending_market_key, bump_seed);
+
+    let borrow_amount_f = {
+        let debt_reserve = &mut accounts.debt_reserve.load_mut()?;
+        let lending_market = &accounts.lending_market.load()?;
+        let obligation = &mut accounts.obligation.load_mut()?;
+
+        let referrer_token_state_option: Option<RefMut<ReferrerTokenState>> =
+            if obligation.has_referrer() {
+                match &accounts.referrer_token_state {
+                    Some(referrer_token_state_loader) => {
+                        let referrer_token_state = referrer_token_state_loader.load_mut()?;
+
+                        validate_referrer_token_state(
+                            &crate::ID,
+                            &referrer_token_state,
+                            referrer_token_state_loader.key(),
+                            debt_reserve.liquidity.mint_pubkey,
+                            obligation.referrer,
+                            accounts.debt_reserve.key(),
+                        )?;
+
+                        Some(referrer_token_state)
+                    }
+                    None => return err!(LendingError::ReferrerAccountMissing),
+                }
+            } else {
+                None
+            };
+
+        let initial_reserve_token_balance = token_interface::accessor::amount(
+            &accounts.debt_reserve_liquidity_supply.to_account_info(),
+        )?;
+        let initial_reserve_available_liquidity = debt_reserve.liquidity.available_amount;
+
+        let CalculateBorrowResult {
+            borrow_amount_f,
+            receive_amount,
+            borrow_fee,
+            ..
+        } = lending_operations::borrow_obligation_liquidity_for_leverage(
+            lending_market,
+            debt_reserve,
+            obligation,
+            borrow_liquidity_amount,
+            clock,
+            accounts.debt_reserve.key(),
+            referrer_token_state_option,
+            deposit_reserves
+                .iter()
+                .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap()),
+        )?;
+
+        xmsg!("pnl: Leverage borrow obligation liquidity {receive_amount} with borrow_fee {borrow_fee}",);
+
+        if borrow_fee > 0 {
+            token_transfer::send_origination_fees_transfer(
+                accounts.debt_liquidity_token_program.to_account_info(),
+                accounts.debt_reserve_liquidity_mint.to_account_info(),
+                accounts.debt_reserve_liquidity_supply.to_account_info(),
+                accounts
+                    .debt_reserve_liquidity_fee_receiver
+                    .to_account_info(),
+                accounts.lending_market_authority.to_account_info(),
+                authority_signer_seeds,
+                borrow_fee,
+                accounts.debt_reserve_liquidity_mint.decimals,
+                swap_accounts,
+            )?;
+        }
+
+        token_transfer::borrow_obligation_liquidity_transfer(
+            accounts.debt_liquidity_token_program.to_account_info(),
+            accounts.debt_reserve_liquidity_mint.to_account_info(),
+            accounts.debt_reserve_liquidity_supply.to_account_info(),
+            accounts.user_debt_liquidity.to_account_info(),
+            accounts.lending_market_authority.to_account_info(),
+            authority_signer_seeds,
+            receive_amount,
+            accounts.debt_reserve_liquidity_mint.decimals,
+            swap_accounts,
+        )?;
+
+        lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+            token_interface::accessor::amount(
+                &accounts.debt_reserve_liquidity_supply.to_account_info(),
+            )
+            .unwrap(),
+            debt_reserve.liquidity.available_amount,
+            initial_reserve_token_balance,
+            initial_reserve_available_liquidity,
+            LendingAction::Subtractive(borrow_fee + receive_amount),
+        )?;
+
+        borrow_amount_f
+    };
+
+    let deposit_liquidity_amount = invoke_swap_adapter(
+        accounts,
+        swap_accounts,
+        swap_data,
+        &accounts.user_collateral_liquidity.to_account_info(),
+        min_swap_out_amount,
+    )?;
+    let deposit_liquidity_amount = token_transfer::get_amount_after_transfer_fee(
+        &accounts.collateral_reserve_liquidity_mint.to_account_info(),
+        deposit_liquidity_amount,
+    )?;
+
+    let collateral_reserve = &mut accounts.collateral_reserve.load_mut()?;
+    let debt_reserve = &accounts.debt_reserve.load()?;
+    let lending_market = &accounts.lending_market.load()?;
+    let obligation = &mut accounts.obligation.load_mut()?;
+
+    let initial_reserve_token_balance = token_interface::accessor::amount(
+        &accounts
+            .collateral_reserve_liquidity_supply
+            .to_account_info(),
+    )?;
+    let initial_reserve_available_liquidity = collateral_reserve.liquidity.available_amount;
+
+    let collateral_referrer_token_state = lending_checks::supply_fee_referrer_token_state(
+        &accounts.collateral_referrer_token_state,
+        obligation.referrer,
+        collateral_reserve.config.supply_fees.has_deposit_fee(),
+        collateral_reserve.liquidity.mint_pubkey,
+        accounts.collateral_reserve.key(),
+    )?;
+
+    let DepositLiquidityResult {
+        liquidity_amount,
+        collateral_amount,
+    } = lending_operations::deposit_reserve_liquidity(
+        collateral_reserve,
+        clock,
+        deposit_liquidity_amount,
+        lending_market.referral_fee_bps,
+        collateral_referrer_token_state,
+    )?;
+
+    lending_operations::refresh_reserve(
+        collateral_reserve,
+        clock,
+        None,
+        lending_market.referral_fee_bps,
+    )?;
+
+    lending_operations::deposit_obligation_collateral(
+        lending_market,
+        collateral_reserve,
+        obligation,
+        clock.slot,
+        collateral_amount,
+        accounts.collateral_reserve.key(),
+        MaxReservesAsCollateralCheck::Perform,
+    )?;
+
+    lending_operations::post_adjust_leverage_obligation_invariants(
+        lending_market,
+        obligation,
+        collateral_reserve,
+        debt_reserve,
+        accounts.debt_reserve.key(),
+        liquidity_amount,
+        Fraction::ZERO,
+        borrow_amount_f,
+        Fraction::ZERO,
+    )?;
+    obligation.last_update.mark_stale();
+
+    msg!(
+        "pnl: Leverage deposit reserve liquidity {} and obligation collateral {}",
+        liquidity_amount,
+        collateral_amount
+    );
+
+    token_transfer::deposit_reserve_liquidity_and_obligation_collateral_transfer(
+        accounts.user_collateral_liquidity.to_account_info(),
+        accounts
+            .collateral_reserve_liquidity_supply
+            .to_account_info(),
+        accounts.owner.to_account_info(),
+        accounts.collateral_reserve_liquidity_mint.to_account_info(),
+        accounts
+            .collateral_liquidity_token_program
+            .to_account_info(),
+        accounts
+            .collateral_reserve_collateral_mint
+            .to_account_info(),
+        accounts
+            .collateral_reserve_collateral_supply
+            .to_account_info(),
+        accounts.collateral_token_program.to_account_info(),
+        accounts.lending_market_authority.clone(),
+        authority_signer_seeds,
+        liquidity_amount,
+        accounts.collateral_reserve_liquidity_mint.decimals,
+        collateral_amount,
+        swap_accounts,
+    )?;
+
+    lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+        token_interface::accessor::amount(
+            &accounts
+                .collateral_reserve_liquidity_supply
+                .to_account_info(),
+        )
+        .unwrap(),
+        collateral_reserve.liquidity.available_amount,
+        initial_reserve_token_balance,
+        initial_reserve_available_liquidity,
+        LendingAction::Additive(liquidity_amount),
+    )?;
+
+    Ok(())
+}
+
+fn decrease_leverage<'info>(
+    accounts: &AdjustLeverage<'info>,
+    clock: &Clock,
+    deposit_reserves: &[AccountInfo<'info>],
+    swap_accounts: &[AccountInfo<'info>],
+    withdraw_collateral_amount: u64,
+    min_swap_out_amount: u64,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    let lending_market_key = accounts.lending_market.key();
+    let bump_seed = accounts.lending_market.load()?.bump_seed as u8;
+    let authority_signer_seeds = gen_signer_seeds!(lending_market_key, bump_seed);
+
+    let withdraw_value = {
+        let collateral_reserve = &mut accounts.collateral_reserve.load_mut()?;
+        let debt_reserve = &accounts.debt_reserve.load()?;
+        let lending_market = &accounts.lending_market.load()?;
+        let obligation = &mut accounts.obligation.load_mut()?;
+
+        let (withdraw_collateral_amount, withdraw_value) =
+            lending_operations::withdraw_obligation_collateral_for_swap(
+                lending_market,
+                collateral_reserve,
+                debt_reserve,
+                obligation,
+                withdraw_collateral_amount,
+                clock.slot,
+                accounts.collateral_reserve.key(),
+            )?;
+
+        let initial_reserve_token_balance = token_interface::accessor::amount(
+            &accounts
+                .collateral_reserve_liquidity_supply
+                .to_account_info(),
+        )?;
+        let initial_reserve_available_liquidity = collateral_reserve.liquidity.available_amount;
+
+        let collateral_referrer_token_state = lending_checks::supply_fee_referrer_token_state(
+            &accounts.collateral_referrer_token_state,
+            obligation.referrer,
+            collateral_reserve.config.supply_fees.has_withdrawal_fee(),
+            collateral_reserve.liquidity.mint_pubkey,
+            accounts.collateral_reserve.key(),
+        )?;
+
+        let withdraw_liquidity_amount = lending_operations::redeem_reserve_collateral(
+            collateral_reserve,
+            withdraw_collateral_amount,
+            clock,
+            true,
+            lending_market.referral_fee_bps,
+            collateral_referrer_token_state,
+        )?;
+
+        msg!(
+            "pnl: Deleverage withdraw obligation collateral {} liquidity {}",
+            withdraw_collateral_amount,
+            withdraw_liquidity_amount
+        );
+
+        token_transfer::withdraw_and_redeem_reserve_collateral_transfer(
+            accounts.collateral_token_program.to_account_info(),
+            accounts
+                .collateral_liquidity_token_program
+                .to_account_info(),
+            accounts.collateral_reserve_liquidity_mint.to_account_info(),
+            accounts
+                .collateral_reserve_collateral_mint
+                .to_account_info(),
+            accounts
+                .collateral_reserve_collateral_supply
+                .to_account_info(),
+            accounts
+                .collateral_reserve_liquidity_supply
+                .to_account_info(),
+            accounts.user_collateral_liquidity.to_account_info(),
+            accounts.lending_market_authority.clone(),
+            authority_signer_seeds,
+            withdraw_collateral_amount,
+            withdraw_liquidity_amount,
+            accounts.collateral_reserve_liquidity_mint.decimals,
+            swap_accounts,
+        )?;
+
+        lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+            token_interface::accessor::amount(
+                &accounts
+                    .collateral_reserve_liquidity_supply
+                    .to_account_info(),
+            )
+            .unwrap(),
+            collateral_reserve.liquidity.available_amount,
+            initial_reserve_token_balance,
+            initial_reserve_available_liquidity,
+            LendingAction::Subtractive(withdraw_liquidity_amount),
+        )?;
+
+        withdraw_value
+    };
+
+    let repay_liquidity_amount = invoke_swap_adapter(
+        accounts,
+        swap_accounts,
+        swap_data,
+        &accounts.user_debt_liquidity.to_account_info(),
+        min_swap_out_amount,
+    )?;
+    let repay_liquidity_amount = token_transfer::get_amount_after_transfer_fee(
+        &accounts.debt_reserve_liquidity_mint.to_account_info(),
+        repay_liquidity_amount,
+    )?;
+
+    let debt_reserve = &mut accounts.debt_reserve.load_mut()?;
+    let lending_market = &accounts.lending_market.load()?;
+    let obligation = &mut accounts.obligation.load_mut()?;
+
+    let active_deposit_reserves: Vec<Pubkey> = obligation
+        .deposits
+        .iter()
+        .filter(|deposit| deposit.deposit_reserve != Pubkey::default())
+        .map(|deposit| deposit.deposit_reserve)
+        .collect();
+
+    let initial_reserve_token_balance = token_interface::accessor::amount(
+        &accounts.debt_reserve_liquidity_supply.to_account_info(),
+    )?;
+    let initial_reserve_available_liquidity = debt_reserve.liquidity.available_amount;
+
+    let repay_amount = lending_operations::repay_obligation_liquidity(
+        debt_reserve,
+        obligation,
+        clock,
+        repay_liquidity_amount,
+        accounts.debt_reserve.key(),
+        lending_market,
+        deposit_reserves
+            .iter()
+            .filter(|account_info| active_deposit_reserves.contains(account_info.key))
+            .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap()),
+    )?;
+
+    let collateral_reserve = &accounts.collateral_reserve.load()?;
+    lending_operations::post_adjust_leverage_obligation_invariants(
+        lending_market,
+        obligation,
+        collateral_reserve,
+        debt_reserve,
+        accounts.debt_reserve.key(),
+        0,
+        withdraw_value,
+        Fraction::ZERO,
+        Fraction::from(repay_amount),
+    )?;
+
+    xmsg!("pnl: Deleverage repay obligation liquidity {repay_amount} swap output {repay_liquidity_amount}",);
+
+    token_transfer::repay_obligation_liquidity_transfer(
+        accounts.debt_liquidity_token_program.to_account_info(),
+        accounts.debt_reserve_liquidity_mint.to_account_info(),
+        accounts.user_debt_liquidity.to_account_info(),
+        accounts.debt_reserve_liquidity_supply.to_account_info(),
+        accounts.owner.to_account_info(),
+        repay_amount,
+        accounts.debt_reserve_liquidity_mint.decimals,
+        swap_accounts,
+    )?;
+
+    lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+        token_interface::accessor::amount(
+            &accounts.debt_reserve_liquidity_supply.to_account_info(),
+        )
+        .unwrap(),
+        debt_reserve.liquidity.available_amount,
+        initial_reserve_token_balance,
+        initial_reserve_available_liquidity,
+        LendingAction::Additive(repay_amount),
+    )?;
+
+    Ok(())
+}
+
+fn invoke_swap_adapter<'info>(
+    accounts: &AdjustLeverage<'info>,
+    swap_accounts: &[AccountInfo<'info>],
+    swap_data: Vec<u8>,
+    swap_destination: &AccountInfo<'info>,
+    min_swap_out_amount: u64,
+) -> Result<u64> {
+    let initial_destination_balance = token_interface::accessor::amount(swap_destination)?;
+
+    let swap_instruction = Instruction {
+        program_id: accounts.swap_adapter_program.key(),
+        accounts: swap_accounts
+            .iter()
+            .map(|account_info| AccountMeta {
+                pubkey: account_info.key(),
+                is_signer: account_info.is_signer,
+                is_writable: account_info.is_writable,
+            })
+            .collect(),
+        data: swap_data,
+    };
+    program::invoke(&swap_instruction, swap_accounts)?;
+
+    let swap_out_amount = token_interface::accessor::amount(swap_destination)?
+        .checked_sub(initial_destination_balance)
+        .ok_or_else(|| error!(LendingError::AdjustLeverageSlippageExceeded))?;
+
+    if swap_out_amount < min_swap_out_amount {
+        msg!(
+            "Swap output {} is lower than the minimum accepted {}",
+            swap_out_amount,
+            min_swap_out_amount
+        );
+        return err!(LendingError::AdjustLeverageSlippageExceeded);
+    }
+
+    Ok(swap_out_amount)
+}
+
+#[derive(Accounts)]
+pub struct AdjustLeverage<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    #[account(mut, has_one = lending_market)]
+    pub collateral_reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = collateral_reserve.load()?.liquidity.mint_pubkey,
+        mint::token_program = collateral_liquidity_token_program,
+    )]
+    pub collateral_reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = collateral_reserve.load()?.liquidity.supply_vault,
+    )]
+    pub collateral_reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = collateral_reserve.load()?.collateral.mint_pubkey)]
+    pub collateral_reserve_collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = collateral_reserve.load()?.collateral.supply_vault)]
+    pub collateral_reserve_collateral_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        has_one = lending_market,
+        constraint = debt_reserve.key() != collateral_reserve.key() @ LendingError::InvalidAccountInput
+    )]
+    pub debt_reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = debt_reserve.load()?.liquidity.mint_pubkey,
+        mint::token_program = debt_liquidity_token_program,
+    )]
+    pub debt_reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = debt_reserve.load()?.liquidity.supply_vault
+    )]
+    pub debt_reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        address = debt_reserve.load()?.liquidity.fee_vault
+    )]
+    pub debt_reserve_liquidity_fee_receiver: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        token::mint = collateral_reserve.load()?.liquidity.mint_pubkey,
+        token::authority = owner,
+    )]
+    pub user_collateral_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        token::mint = debt_reserve.load()?.liquidity.mint_pubkey,
+        token::authority = owner,
+    )]
+    pub user_debt_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
+    #[account(mut)]
+    pub collateral_referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
+    pub swap_adapter_program: AccountInfo<'info>,
+
+    pub collateral_token_program: Program<'info, Token>,
+    pub collateral_liquidity_token_program: Interface<'info, TokenInterface>,
+    pub debt_liquidity_token_program: Interface<'info, TokenInterface>,
+
+    pub collateral_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub debt_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub farms_program: Program<'info, farms::program::Farms>,
+}
@@ -13,7 +13,10 @@ use crate::{
     check_refresh_ixs, gen_signer_seeds,
     lending_market::{lending_checks, lending_operations},
     refresh_farms,
-    state::{obligation::Obligation, CalculateBorrowResult, LendingMarket, Reserve},
+    state::{
+        obligation::{Obligation, ObligationDelegatePermissions},
+        CalculateBorrowResult, LendingMarket, Reserve,
+    },
     utils::{seeds, token_transfer, FatAccountLoader},
     xmsg, LendingAction, LendingError, ReferrerTokenState, ReserveFarmKind,
 };
@@ -50,7 +53,7 @@ pub fn process_v2<'info>(
     Ok(())
 }
 
-fn process_impl<'info>(
+pub(super) fn process_impl<'info>(
     accounts: &BorrowObligationLiquidity<'info>,
     remaining_accounts: &[AccountInfo<'info>],
     liquidity_amount: u64,
@@ -67,7 +70,17 @@ fn process_impl<'info>(
     let authority_signer_seeds =
         gen_signer_seeds!(lending_market_key.as_ref(), lending_market.bump_seed as u8);
 
-    let deposit_reserves_iter = remaining_accounts
+    let deposits_count = obligation.deposits_count();
+    if remaining_accounts.len() < deposits_count {
+        msg!(
+            "Expected at least {} deposit reserves in remaining accounts",
+            deposits_count
+        );
+        return err!(LendingError::InvalidAccountInput);
+    }
+    let (deposit_reserves, transfer_hook_accounts) = remaining_accounts.split_at(deposits_count);
+
+    let deposit_reserves_iter = deposit_reserves
         .iter()
         .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap());
 
@@ -99,6 +112,7 @@ fn process_impl<'info>(
     let initial_reserve_available_liquidity = borrow_reserve.liquidity.available_amount;
 
     let CalculateBorrowResult {
+        borrow_amount_f,
         receive_amount,
         borrow_fee,
         ..
@@ -113,6 +127,14 @@ fn process_impl<'info>(
         deposit_reserves_iter,
     )?;
 
+    if accounts.owner.key() != obligation.owner {
+        lending_operations::utils::check_delegate_borrow_ltv(
+            borrow_amount_f,
+            obligation,
+            borrow_reserve,
+        )?;
+    }
+
     xmsg!("pnl: Borrow obligation liquidity {receive_amount} with borrow_fee {borrow_fee}",);
 
     if borrow_fee > 0 {
@@ -127,6 +149,7 @@ fn process_impl<'info>(
             authority_signer_seeds,
             borrow_fee,
             accounts.borrow_reserve_liquidity_mint.decimals,
+            transfer_hook_accounts,
         )?;
     }
 
@@ -139,6 +162,7 @@ fn process_impl<'info>(
         authority_signer_seeds,
         receive_amount,
         accounts.borrow_reserve_liquidity_mint.decimals,
+        transfer_hook_accounts,
     )?;
 
     lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
@@ -159,7 +183,7 @@ pub struct BorrowObligationLiquidity<'info> {
 
     #[account(mut,
         has_one = lending_market,
-        has_one = owner @ LendingError::InvalidObligationOwner
+        constraint = obligation.load()?.has_authority(owner.key(), ObligationDelegatePermissions::BORROW) @ LendingError::InvalidObligationOwner
     )]
     pub obligation: AccountLoader<'info, Obligation>,
 
@@ -193,7 +217,7 @@ pub struct BorrowObligationLiquidity<'info> {
 
     #[account(mut,
         token::mint = reserve_source_liquidity.mint,
-        token::authority = owner,
+        token::authority = obligation.load()?.owner,
     )]
     pub user_destination_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -0,0 +1,142 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::{instructions::Instructions as SysInstructions, SysvarId},
+    Accounts,
+};
+use anchor_spl::{
+    token::spl_token,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    gen_signer_seeds,
+    handler_borrow_obligation_liquidity::{self, BorrowObligationLiquidity},
+    handler_refresh_obligation_farms_for_reserve::*,
+    refresh_farms,
+    state::{
+        obligation::{Obligation, ObligationDelegatePermissions},
+        LendingMarket, Reserve,
+    },
+    utils::{seeds, token_transfer},
+    LendingError, ReferrerTokenState, ReserveFarmKind,
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, BorrowObligationLiquidityNative<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let borrow_accounts = BorrowObligationLiquidity {
+        owner: accounts.owner.clone(),
+        obligation: accounts.obligation.clone(),
+        lending_market: accounts.lending_market.clone(),
+        lending_market_authority: accounts.lending_market_authority.clone(),
+        borrow_reserve: accounts.borrow_reserve.clone(),
+        borrow_reserve_liquidity_mint: accounts.borrow_reserve_liquidity_mint.clone(),
+        reserve_source_liquidity: accounts.reserve_source_liquidity.clone(),
+        borrow_reserve_liquidity_fee_receiver: accounts
+            .borrow_reserve_liquidity_fee_receiver
+            .clone(),
+        user_destination_liquidity: accounts.user_native_liquidity.clone(),
+        referrer_token_state: accounts.referrer_token_state.clone(),
+        token_program: accounts.token_program.clone(),
+        instruction_sysvar_account: accounts.instruction_sysvar_account.clone(),
+    };
+
+    handler_borrow_obligation_liquidity::process_impl(
+        &borrow_accounts,
+        ctx.remaining_accounts,
+        liquidity_amount,
+    )?;
+    refresh_farms!(
+        borrow_accounts,
+        [(
+            borrow_accounts.borrow_reserve,
+            accounts.farms_accounts,
+            Debt,
+        )],
+    );
+
+    let lending_market = accounts.lending_market.load()?;
+    let lending_market_key = accounts.lending_market.key();
+    let authority_signer_seeds =
+        gen_signer_seeds!(lending_market_key.as_ref(), lending_market.bump_seed as u8);
+
+    token_transfer::close_native_liquidity_account(
+        accounts.token_program.to_account_info(),
+        accounts.user_native_liquidity.to_account_info(),
+        accounts.obligation_owner.clone(),
+        accounts.lending_market_authority.clone(),
+        &[authority_signer_seeds],
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct BorrowObligationLiquidityNative<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        constraint = obligation.load()?.has_authority(owner.key(), ObligationDelegatePermissions::BORROW) @ LendingError::InvalidObligationOwner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    #[account(mut,
+        address = obligation.load()?.owner @ LendingError::InvalidObligationOwner,
+    )]
+    pub obligation_owner: AccountInfo<'info>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+    )]
+    pub borrow_reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = borrow_reserve.load()?.liquidity.mint_pubkey,
+        constraint = borrow_reserve_liquidity_mint.key() == spl_token::native_mint::ID @ LendingError::ReserveNotNativeSol,
+        mint::token_program = token_program,
+    )]
+    pub borrow_reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = borrow_reserve.load()?.liquidity.supply_vault
+    )]
+    pub reserve_source_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        address = borrow_reserve.load()?.liquidity.fee_vault
+    )]
+    pub borrow_reserve_liquidity_fee_receiver: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(init,
+        seeds = [seeds::NATIVE_LIQUIDITY, owner.key().as_ref()],
+        bump,
+        payer = owner,
+        token::mint = borrow_reserve_liquidity_mint,
+        token::authority = lending_market_authority,
+        token::token_program = token_program,
+    )]
+    pub user_native_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    #[account(address = SysInstructions::id())]
+    pub instruction_sysvar_account: AccountInfo<'info>,
+
+    pub farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub farms_program: Program<'info, farms::program::Farms>,
+}
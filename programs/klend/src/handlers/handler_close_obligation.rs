@@ -0,0 +1,56 @@
+use anchor_lang::{prelude::*, Accounts};
+use farms::state::UserState as FarmsUserState;
+
+use crate::{
+    lending_market::lending_checks,
+    state::{obligation::Obligation, LendingMarket},
+    LendingError,
+};
+
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, CloseObligation<'info>>) -> Result<()> {
+    let obligation_key = ctx.accounts.obligation.key();
+
+    let obligation_farm_user_states = lending_checks::obligation_farm_user_states(
+        &ctx.accounts.obligation.load()?,
+        obligation_key,
+        ctx.remaining_accounts,
+    )?;
+
+    for account_info in obligation_farm_user_states {
+        let obligation_farm_user_state = AccountLoader::<FarmsUserState>::try_from(account_info)?;
+
+        if obligation_farm_user_state.load()?.active_stake_scaled != 0 {
+            msg!(
+                "Obligation farm user state {} still has an active stake",
+                account_info.key()
+            );
+            return err!(LendingError::ObligationFarmsNotSettled);
+        }
+    }
+
+    msg!(
+        "Closing obligation {} of owner {}",
+        obligation_key,
+        ctx.accounts.owner.key()
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseObligation<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner,
+        constraint = obligation.load()?.deposits_empty() && obligation.load()?.borrows_empty() @ LendingError::ObligationNotEmpty,
+        close = rent_receiver
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+
+    #[account(mut)]
+    pub rent_receiver: AccountInfo<'info>,
+}
@@ -0,0 +1,42 @@
+use anchor_lang::{prelude::*, Accounts};
+
+use crate::{
+    state::{LendingMarket, Reserve},
+    utils::seeds::BASE_SEED_REFERRER_TOKEN_STATE,
+    LendingError, ReferrerTokenState,
+};
+
+pub fn process(ctx: Context<CloseReferrerTokenState>) -> Result<()> {
+    msg!(
+        "Closing referrer token state of referrer {} for reserve {}",
+        ctx.accounts.referrer.key(),
+        ctx.accounts.reserve.key()
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseReferrerTokenState<'info> {
+    pub referrer: Signer<'info>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+
+    #[account(
+        has_one = lending_market
+    )]
+    pub reserve: AccountLoader<'info, Reserve>,
+
+    #[account(mut,
+        seeds = [BASE_SEED_REFERRER_TOKEN_STATE, referrer.key().as_ref(), reserve.key().as_ref()],
+        bump = referrer_token_state.load()?.bump.try_into().unwrap(),
+        has_one = referrer,
+        constraint = referrer_token_state.load()?.mint == reserve.load()?.liquidity.mint_pubkey @ LendingError::InvalidAccountInput,
+        constraint = referrer_token_state.load()?.amount_unclaimed_sf == 0 @ LendingError::ReferrerTokenStateNotEmpty,
+        close = rent_receiver
+    )]
+    pub referrer_token_state: AccountLoader<'info, ReferrerTokenState>,
+
+    #[account(mut)]
+    pub rent_receiver: AccountInfo<'info>,
+}
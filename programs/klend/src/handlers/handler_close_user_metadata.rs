@@ -0,0 +1,28 @@
+use anchor_lang::{prelude::*, Accounts};
+
+use crate::{utils::seeds::BASE_SEED_USER_METADATA, UserMetadata};
+
+pub fn process(ctx: Context<CloseUserMetadata>) -> Result<()> {
+    msg!(
+        "Closing user metadata of owner {}",
+        ctx.accounts.owner.key()
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseUserMetadata<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        seeds = [BASE_SEED_USER_METADATA, owner.key().as_ref()],
+        bump = user_metadata.load()?.bump.try_into().unwrap(),
+        has_one = owner,
+        close = rent_receiver
+    )]
+    pub user_metadata: AccountLoader<'info, UserMetadata>,
+
+    #[account(mut)]
+    pub rent_receiver: AccountInfo<'info>,
+}
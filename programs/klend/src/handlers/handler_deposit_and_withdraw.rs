@@ -25,6 +25,7 @@ pub fn process(
             &ctx.accounts.deposit_accounts,
             liquidity_amount,
             MaxReservesAsCollateralCheck::Skip,
+            &[],
         )?;
     }
 
@@ -65,6 +66,7 @@ pub fn process(
             &ctx.accounts.withdraw_accounts,
             withdraw_collateral_amount,
             LtvMaxWithdrawalCheck::LiquidationThreshold,
+            &[],
         )?
     };
 
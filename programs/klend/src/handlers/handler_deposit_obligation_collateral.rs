@@ -11,9 +11,12 @@ use crate::{
     handler_refresh_obligation_farms_for_reserve::*,
     lending_market::{lending_checks, lending_operations},
     refresh_farms,
-    state::{obligation::Obligation, DepositObligationCollateralAccounts, LendingMarket, Reserve},
+    state::{
+        obligation::{Obligation, ObligationDelegatePermissions},
+        DepositObligationCollateralAccounts, LendingMarket, Reserve,
+    },
     utils::{seeds, token_transfer},
-    MaxReservesAsCollateralCheck, ReserveFarmKind,
+    LendingError, MaxReservesAsCollateralCheck, ReserveFarmKind,
 };
 
 pub fn process_v1(ctx: Context<DepositObligationCollateral>, collateral_amount: u64) -> Result<()> {
@@ -96,7 +99,7 @@ pub struct DepositObligationCollateral<'info> {
     pub owner: Signer<'info>,
 
     #[account(mut,
-        has_one = owner,
+        constraint = obligation.load()?.has_authority(owner.key(), ObligationDelegatePermissions::DEPOSIT) @ LendingError::InvalidObligationOwner,
         has_one = lending_market,
     )]
     pub obligation: AccountLoader<'info, Obligation>,
@@ -14,68 +14,98 @@ use crate::{
     lending_market::{lending_checks, lending_operations},
     state::{LendingMarket, Reserve},
     utils::{seeds, token_transfer},
-    DepositLiquidityResult, LendingAction,
+    DepositLiquidityResult, LendingAction, ReferrerTokenState, UserMetadata,
 };
 
-pub fn process(ctx: Context<DepositReserveLiquidity>, liquidity_amount: u64) -> Result<()> {
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositReserveLiquidity<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    process_impl(ctx.accounts, liquidity_amount, ctx.remaining_accounts)
+}
+
+pub(super) fn process_impl<'info>(
+    accounts: &DepositReserveLiquidity<'info>,
+    liquidity_amount: u64,
+    transfer_hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
     lending_checks::deposit_reserve_liquidity_checks(
         &crate::state::nested_accounts::DepositReserveLiquidityAccounts {
-            lending_market: ctx.accounts.lending_market.clone(),
-            lending_market_authority: ctx.accounts.lending_market_authority.clone(),
-            reserve: ctx.accounts.reserve.clone(),
-            reserve_liquidity_mint: ctx.accounts.reserve_liquidity_mint.clone(),
-            reserve_liquidity_supply: ctx.accounts.reserve_liquidity_supply.clone(),
-            reserve_collateral_mint: ctx.accounts.reserve_collateral_mint.clone(),
-            owner: ctx.accounts.owner.clone(),
-            user_source_liquidity: ctx.accounts.user_source_liquidity.clone(),
-            user_destination_collateral: ctx.accounts.user_destination_collateral.clone(),
-            liquidity_token_program: ctx.accounts.liquidity_token_program.clone(),
+            lending_market: accounts.lending_market.clone(),
+            lending_market_authority: accounts.lending_market_authority.clone(),
+            reserve: accounts.reserve.clone(),
+            reserve_liquidity_mint: accounts.reserve_liquidity_mint.clone(),
+            reserve_liquidity_supply: accounts.reserve_liquidity_supply.clone(),
+            reserve_collateral_mint: accounts.reserve_collateral_mint.clone(),
+            owner: accounts.owner.clone(),
+            user_source_liquidity: accounts.user_source_liquidity.clone(),
+            user_destination_collateral: accounts.user_destination_collateral.clone(),
+            liquidity_token_program: accounts.liquidity_token_program.clone(),
         },
     )?;
 
     let clock = Clock::get()?;
-    let reserve = &mut ctx.accounts.reserve.load_mut()?;
-    let lending_market = &ctx.accounts.lending_market.load()?;
+    let reserve = &mut accounts.reserve.load_mut()?;
+    let lending_market = &accounts.lending_market.load()?;
 
-    let lending_market_key = ctx.accounts.lending_market.key();
+    let lending_market_key = accounts.lending_market.key();
     let authority_signer_seeds =
         gen_signer_seeds!(lending_market_key.as_ref(), lending_market.bump_seed as u8);
 
     refresh_reserve(reserve, &clock, None, lending_market.referral_fee_bps)?;
 
-    let initial_reserve_token_balance = token_interface::accessor::amount(
-        &ctx.accounts.reserve_liquidity_supply.to_account_info(),
-    )?;
+    let initial_reserve_token_balance =
+        token_interface::accessor::amount(&accounts.reserve_liquidity_supply.to_account_info())?;
     let initial_reserve_available_liquidity = reserve.liquidity.available_amount;
+
+    let referrer = match &accounts.owner_user_metadata {
+        Some(owner_user_metadata) => owner_user_metadata.load()?.referrer,
+        None => Pubkey::default(),
+    };
+    let referrer_token_state = lending_checks::supply_fee_referrer_token_state(
+        &accounts.referrer_token_state,
+        referrer,
+        reserve.config.supply_fees.has_deposit_fee(),
+        reserve.liquidity.mint_pubkey,
+        accounts.reserve.key(),
+    )?;
+
     let DepositLiquidityResult {
         liquidity_amount,
         collateral_amount,
-    } = lending_operations::deposit_reserve_liquidity(reserve, &clock, liquidity_amount)?;
+    } = lending_operations::deposit_reserve_liquidity(
+        reserve,
+        &clock,
+        liquidity_amount,
+        lending_market.referral_fee_bps,
+        referrer_token_state,
+    )?;
 
     msg!(
         "pnl: Depositing in reserve {} liquidity {}",
-        ctx.accounts.reserve.key(),
+        accounts.reserve.key(),
         liquidity_amount
     );
 
     token_transfer::deposit_reserve_liquidity_transfer(
-        ctx.accounts.user_source_liquidity.to_account_info(),
-        ctx.accounts.reserve_liquidity_supply.to_account_info(),
-        ctx.accounts.owner.to_account_info(),
-        ctx.accounts.reserve_liquidity_mint.to_account_info(),
-        ctx.accounts.liquidity_token_program.to_account_info(),
-        ctx.accounts.reserve_collateral_mint.to_account_info(),
-        ctx.accounts.collateral_token_program.to_account_info(),
-        ctx.accounts.user_destination_collateral.to_account_info(),
-        ctx.accounts.lending_market_authority.clone(),
+        accounts.user_source_liquidity.to_account_info(),
+        accounts.reserve_liquidity_supply.to_account_info(),
+        accounts.owner.to_account_info(),
+        accounts.reserve_liquidity_mint.to_account_info(),
+        accounts.liquidity_token_program.to_account_info(),
+        accounts.reserve_collateral_mint.to_account_info(),
+        accounts.collateral_token_program.to_account_info(),
+        accounts.user_destination_collateral.to_account_info(),
+        accounts.lending_market_authority.clone(),
         authority_signer_seeds,
         liquidity_amount,
-        ctx.accounts.reserve_liquidity_mint.decimals,
+        accounts.reserve_liquidity_mint.decimals,
         collateral_amount,
+        transfer_hook_accounts,
     )?;
 
     lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
-        token_interface::accessor::amount(&ctx.accounts.reserve_liquidity_supply.to_account_info())
+        token_interface::accessor::amount(&accounts.reserve_liquidity_supply.to_account_info())
             .unwrap(),
         reserve.liquidity.available_amount,
         initial_reserve_token_balance,
@@ -125,6 +155,15 @@ pub struct DepositReserveLiquidity<'info> {
     )]
     pub user_destination_collateral: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    #[account(
+        seeds = [seeds::BASE_SEED_USER_METADATA, owner.key().as_ref()],
+        bump = owner_user_metadata.load()?.bump.try_into().unwrap(),
+    )]
+    pub owner_user_metadata: Option<AccountLoader<'info, UserMetadata>>,
+
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
     pub collateral_token_program: Program<'info, Token>,
     pub liquidity_token_program: Interface<'info, TokenInterface>,
 
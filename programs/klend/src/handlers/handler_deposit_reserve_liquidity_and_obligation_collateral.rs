@@ -13,13 +13,18 @@ use crate::{
     handler_refresh_obligation_farms_for_reserve::*,
     lending_market::{lending_checks, lending_operations},
     refresh_farms,
-    state::{nested_accounts::*, obligation::Obligation, LendingMarket, Reserve},
+    state::{
+        nested_accounts::*,
+        obligation::{Obligation, ObligationDelegatePermissions},
+        LendingMarket, Reserve,
+    },
     utils::{seeds, token_transfer},
-    DepositLiquidityResult, LendingAction, MaxReservesAsCollateralCheck, ReserveFarmKind,
+    DepositLiquidityResult, LendingAction, LendingError, MaxReservesAsCollateralCheck,
+    ReferrerTokenState, ReserveFarmKind,
 };
 
-pub fn process_v1(
-    ctx: Context<DepositReserveLiquidityAndObligationCollateral>,
+pub fn process_v1<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositReserveLiquidityAndObligationCollateral<'info>>,
     liquidity_amount: u64,
 ) -> Result<()> {
     check_refresh_ixs!(
@@ -31,17 +36,19 @@ pub fn process_v1(
         ctx.accounts,
         liquidity_amount,
         MaxReservesAsCollateralCheck::Perform,
+        ctx.remaining_accounts,
     )
 }
 
-pub fn process_v2(
-    ctx: Context<DepositReserveLiquidityAndObligationCollateralV2>,
+pub fn process_v2<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositReserveLiquidityAndObligationCollateralV2<'info>>,
     liquidity_amount: u64,
 ) -> Result<()> {
     process_impl(
         &ctx.accounts.deposit_accounts,
         liquidity_amount,
         MaxReservesAsCollateralCheck::Perform,
+        ctx.remaining_accounts,
     )?;
 
     refresh_farms!(
@@ -56,10 +63,11 @@ pub fn process_v2(
     Ok(())
 }
 
-pub(super) fn process_impl(
-    accounts: &DepositReserveLiquidityAndObligationCollateral,
+pub(super) fn process_impl<'info>(
+    accounts: &DepositReserveLiquidityAndObligationCollateral<'info>,
     liquidity_amount: u64,
     max_reserves_as_collateral_check: MaxReservesAsCollateralCheck,
+    transfer_hook_accounts: &[AccountInfo<'info>],
 ) -> Result<()> {
     msg!(
         "DepositReserveLiquidityAndObligationCollateral Reserve {} amount {}",
@@ -88,10 +96,23 @@ pub(super) fn process_impl(
     let initial_reserve_token_balance =
         token_interface::accessor::amount(&accounts.reserve_liquidity_supply.to_account_info())?;
     let initial_reserve_available_liquidity = reserve.liquidity.available_amount;
+    let referrer_token_state = lending_checks::supply_fee_referrer_token_state(
+        &accounts.referrer_token_state,
+        obligation.referrer,
+        reserve.config.supply_fees.has_deposit_fee(),
+        reserve.liquidity.mint_pubkey,
+        accounts.reserve.key(),
+    )?;
     let DepositLiquidityResult {
         liquidity_amount,
         collateral_amount,
-    } = lending_operations::deposit_reserve_liquidity(reserve, &clock, liquidity_amount)?;
+    } = lending_operations::deposit_reserve_liquidity(
+        reserve,
+        &clock,
+        liquidity_amount,
+        lending_market.referral_fee_bps,
+        referrer_token_state,
+    )?;
 
     lending_operations::refresh_reserve(reserve, &clock, None, lending_market.referral_fee_bps)?;
 
@@ -127,6 +148,7 @@ pub(super) fn process_impl(
         liquidity_amount,
         accounts.reserve_liquidity_mint.decimals,
         collateral_amount,
+        transfer_hook_accounts,
     )?;
 
     lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
@@ -148,7 +170,7 @@ pub struct DepositReserveLiquidityAndObligationCollateral<'info> {
 
     #[account(mut,
         has_one = lending_market,
-        has_one = owner
+        constraint = obligation.load()?.has_authority(owner.key(), ObligationDelegatePermissions::DEPOSIT) @ LendingError::InvalidObligationOwner
     )]
     pub obligation: AccountLoader<'info, Obligation>,
 
@@ -187,6 +209,9 @@ pub struct DepositReserveLiquidityAndObligationCollateral<'info> {
 
     pub placeholder_user_destination_collateral: Option<AccountInfo<'info>>,
 
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
     pub collateral_token_program: Program<'info, Token>,
     pub liquidity_token_program: Interface<'info, TokenInterface>,
 
@@ -0,0 +1,147 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::{instructions::Instructions as SysInstructions, SysvarId},
+    Accounts,
+};
+use anchor_spl::{
+    token::{spl_token, Token},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    handler_deposit_reserve_liquidity_and_obligation_collateral::{
+        self, DepositReserveLiquidityAndObligationCollateral,
+    },
+    handler_refresh_obligation_farms_for_reserve::*,
+    refresh_farms,
+    state::{
+        obligation::{Obligation, ObligationDelegatePermissions},
+        LendingMarket, Reserve,
+    },
+    utils::{seeds, token_transfer},
+    LendingError, MaxReservesAsCollateralCheck, ReferrerTokenState, ReserveFarmKind,
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositReserveLiquidityAndObligationCollateralNative<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    token_transfer::wrap_native_liquidity_transfer(
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.liquidity_token_program.to_account_info(),
+        ctx.accounts.owner.to_account_info(),
+        ctx.accounts.user_native_liquidity.to_account_info(),
+        liquidity_amount,
+    )?;
+    ctx.accounts.user_native_liquidity.reload()?;
+
+    let accounts = &ctx.accounts;
+    let deposit_accounts = DepositReserveLiquidityAndObligationCollateral {
+        owner: accounts.owner.clone(),
+        obligation: accounts.obligation.clone(),
+        lending_market: accounts.lending_market.clone(),
+        lending_market_authority: accounts.lending_market_authority.clone(),
+        reserve: accounts.reserve.clone(),
+        reserve_liquidity_mint: accounts.reserve_liquidity_mint.clone(),
+        reserve_liquidity_supply: accounts.reserve_liquidity_supply.clone(),
+        reserve_collateral_mint: accounts.reserve_collateral_mint.clone(),
+        reserve_destination_deposit_collateral: accounts
+            .reserve_destination_deposit_collateral
+            .clone(),
+        user_source_liquidity: accounts.user_native_liquidity.clone(),
+        placeholder_user_destination_collateral: None,
+        referrer_token_state: accounts.referrer_token_state.clone(),
+        collateral_token_program: accounts.collateral_token_program.clone(),
+        liquidity_token_program: accounts.liquidity_token_program.clone(),
+        instruction_sysvar_account: accounts.instruction_sysvar_account.clone(),
+    };
+
+    handler_deposit_reserve_liquidity_and_obligation_collateral::process_impl(
+        &deposit_accounts,
+        liquidity_amount,
+        MaxReservesAsCollateralCheck::Perform,
+        &[],
+    )?;
+
+    refresh_farms!(
+        deposit_accounts,
+        [(
+            deposit_accounts.reserve,
+            accounts.farms_accounts,
+            Collateral,
+        )],
+    );
+
+    token_transfer::close_native_liquidity_account(
+        accounts.liquidity_token_program.to_account_info(),
+        accounts.user_native_liquidity.to_account_info(),
+        accounts.owner.to_account_info(),
+        accounts.owner.to_account_info(),
+        &[],
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DepositReserveLiquidityAndObligationCollateralNative<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        constraint = obligation.load()?.has_authority(owner.key(), ObligationDelegatePermissions::DEPOSIT) @ LendingError::InvalidObligationOwner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    #[account(mut, has_one = lending_market)]
+    pub reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = reserve.load()?.liquidity.mint_pubkey,
+        constraint = reserve_liquidity_mint.key() == spl_token::native_mint::ID @ LendingError::ReserveNotNativeSol,
+        mint::token_program = liquidity_token_program,
+    )]
+    pub reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = reserve.load()?.liquidity.supply_vault,
+    )]
+    pub reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = reserve.load()?.collateral.mint_pubkey)]
+    pub reserve_collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = reserve.load()?.collateral.supply_vault)]
+    pub reserve_destination_deposit_collateral: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(init,
+        seeds = [seeds::NATIVE_LIQUIDITY, owner.key().as_ref()],
+        bump,
+        payer = owner,
+        token::mint = reserve_liquidity_mint,
+        token::authority = owner,
+        token::token_program = liquidity_token_program,
+    )]
+    pub user_native_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
+    pub collateral_token_program: Program<'info, Token>,
+    pub liquidity_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    #[account(address = SysInstructions::id())]
+    pub instruction_sysvar_account: AccountInfo<'info>,
+
+    pub farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub farms_program: Program<'info, farms::program::Farms>,
+}
@@ -0,0 +1,138 @@
+use anchor_lang::{prelude::*, Accounts};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    gen_signer_seeds,
+    lending_market::lending_operations,
+    state::{LendingMarket, Reserve},
+    utils::{constraints, seeds, token_transfer},
+    LendingError,
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, DistributeProtocolFees<'info>>,
+) -> Result<()> {
+    constraints::token_2022::validate_liquidity_token_extensions(
+        &ctx.accounts.reserve_liquidity_mint.to_account_info(),
+        &ctx.accounts
+            .reserve_liquidity_fee_receiver
+            .to_account_info(),
+        &ctx.accounts.reserve.load()?.config.transfer_hook_program,
+    )?;
+
+    let clock = &Clock::get()?;
+
+    let reserve = &mut ctx.accounts.reserve.load_mut()?;
+    let lending_market = &ctx.accounts.lending_market.load()?;
+    let lending_market_key = ctx.accounts.lending_market.key();
+
+    if lending_market.active_fee_recipients().next().is_none() {
+        return err!(LendingError::FeeRecipientsNotConfigured);
+    }
+
+    let authority_signer_seeds =
+        gen_signer_seeds!(lending_market_key, lending_market.bump_seed as u8);
+
+    if reserve.calculate_redeem_fees() > 0 {
+        let withdraw_amount = lending_operations::redeem_fees(reserve, clock.slot)?;
+
+        msg!("Redeeming fees: {}", withdraw_amount);
+
+        token_transfer::withdraw_fees_from_reserve(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.reserve_liquidity_mint.to_account_info(),
+            ctx.accounts.reserve_supply_liquidity.to_account_info(),
+            ctx.accounts
+                .reserve_liquidity_fee_receiver
+                .to_account_info(),
+            ctx.accounts.lending_market_authority.to_account_info(),
+            authority_signer_seeds,
+            withdraw_amount,
+            ctx.accounts.reserve_liquidity_mint.decimals,
+            &[],
+        )?;
+    }
+
+    let fee_vault_balance = token_interface::accessor::amount(
+        &ctx.accounts
+            .reserve_liquidity_fee_receiver
+            .to_account_info(),
+    )?;
+
+    let mut recipient_token_accounts = ctx.remaining_accounts.iter();
+    for fee_recipient in lending_market.active_fee_recipients() {
+        let recipient_token_account_info = recipient_token_accounts
+            .next()
+            .ok_or(error!(LendingError::InvalidAccountInput))?;
+        let recipient_token_account =
+            InterfaceAccount::<TokenAccount>::try_from(recipient_token_account_info)?;
+
+        require_keys_eq!(
+            recipient_token_account.mint,
+            reserve.liquidity.mint_pubkey,
+            LendingError::InvalidAccountInput
+        );
+        require_keys_eq!(
+            recipient_token_account.owner,
+            fee_recipient.recipient,
+            LendingError::InvalidAccountInput
+        );
+
+        let amount = fee_recipient.share_of(fee_vault_balance);
+        if amount == 0 {
+            continue;
+        }
+
+        msg!(
+            "Distributing fees: {} to {}",
+            amount,
+            fee_recipient.recipient
+        );
+
+        token_transfer::withdraw_fees_from_reserve(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.reserve_liquidity_mint.to_account_info(),
+            ctx.accounts
+                .reserve_liquidity_fee_receiver
+                .to_account_info(),
+            recipient_token_account_info.clone(),
+            ctx.accounts.lending_market_authority.to_account_info(),
+            authority_signer_seeds,
+            amount,
+            ctx.accounts.reserve_liquidity_mint.decimals,
+            &[],
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DistributeProtocolFees<'info> {
+    #[account(mut,
+        has_one = lending_market)]
+    pub reserve: AccountLoader<'info, Reserve>,
+    #[account(
+        address = reserve.load()?.liquidity.mint_pubkey,
+        mint::token_program = token_program,
+    )]
+    pub reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut,
+        address = reserve.load()?.liquidity.fee_vault,
+        token::authority = lending_market_authority,
+    )]
+    pub reserve_liquidity_fee_receiver: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut,
+        address = reserve.load()?.liquidity.supply_vault,
+    )]
+    pub reserve_supply_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
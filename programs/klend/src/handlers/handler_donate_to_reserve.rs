@@ -0,0 +1,91 @@
+use anchor_lang::{prelude::*, Accounts};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    lending_market::{lending_checks, lending_operations},
+    state::{LendingMarket, Reserve},
+    utils::{constraints, token_transfer},
+    LendingAction,
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, DonateToReserve<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    constraints::token_2022::validate_liquidity_token_extensions(
+        &ctx.accounts.reserve_liquidity_mint.to_account_info(),
+        &ctx.accounts.reserve_liquidity_supply.to_account_info(),
+        &ctx.accounts.reserve.load()?.config.transfer_hook_program,
+    )?;
+
+    let clock = Clock::get()?;
+    let reserve = &mut ctx.accounts.reserve.load_mut()?;
+    let lending_market = &ctx.accounts.lending_market.load()?;
+
+    lending_operations::refresh_reserve(reserve, &clock, None, lending_market.referral_fee_bps)?;
+
+    let initial_reserve_token_balance = token_interface::accessor::amount(
+        &ctx.accounts.reserve_liquidity_supply.to_account_info(),
+    )?;
+    let initial_reserve_available_liquidity = reserve.liquidity.available_amount;
+
+    lending_operations::donate_to_reserve(reserve, &clock, liquidity_amount)?;
+
+    msg!(
+        "Donating to reserve {} liquidity {}",
+        ctx.accounts.reserve.key(),
+        liquidity_amount
+    );
+
+    token_transfer::deposit_initial_reserve_liquidity_transfer(
+        ctx.accounts.user_source_liquidity.to_account_info(),
+        ctx.accounts.reserve_liquidity_supply.to_account_info(),
+        ctx.accounts.owner.to_account_info(),
+        ctx.accounts.reserve_liquidity_mint.to_account_info(),
+        ctx.accounts.liquidity_token_program.to_account_info(),
+        liquidity_amount,
+        ctx.accounts.reserve_liquidity_mint.decimals,
+        ctx.remaining_accounts,
+    )?;
+
+    lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+        token_interface::accessor::amount(&ctx.accounts.reserve_liquidity_supply.to_account_info())
+            .unwrap(),
+        reserve.liquidity.available_amount,
+        initial_reserve_token_balance,
+        initial_reserve_available_liquidity,
+        LendingAction::Additive(liquidity_amount),
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DonateToReserve<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market
+    )]
+    pub reserve: AccountLoader<'info, Reserve>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+
+    #[account(
+        address = reserve.load()?.liquidity.mint_pubkey,
+        mint::token_program = liquidity_token_program,
+    )]
+    pub reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = reserve.load()?.liquidity.supply_vault,
+    )]
+    pub reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        token::mint = reserve_liquidity_supply.mint
+    )]
+    pub user_source_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub liquidity_token_program: Interface<'info, TokenInterface>,
+}
@@ -9,7 +9,10 @@ use crate::{
     LendingAction, ReferrerTokenState,
 };
 
-pub fn process(ctx: Context<FlashBorrowReserveLiquidity>, liquidity_amount: u64) -> Result<()> {
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, FlashBorrowReserveLiquidity<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
     lending_checks::flash_borrow_reserve_liquidity_checks(&ctx)?;
     let reserve = &mut ctx.accounts.reserve.load_mut()?;
     let lending_market = &ctx.accounts.lending_market.load()?;
@@ -42,6 +45,7 @@ pub fn process(ctx: Context<FlashBorrowReserveLiquidity>, liquidity_amount: u64)
         authority_signer_seeds,
         liquidity_amount,
         ctx.accounts.reserve_liquidity_mint.decimals,
+        ctx.remaining_accounts,
     )?;
 
     lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
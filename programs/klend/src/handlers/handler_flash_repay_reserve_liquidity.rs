@@ -9,8 +9,8 @@ use crate::{
     LendingAction, ReferrerTokenState,
 };
 
-pub fn process(
-    ctx: Context<FlashRepayReserveLiquidity>,
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, FlashRepayReserveLiquidity<'info>>,
     liquidity_amount: u64,
     borrow_instruction_index: u8,
 ) -> Result<()> {
@@ -70,6 +70,7 @@ pub fn process(
         ctx.accounts.user_transfer_authority.to_account_info(),
         flash_loan_amount_with_referrer_fee,
         ctx.accounts.reserve_liquidity_mint.decimals,
+        ctx.remaining_accounts,
     )?;
 
     if reserve_origination_fee > 0 {
@@ -83,6 +84,7 @@ pub fn process(
             ctx.accounts.user_transfer_authority.to_account_info(),
             reserve_origination_fee,
             ctx.accounts.reserve_liquidity_mint.decimals,
+            ctx.remaining_accounts,
         )?;
     }
 
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    lending_market::lending_operations, utils::FatAccountLoader, LendingError, LendingMarket,
+    Obligation, Reserve,
+};
+
+pub fn process(ctx: Context<GetBestElevationGroup>) -> Result<u8> {
+    let obligation = &ctx.accounts.obligation.load()?;
+    let lending_market = &ctx.accounts.lending_market.load()?;
+    let slot = Clock::get()?.slot;
+    let deposit_count = obligation.deposits_count();
+    let borrow_count = obligation.borrows_count();
+
+    if ctx.remaining_accounts.iter().len() != deposit_count + borrow_count {
+        return err!(LendingError::InvalidAccountInput);
+    }
+
+    let deposit_reserves_iter = ctx
+        .remaining_accounts
+        .iter()
+        .take(deposit_count)
+        .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap());
+
+    let borrow_reserves_iter = ctx
+        .remaining_accounts
+        .iter()
+        .skip(deposit_count)
+        .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap());
+
+    lending_operations::find_best_elevation_group(
+        obligation,
+        lending_market,
+        slot,
+        deposit_reserves_iter,
+        borrow_reserves_iter,
+    )
+}
+
+#[derive(Accounts)]
+pub struct GetBestElevationGroup<'info> {
+    #[account(has_one = lending_market)]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+}
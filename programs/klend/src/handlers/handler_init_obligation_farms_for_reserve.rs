@@ -30,6 +30,9 @@ pub fn process(ctx: Context<InitObligationFarmsForReserve>, mode: u8) -> Result<
 
     farms_ixs::cpi_initialize_farmer_delegated(&ctx, obligation, farm)?;
 
+    let obligation = &mut ctx.accounts.obligation.load_mut()?;
+    obligation.add_farm_reserve(ctx.accounts.reserve.key())?;
+
     Ok(())
 }
 
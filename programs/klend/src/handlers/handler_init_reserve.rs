@@ -19,9 +19,14 @@ use crate::{
 pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, InitReserve<'info>>) -> Result<()> {
     let clock = &Clock::get()?;
     let reserve = &mut ctx.accounts.reserve.load_init()?;
+    let mint_transfer_hook_program = constraints::token_2022::get_transfer_hook_program_id(
+        &ctx.accounts.reserve_liquidity_mint.to_account_info(),
+    )?
+    .unwrap_or_default();
     constraints::token_2022::validate_liquidity_token_extensions(
         &ctx.accounts.reserve_liquidity_mint.to_account_info(),
         &ctx.accounts.reserve_liquidity_supply.to_account_info(),
+        &mint_transfer_hook_program,
     )?;
 
     let market = &ctx.accounts.lending_market.load()?;
@@ -57,6 +62,7 @@ pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, InitReserve<'info>>) -> Re
         ctx.accounts.liquidity_token_program.to_account_info(),
         market.min_initial_deposit_amount,
         ctx.accounts.reserve_liquidity_mint.decimals,
+        ctx.remaining_accounts,
     )?;
 
     Ok(())
@@ -6,7 +6,6 @@ use anchor_lang::{
 use anchor_spl::{
     token,
     token::Token,
-    token_interface,
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
@@ -17,11 +16,11 @@ use crate::{
     refresh_farms,
     state::{obligation::Obligation, LendingMarket, RedeemReserveCollateralAccounts, Reserve},
     utils::{seeds, token_transfer, FatAccountLoader},
-    xmsg, LendingAction, LiquidateAndRedeemResult, ReserveFarmKind,
+    xmsg, LendingAction, LendingError, LiquidateAndRedeemResult, ReserveFarmKind,
 };
 
-pub fn process_v1(
-    ctx: Context<LiquidateObligationAndRedeemReserveCollateral>,
+pub fn process_v1<'info>(
+    ctx: Context<'_, '_, '_, 'info, LiquidateObligationAndRedeemReserveCollateral<'info>>,
     liquidity_amount: u64,
     min_acceptable_received_liquidity_amount: u64,
     max_allowed_ltv_override_percent: u64,
@@ -42,8 +41,8 @@ pub fn process_v1(
     )
 }
 
-pub fn process_v2(
-    ctx: Context<LiquidateObligationAndRedeemReserveCollateralV2>,
+pub fn process_v2<'info>(
+    ctx: Context<'_, '_, '_, 'info, LiquidateObligationAndRedeemReserveCollateralV2<'info>>,
     liquidity_amount: u64,
     min_acceptable_received_liquidity_amount: u64,
     max_allowed_ltv_override_percent: u64,
@@ -73,9 +72,9 @@ pub fn process_v2(
     Ok(())
 }
 
-fn process_impl(
-    accounts: &LiquidateObligationAndRedeemReserveCollateral,
-    remaining_accounts: &[AccountInfo],
+fn process_impl<'info>(
+    accounts: &LiquidateObligationAndRedeemReserveCollateral<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
     liquidity_amount: u64,
     min_acceptable_received_liquidity_amount: u64,
     max_allowed_ltv_override_percent: u64,
@@ -106,6 +105,16 @@ fn process_impl(
     let lending_market_key = accounts.lending_market.key();
     let clock = &Clock::get()?;
 
+    let deposits_count = obligation.deposits_count();
+    if remaining_accounts.len() < deposits_count {
+        msg!(
+            "Expected at least {} deposit reserves in remaining accounts",
+            deposits_count
+        );
+        return err!(LendingError::InvalidAccountInput);
+    }
+    let (deposit_reserves, transfer_hook_accounts) = remaining_accounts.split_at(deposits_count);
+
     let max_allowed_ltv_override_pct_opt =
         if accounts.liquidator.key() == obligation.owner && max_allowed_ltv_override_percent > 0 {
             if cfg!(feature = "staging") {
@@ -148,7 +157,7 @@ fn process_impl(
         liquidity_amount,
         min_acceptable_received_liquidity_amount,
         max_allowed_ltv_override_pct_opt,
-        remaining_accounts.iter().map(|a| {
+        deposit_reserves.iter().map(|a| {
             FatAccountLoader::try_from(a).expect("Remaining account is not a valid deposit reserve")
         }),
     )?;
@@ -161,6 +170,7 @@ fn process_impl(
         accounts.liquidator.to_account_info(),
         repay_amount,
         accounts.repay_reserve_liquidity_mint.decimals,
+        transfer_hook_accounts,
     )?;
 
     token_transfer::withdraw_obligation_collateral_transfer(
@@ -189,20 +199,19 @@ fn process_impl(
             withdraw_collateral_amount,
             withdraw_liquidity_amount,
             accounts.withdraw_reserve_liquidity_mint.decimals,
+            transfer_hook_accounts,
         )?;
 
-        token_interface::transfer_checked(
-            CpiContext::new(
-                accounts.withdraw_liquidity_token_program.to_account_info(),
-                token_interface::TransferChecked {
-                    from: accounts.user_destination_liquidity.to_account_info(),
-                    to: accounts
-                        .withdraw_reserve_liquidity_fee_receiver
-                        .to_account_info(),
-                    authority: accounts.liquidator.to_account_info(),
-                    mint: accounts.withdraw_reserve_liquidity_mint.to_account_info(),
-                },
-            ),
+        token_transfer::transfer_checked(
+            accounts.withdraw_liquidity_token_program.to_account_info(),
+            accounts.user_destination_liquidity.to_account_info(),
+            accounts.withdraw_reserve_liquidity_mint.to_account_info(),
+            accounts
+                .withdraw_reserve_liquidity_fee_receiver
+                .to_account_info(),
+            accounts.liquidator.to_account_info(),
+            &[],
+            transfer_hook_accounts,
             protocol_fee,
             accounts.withdraw_reserve_liquidity_mint.decimals,
         )?;
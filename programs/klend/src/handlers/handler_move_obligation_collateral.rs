@@ -0,0 +1,95 @@
+use anchor_lang::{prelude::*, Accounts};
+use farms::program::Farms;
+
+use crate::{
+    handler_refresh_obligation_farms_for_reserve::*,
+    lending_market::lending_operations,
+    state::{obligation::Obligation, LendingMarket, Reserve},
+    utils::{cpi_refresh_farms, seeds},
+    LendingError, ReserveFarmKind,
+};
+
+pub fn process(ctx: Context<MoveObligationCollateral>, collateral_amount: u64) -> Result<()> {
+    {
+        let clock = &Clock::get()?;
+        let reserve = &mut ctx.accounts.reserve.load_mut()?;
+        let source_obligation = &mut ctx.accounts.source_obligation.load_mut()?;
+        let destination_obligation = &mut ctx.accounts.destination_obligation.load_mut()?;
+        let lending_market = &ctx.accounts.lending_market.load()?;
+
+        let move_amount = lending_operations::move_obligation_collateral(
+            lending_market,
+            reserve,
+            source_obligation,
+            destination_obligation,
+            collateral_amount,
+            clock.slot,
+            ctx.accounts.reserve.key(),
+        )?;
+
+        msg!(
+            "Moved collateral {} from obligation {} to obligation {}",
+            move_amount,
+            ctx.accounts.source_obligation.key(),
+            ctx.accounts.destination_obligation.key()
+        );
+    }
+
+    for (obligation, farms_accounts) in [
+        (
+            &ctx.accounts.source_obligation,
+            &ctx.accounts.source_farms_accounts,
+        ),
+        (
+            &ctx.accounts.destination_obligation,
+            &ctx.accounts.destination_farms_accounts,
+        ),
+    ] {
+        cpi_refresh_farms::refresh_obligation_farms_for_reserve(
+            cpi_refresh_farms::RefreshFarmsParams {
+                reserve: &ctx.accounts.reserve,
+                farms_accounts,
+                farm_kind: ReserveFarmKind::Collateral,
+            },
+            obligation,
+            &ctx.accounts.lending_market_authority,
+            &ctx.accounts.lending_market,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MoveObligationCollateral<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner
+    )]
+    pub source_obligation: AccountLoader<'info, Obligation>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner,
+        constraint = destination_obligation.key() != source_obligation.key() @ LendingError::InvalidAccountInput
+    )]
+    pub destination_obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = lending_market
+    )]
+    pub reserve: AccountLoader<'info, Reserve>,
+
+    pub source_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub destination_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub farms_program: Program<'info, Farms>,
+}
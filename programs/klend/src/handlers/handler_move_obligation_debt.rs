@@ -0,0 +1,115 @@
+use anchor_lang::{prelude::*, Accounts};
+use farms::program::Farms;
+
+use crate::{
+    fraction::FractionExtra,
+    handler_refresh_obligation_farms_for_reserve::*,
+    lending_market::lending_operations,
+    state::{obligation::Obligation, LendingMarket, Reserve},
+    utils::{cpi_refresh_farms, seeds, FatAccountLoader},
+    LendingError, ReserveFarmKind,
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, MoveObligationDebt<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    {
+        let clock = &Clock::get()?;
+        let borrow_reserve = &mut ctx.accounts.borrow_reserve.load_mut()?;
+        let source_obligation = &mut ctx.accounts.source_obligation.load_mut()?;
+        let destination_obligation = &mut ctx.accounts.destination_obligation.load_mut()?;
+        let lending_market = &ctx.accounts.lending_market.load()?;
+
+        let source_deposits_count = source_obligation.deposits_count();
+        if ctx.remaining_accounts.len()
+            != source_deposits_count + destination_obligation.deposits_count()
+        {
+            msg!("Deposit reserves of both obligations must be provided in the remaining accounts");
+            return err!(LendingError::InvalidAccountInput);
+        }
+        let (source_deposit_reserves, destination_deposit_reserves) =
+            ctx.remaining_accounts.split_at(source_deposits_count);
+
+        let move_amount_f = lending_operations::move_obligation_debt(
+            lending_market,
+            borrow_reserve,
+            source_obligation,
+            destination_obligation,
+            liquidity_amount,
+            clock.slot,
+            ctx.accounts.borrow_reserve.key(),
+            source_deposit_reserves
+                .iter()
+                .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap()),
+            destination_deposit_reserves
+                .iter()
+                .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap()),
+        )?;
+
+        msg!(
+            "Moved debt {} from obligation {} to obligation {}",
+            move_amount_f.to_display(),
+            ctx.accounts.source_obligation.key(),
+            ctx.accounts.destination_obligation.key()
+        );
+    }
+
+    for (obligation, farms_accounts) in [
+        (
+            &ctx.accounts.source_obligation,
+            &ctx.accounts.source_farms_accounts,
+        ),
+        (
+            &ctx.accounts.destination_obligation,
+            &ctx.accounts.destination_farms_accounts,
+        ),
+    ] {
+        cpi_refresh_farms::refresh_obligation_farms_for_reserve(
+            cpi_refresh_farms::RefreshFarmsParams {
+                reserve: &ctx.accounts.borrow_reserve,
+                farms_accounts,
+                farm_kind: ReserveFarmKind::Debt,
+            },
+            obligation,
+            &ctx.accounts.lending_market_authority,
+            &ctx.accounts.lending_market,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MoveObligationDebt<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner
+    )]
+    pub source_obligation: AccountLoader<'info, Obligation>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner,
+        constraint = destination_obligation.key() != source_obligation.key() @ LendingError::InvalidAccountInput
+    )]
+    pub destination_obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    #[account(mut,
+        has_one = lending_market
+    )]
+    pub borrow_reserve: AccountLoader<'info, Reserve>,
+
+    pub source_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub destination_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub farms_program: Program<'info, Farms>,
+}
@@ -0,0 +1,35 @@
+use anchor_lang::{prelude::*, Accounts};
+
+use crate::{
+    state::{obligation::Obligation, LendingMarket},
+    LendingError,
+};
+
+pub fn process(ctx: Context<ProposeObligationOwner>, new_owner: Pubkey) -> Result<()> {
+    let obligation = &mut ctx.accounts.obligation.load_mut()?;
+
+    if new_owner == obligation.owner {
+        msg!("New obligation owner must be different from the current one");
+        return err!(LendingError::InvalidConfig);
+    }
+
+    msg!("Prv pending owner {}", obligation.pending_owner);
+    msg!("New pending owner {}", new_owner);
+
+    obligation.pending_owner = new_owner;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ProposeObligationOwner<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+}
@@ -8,10 +8,11 @@ use crate::{
     utils::{constraints, seeds, token_transfer},
 };
 
-pub fn process(ctx: Context<RedeemFees>) -> Result<()> {
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, RedeemFees<'info>>) -> Result<()> {
     constraints::token_2022::validate_liquidity_token_extensions(
         &ctx.accounts.reserve_liquidity_mint.to_account_info(),
         &ctx.accounts.reserve_supply_liquidity.to_account_info(),
+        &ctx.accounts.reserve.load()?.config.transfer_hook_program,
     )?;
 
     let clock = &Clock::get()?;
@@ -38,6 +39,7 @@ pub fn process(ctx: Context<RedeemFees>) -> Result<()> {
         authority_signer_seeds,
         withdraw_amount,
         ctx.accounts.reserve_liquidity_mint.decimals,
+        ctx.remaining_accounts,
     )?;
 
     Ok(())
@@ -13,40 +13,69 @@ use crate::{
     lending_market::{lending_checks, lending_operations},
     state::{LendingMarket, RedeemReserveCollateralAccounts, Reserve},
     utils::{seeds, token_transfer},
-    LendingAction,
+    LendingAction, ReferrerTokenState, UserMetadata,
 };
 
-pub fn process(ctx: Context<RedeemReserveCollateral>, collateral_amount: u64) -> Result<()> {
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemReserveCollateral<'info>>,
+    collateral_amount: u64,
+) -> Result<()> {
+    process_impl(ctx.accounts, collateral_amount, ctx.remaining_accounts)
+}
+
+pub(super) fn process_impl<'info>(
+    accounts: &RedeemReserveCollateral<'info>,
+    collateral_amount: u64,
+    transfer_hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
     lending_checks::redeem_reserve_collateral_checks(&RedeemReserveCollateralAccounts {
-        user_source_collateral: ctx.accounts.user_source_collateral.clone(),
-        user_destination_liquidity: ctx.accounts.user_destination_liquidity.clone(),
-        reserve: ctx.accounts.reserve.clone(),
-        reserve_liquidity_mint: ctx.accounts.reserve_liquidity_mint.clone(),
-        reserve_collateral_mint: ctx.accounts.reserve_collateral_mint.clone(),
-        reserve_liquidity_supply: ctx.accounts.reserve_liquidity_supply.clone(),
-        lending_market: ctx.accounts.lending_market.clone(),
-        lending_market_authority: ctx.accounts.lending_market_authority.clone(),
-        owner: ctx.accounts.owner.clone(),
-        collateral_token_program: ctx.accounts.collateral_token_program.clone(),
-        liquidity_token_program: ctx.accounts.liquidity_token_program.clone(),
+        user_source_collateral: accounts.user_source_collateral.clone(),
+        user_destination_liquidity: accounts.user_destination_liquidity.clone(),
+        reserve: accounts.reserve.clone(),
+        reserve_liquidity_mint: accounts.reserve_liquidity_mint.clone(),
+        reserve_collateral_mint: accounts.reserve_collateral_mint.clone(),
+        reserve_liquidity_supply: accounts.reserve_liquidity_supply.clone(),
+        lending_market: accounts.lending_market.clone(),
+        lending_market_authority: accounts.lending_market_authority.clone(),
+        owner: accounts.owner.clone(),
+        collateral_token_program: accounts.collateral_token_program.clone(),
+        liquidity_token_program: accounts.liquidity_token_program.clone(),
     })?;
 
-    let reserve = &mut ctx.accounts.reserve.load_mut()?;
-    let lending_market = &ctx.accounts.lending_market.load()?;
+    let reserve = &mut accounts.reserve.load_mut()?;
+    let lending_market = &accounts.lending_market.load()?;
     let clock = Clock::get()?;
 
-    let lending_market_key = ctx.accounts.lending_market.key();
+    let lending_market_key = accounts.lending_market.key();
     let authority_signer_seeds =
         gen_signer_seeds!(lending_market_key.as_ref(), lending_market.bump_seed as u8);
 
-    let initial_reserve_token_balance = token_interface::accessor::amount(
-        &ctx.accounts.reserve_liquidity_supply.to_account_info(),
-    )?;
+    let initial_reserve_token_balance =
+        token_interface::accessor::amount(&accounts.reserve_liquidity_supply.to_account_info())?;
     let initial_reserve_available_liquidity = reserve.liquidity.available_amount;
 
     lending_operations::refresh_reserve(reserve, &clock, None, lending_market.referral_fee_bps)?;
-    let withdraw_liquidity_amount =
-        lending_operations::redeem_reserve_collateral(reserve, collateral_amount, &clock, true)?;
+
+    let referrer = match &accounts.owner_user_metadata {
+        Some(owner_user_metadata) => owner_user_metadata.load()?.referrer,
+        None => Pubkey::default(),
+    };
+    let referrer_token_state = lending_checks::supply_fee_referrer_token_state(
+        &accounts.referrer_token_state,
+        referrer,
+        reserve.config.supply_fees.has_withdrawal_fee(),
+        reserve.liquidity.mint_pubkey,
+        accounts.reserve.key(),
+    )?;
+
+    let withdraw_liquidity_amount = lending_operations::redeem_reserve_collateral(
+        reserve,
+        collateral_amount,
+        &clock,
+        true,
+        lending_market.referral_fee_bps,
+        referrer_token_state,
+    )?;
 
     msg!(
         "pnl: Redeeming reserve collateral {}",
@@ -54,23 +83,24 @@ pub fn process(ctx: Context<RedeemReserveCollateral>, collateral_amount: u64) ->
     );
 
     token_transfer::redeem_reserve_collateral_transfer(
-        ctx.accounts.collateral_token_program.to_account_info(),
-        ctx.accounts.liquidity_token_program.to_account_info(),
-        ctx.accounts.reserve_liquidity_mint.to_account_info(),
-        ctx.accounts.reserve_collateral_mint.to_account_info(),
-        ctx.accounts.user_source_collateral.to_account_info(),
-        ctx.accounts.owner.to_account_info(),
-        ctx.accounts.reserve_liquidity_supply.to_account_info(),
-        ctx.accounts.user_destination_liquidity.to_account_info(),
-        ctx.accounts.lending_market_authority.clone(),
+        accounts.collateral_token_program.to_account_info(),
+        accounts.liquidity_token_program.to_account_info(),
+        accounts.reserve_liquidity_mint.to_account_info(),
+        accounts.reserve_collateral_mint.to_account_info(),
+        accounts.user_source_collateral.to_account_info(),
+        accounts.owner.to_account_info(),
+        accounts.reserve_liquidity_supply.to_account_info(),
+        accounts.user_destination_liquidity.to_account_info(),
+        accounts.lending_market_authority.clone(),
         authority_signer_seeds,
         collateral_amount,
         withdraw_liquidity_amount,
-        ctx.accounts.reserve_liquidity_mint.decimals,
+        accounts.reserve_liquidity_mint.decimals,
+        transfer_hook_accounts,
     )?;
 
     lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
-        token_interface::accessor::amount(&ctx.accounts.reserve_liquidity_supply.to_account_info())
+        token_interface::accessor::amount(&accounts.reserve_liquidity_supply.to_account_info())
             .unwrap(),
         reserve.liquidity.available_amount,
         initial_reserve_token_balance,
@@ -121,6 +151,15 @@ pub struct RedeemReserveCollateral<'info> {
     )]
     pub user_destination_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    #[account(
+        seeds = [seeds::BASE_SEED_USER_METADATA, owner.key().as_ref()],
+        bump = owner_user_metadata.load()?.bump.try_into().unwrap(),
+    )]
+    pub owner_user_metadata: Option<AccountLoader<'info, UserMetadata>>,
+
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
     pub collateral_token_program: Program<'info, Token>,
     pub liquidity_token_program: Interface<'info, TokenInterface>,
 
@@ -0,0 +1,334 @@
+use std::cell::RefMut;
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program,
+    },
+    Accounts,
+};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use lending_checks::validate_referrer_token_state;
+
+use super::handler_refresh_obligation_farms_for_reserve::*;
+use crate::{
+    gen_signer_seeds,
+    lending_market::{lending_checks, lending_operations},
+    refresh_farms,
+    state::{obligation::Obligation, CalculateBorrowResult, LendingMarket, Reserve},
+    utils::{seeds, token_transfer, FatAccountLoader},
+    xmsg, LendingAction, LendingError, ReferrerTokenState, ReserveFarmKind,
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefinanceDebt<'info>>,
+    borrow_liquidity_amount: u64,
+    min_repay_liquidity_amount: u64,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let clock = Clock::get()?;
+    let lending_market_key = accounts.lending_market.key();
+
+    let bump_seed = {
+        let lending_market = &accounts.lending_market.load()?;
+        if lending_market.swap_adapter_program == Pubkey::default()
+            || lending_market.swap_adapter_program != accounts.swap_adapter_program.key()
+        {
+            return err!(LendingError::SwapAdapterNotWhitelisted);
+        }
+        lending_market.bump_seed as u8
+    };
+    let authority_signer_seeds = gen_signer_seeds!(lending_market_key, bump_seed);
+
+    let deposits_count = accounts.obligation.load()?.deposits_count();
+    if ctx.remaining_accounts.len() < deposits_count {
+        msg!(
+            "Expected at least {} deposit reserves in remaining accounts",
+            deposits_count
+        );
+        return err!(LendingError::InvalidAccountInput);
+    }
+    // The swap accounts also carry the transfer hook extra accounts, token-2022 resolves them by key.
+    let (deposit_reserves, swap_accounts) = ctx.remaining_accounts.split_at(deposits_count);
+
+    let borrow_amount_f = {
+        let borrow_reserve = &mut accounts.borrow_reserve.load_mut()?;
+        let lending_market = &accounts.lending_market.load()?;
+        let obligation = &mut accounts.obligation.load_mut()?;
+
+        let referrer_token_state_option: Option<RefMut<ReferrerTokenState>> =
+            if obligation.has_referrer() {
+                match &accounts.referrer_token_state {
+                    Some(referrer_token_state_loader) => {
+                        let referrer_token_state = referrer_token_state_loader.load_mut()?;
+
+                        validate_referrer_token_state(
+                            &crate::ID,
+                            &referrer_token_state,
+                            referrer_token_state_loader.key(),
+                            borrow_reserve.liquidity.mint_pubkey,
+                            obligation.referrer,
+                            accounts.borrow_reserve.key(),
+                        )?;
+
+                        Some(referrer_token_state)
+                    }
+                    None => return err!(LendingError::ReferrerAccountMissing),
+                }
+            } else {
+                None
+            };
+
+        let initial_reserve_token_balance = token_interface::accessor::amount(
+            &accounts.borrow_reserve_liquidity_supply.to_account_info(),
+        )?;
+        let initial_reserve_available_liquidity = borrow_reserve.liquidity.available_amount;
+
+        let CalculateBorrowResult {
+            borrow_amount_f,
+            receive_amount,
+            borrow_fee,
+            ..
+        } = lending_operations::borrow_obligation_liquidity_for_refinance(
+            lending_market,
+            borrow_reserve,
+            obligation,
+            borrow_liquidity_amount,
+            &clock,
+            accounts.borrow_reserve.key(),
+            accounts.repay_reserve.key(),
+            referrer_token_state_option,
+            deposit_reserves
+                .iter()
+                .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap()),
+        )?;
+
+        xmsg!("pnl: Refinance borrow obligation liquidity {receive_amount} with borrow_fee {borrow_fee}",);
+
+        if borrow_fee > 0 {
+            token_transfer::send_origination_fees_transfer(
+                accounts.borrow_liquidity_token_program.to_account_info(),
+                accounts.borrow_reserve_liquidity_mint.to_account_info(),
+                accounts.borrow_reserve_liquidity_supply.to_account_info(),
+                accounts
+                    .borrow_reserve_liquidity_fee_receiver
+                    .to_account_info(),
+                accounts.lending_market_authority.to_account_info(),
+                authority_signer_seeds,
+                borrow_fee,
+                accounts.borrow_reserve_liquidity_mint.decimals,
+                swap_accounts,
+            )?;
+        }
+
+        token_transfer::borrow_obligation_liquidity_transfer(
+            accounts.borrow_liquidity_token_program.to_account_info(),
+            accounts.borrow_reserve_liquidity_mint.to_account_info(),
+            accounts.borrow_reserve_liquidity_supply.to_account_info(),
+            accounts.user_source_liquidity.to_account_info(),
+            accounts.lending_market_authority.to_account_info(),
+            authority_signer_seeds,
+            receive_amount,
+            accounts.borrow_reserve_liquidity_mint.decimals,
+            swap_accounts,
+        )?;
+
+        lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+            token_interface::accessor::amount(
+                &accounts.borrow_reserve_liquidity_supply.to_account_info(),
+            )
+            .unwrap(),
+            borrow_reserve.liquidity.available_amount,
+            initial_reserve_token_balance,
+            initial_reserve_available_liquidity,
+            LendingAction::Subtractive(borrow_fee + receive_amount),
+        )?;
+
+        borrow_amount_f
+    };
+
+    let initial_user_destination_balance =
+        token_interface::accessor::amount(&accounts.user_destination_liquidity.to_account_info())?;
+
+    let swap_instruction = Instruction {
+        program_id: accounts.swap_adapter_program.key(),
+        accounts: swap_accounts
+            .iter()
+            .map(|account_info| AccountMeta {
+                pubkey: account_info.key(),
+                is_signer: account_info.is_signer,
+                is_writable: account_info.is_writable,
+            })
+            .collect(),
+        data: swap_data,
+    };
+    program::invoke(&swap_instruction, swap_accounts)?;
+
+    let repay_liquidity_amount =
+        token_interface::accessor::amount(&accounts.user_destination_liquidity.to_account_info())?
+            .checked_sub(initial_user_destination_balance)
+            .ok_or_else(|| error!(LendingError::RefinanceDebtSlippageExceeded))?;
+
+    if repay_liquidity_amount < min_repay_liquidity_amount {
+        msg!(
+            "Swap output {} is lower than the minimum accepted {}",
+            repay_liquidity_amount,
+            min_repay_liquidity_amount
+        );
+        return err!(LendingError::RefinanceDebtSlippageExceeded);
+    }
+
+    let repay_liquidity_amount = token_transfer::get_amount_after_transfer_fee(
+        &accounts.repay_reserve_liquidity_mint.to_account_info(),
+        repay_liquidity_amount,
+    )?;
+
+    {
+        let repay_reserve = &mut accounts.repay_reserve.load_mut()?;
+        let borrow_reserve = &accounts.borrow_reserve.load()?;
+        let lending_market = &accounts.lending_market.load()?;
+        let obligation = &mut accounts.obligation.load_mut()?;
+
+        let initial_reserve_token_balance = token_interface::accessor::amount(
+            &accounts.repay_reserve_liquidity_supply.to_account_info(),
+        )?;
+        let initial_reserve_available_liquidity = repay_reserve.liquidity.available_amount;
+
+        let repay_amount = lending_operations::repay_obligation_liquidity_for_refinance(
+            lending_market,
+            repay_reserve,
+            borrow_reserve,
+            obligation,
+            &clock,
+            repay_liquidity_amount,
+            accounts.repay_reserve.key(),
+            accounts.borrow_reserve.key(),
+            borrow_amount_f,
+            deposit_reserves
+                .iter()
+                .map(|account_info| FatAccountLoader::<Reserve>::try_from(account_info).unwrap()),
+        )?;
+
+        xmsg!("pnl: Refinance repay obligation liquidity {repay_amount} swap output {repay_liquidity_amount}",);
+
+        token_transfer::repay_obligation_liquidity_transfer(
+            accounts.repay_liquidity_token_program.to_account_info(),
+            accounts.repay_reserve_liquidity_mint.to_account_info(),
+            accounts.user_destination_liquidity.to_account_info(),
+            accounts.repay_reserve_liquidity_supply.to_account_info(),
+            accounts.owner.to_account_info(),
+            repay_amount,
+            accounts.repay_reserve_liquidity_mint.decimals,
+            swap_accounts,
+        )?;
+
+        lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+            token_interface::accessor::amount(
+                &accounts.repay_reserve_liquidity_supply.to_account_info(),
+            )
+            .unwrap(),
+            repay_reserve.liquidity.available_amount,
+            initial_reserve_token_balance,
+            initial_reserve_available_liquidity,
+            LendingAction::Additive(repay_amount),
+        )?;
+    }
+
+    refresh_farms!(
+        ctx.accounts,
+        [
+            (
+                ctx.accounts.borrow_reserve,
+                ctx.accounts.borrow_farms_accounts,
+                Debt,
+            ),
+            (
+                ctx.accounts.repay_reserve,
+                ctx.accounts.repay_farms_accounts,
+                Debt,
+            ),
+        ],
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RefinanceDebt<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    #[account(mut, has_one = lending_market)]
+    pub borrow_reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = borrow_reserve.load()?.liquidity.mint_pubkey,
+        mint::token_program = borrow_liquidity_token_program,
+    )]
+    pub borrow_reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = borrow_reserve.load()?.liquidity.supply_vault
+    )]
+    pub borrow_reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        address = borrow_reserve.load()?.liquidity.fee_vault
+    )]
+    pub borrow_reserve_liquidity_fee_receiver: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        has_one = lending_market,
+        constraint = repay_reserve.key() != borrow_reserve.key() @ LendingError::InvalidAccountInput
+    )]
+    pub repay_reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = repay_reserve.load()?.liquidity.mint_pubkey,
+        mint::token_program = repay_liquidity_token_program,
+    )]
+    pub repay_reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = repay_reserve.load()?.liquidity.supply_vault
+    )]
+    pub repay_reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        token::mint = borrow_reserve.load()?.liquidity.mint_pubkey,
+        token::authority = owner,
+    )]
+    pub user_source_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        token::mint = repay_reserve.load()?.liquidity.mint_pubkey,
+        token::authority = owner,
+    )]
+    pub user_destination_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
+    pub swap_adapter_program: AccountInfo<'info>,
+
+    pub borrow_liquidity_token_program: Interface<'info, TokenInterface>,
+    pub repay_liquidity_token_program: Interface<'info, TokenInterface>,
+
+    pub borrow_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub repay_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub farms_program: Program<'info, farms::program::Farms>,
+}
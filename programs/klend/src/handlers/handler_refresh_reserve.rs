@@ -4,7 +4,10 @@ use crate::{
     fraction::FractionExtra,
     lending_market::lending_operations,
     state::Reserve,
-    utils::{constraints, prices::get_price, PROGRAM_VERSION},
+    utils::{
+        prices::{get_price, PriceAccounts},
+        PROGRAM_VERSION,
+    },
     LendingError, LendingMarket,
 };
 
@@ -13,7 +16,13 @@ pub fn process(ctx: Context<RefreshReserve>) -> Result<()> {
     let reserve = &mut ctx.accounts.reserve.load_mut()?;
     let lending_market = &ctx.accounts.lending_market.load()?;
 
-    constraints::check_remaining_accounts(&ctx)?;
+    let mut oracle_slot_accounts = ctx.remaining_accounts.iter();
+    let oracle_slot_price = oracle_slot_accounts.next();
+    let oracle_slot_secondary = oracle_slot_accounts.next();
+    require!(
+        oracle_slot_accounts.next().is_none(),
+        LendingError::InvalidAccountInput
+    );
 
     require!(
         reserve.version == PROGRAM_VERSION as u64,
@@ -25,19 +34,25 @@ pub fn process(ctx: Context<RefreshReserve>) -> Result<()> {
         lending_market,
         clock.unix_timestamp,
     ) {
-        reserve.config.token_info.validate_token_info_config(
-            ctx.accounts.pyth_oracle.as_ref(),
-            ctx.accounts.switchboard_price_oracle.as_ref(),
-            ctx.accounts.switchboard_twap_oracle.as_ref(),
-            ctx.accounts.scope_prices.as_ref(),
-        )?;
+        let price_accounts = PriceAccounts {
+            pyth_price: ctx.accounts.pyth_oracle.as_ref(),
+            switchboard_price: ctx.accounts.switchboard_price_oracle.as_ref(),
+            switchboard_twap: ctx.accounts.switchboard_twap_oracle.as_ref(),
+            scope_prices: ctx.accounts.scope_prices.as_ref(),
+            oracle_slot_price,
+            oracle_slot_secondary,
+            lending_market: reserve.lending_market,
+        };
+
+        reserve
+            .config
+            .token_info
+            .validate_token_info_config(&price_accounts)?;
 
         get_price(
             &reserve.config.token_info,
-            ctx.accounts.pyth_oracle.as_ref(),
-            ctx.accounts.switchboard_price_oracle.as_ref(),
-            ctx.accounts.switchboard_twap_oracle.as_ref(),
-            ctx.accounts.scope_prices.as_ref(),
+            &price_accounts,
+            reserve.get_trusted_price_ema(u64::try_from(clock.unix_timestamp).unwrap()),
             clock,
         )?
     } else {
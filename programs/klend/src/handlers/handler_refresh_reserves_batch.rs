@@ -4,7 +4,10 @@ use crate::{
     fraction::FractionExtra,
     lending_market::lending_operations,
     state::Reserve,
-    utils::{prices::get_price, FatAccountLoader, PROGRAM_VERSION},
+    utils::{
+        prices::{get_price, PriceAccounts},
+        FatAccountLoader, PROGRAM_VERSION,
+    },
     LendingError, LendingMarket,
 };
 
@@ -63,25 +66,40 @@ pub fn process(ctx: Context<RefreshReservesBatch>, skip_price_updates: bool) ->
             let switchboard_price_oracle = maybe_price_account(remaining_accounts_it.next())?;
             let switchboard_twap_oracle = maybe_price_account(remaining_accounts_it.next())?;
             let scope_prices = maybe_price_account(remaining_accounts_it.next())?;
+            let (oracle_slot_price, oracle_slot_secondary) =
+                if reserve.config.token_info.oracle_slot.is_enabled() {
+                    (
+                        maybe_price_account(remaining_accounts_it.next())?,
+                        maybe_price_account(remaining_accounts_it.next())?,
+                    )
+                } else {
+                    (None, None)
+                };
 
             if lending_operations::is_price_refresh_needed(
                 reserve,
                 lending_market,
                 clock.unix_timestamp,
             ) {
-                reserve.config.token_info.validate_token_info_config(
-                    pyth_oracle,
-                    switchboard_price_oracle,
-                    switchboard_twap_oracle,
+                let price_accounts = PriceAccounts {
+                    pyth_price: pyth_oracle,
+                    switchboard_price: switchboard_price_oracle,
+                    switchboard_twap: switchboard_twap_oracle,
                     scope_prices,
-                )?;
+                    oracle_slot_price,
+                    oracle_slot_secondary,
+                    lending_market: reserve.lending_market,
+                };
+
+                reserve
+                    .config
+                    .token_info
+                    .validate_token_info_config(&price_accounts)?;
 
                 get_price(
                     &reserve.config.token_info,
-                    pyth_oracle,
-                    switchboard_price_oracle,
-                    switchboard_twap_oracle,
-                    scope_prices,
+                    &price_accounts,
+                    reserve.get_trusted_price_ema(u64::try_from(clock.unix_timestamp).unwrap()),
                     clock,
                 )?
             } else {
@@ -0,0 +1,55 @@
+use anchor_lang::{prelude::*, Accounts};
+use anchor_spl::token_interface::{self, TokenAccount};
+
+use crate::{
+    lending_market::{lending_checks, lending_operations},
+    state::{LendingMarket, Reserve},
+    LendingAction,
+};
+
+pub fn process(ctx: Context<ReinvestProtocolFees>, amount: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let reserve = &mut ctx.accounts.reserve.load_mut()?;
+
+    let initial_reserve_token_balance = token_interface::accessor::amount(
+        &ctx.accounts.reserve_liquidity_supply.to_account_info(),
+    )?;
+    let initial_reserve_available_liquidity = reserve.liquidity.available_amount;
+
+    let reinvest_amount = lending_operations::reinvest_protocol_fees(reserve, clock.slot, amount)?;
+
+    msg!(
+        "Reinvesting protocol fees in reserve {}: {}",
+        ctx.accounts.reserve.key(),
+        reinvest_amount
+    );
+
+    lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+        token_interface::accessor::amount(&ctx.accounts.reserve_liquidity_supply.to_account_info())
+            .unwrap(),
+        reserve.liquidity.available_amount,
+        initial_reserve_token_balance,
+        initial_reserve_available_liquidity,
+        LendingAction::Additive(0),
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ReinvestProtocolFees<'info> {
+    pub lending_market_owner: Signer<'info>,
+
+    #[account(has_one = lending_market_owner)]
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+
+    #[account(mut,
+        has_one = lending_market
+    )]
+    pub reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = reserve.load()?.liquidity.supply_vault,
+    )]
+    pub reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+}
@@ -7,13 +7,14 @@ use crate::{
     handler_withdraw_obligation_collateral_and_redeem_reserve_collateral::{self, *},
     lending_market::lending_operations,
     refresh_farms,
+    state::obligation::ObligationDelegatePermissions,
     utils::seeds::pda,
     LendingError, LtvMaxWithdrawalCheck, MaxReservesAsCollateralCheck, RefreshObligation,
     RefreshObligationBumps, ReserveFarmKind,
 };
 
-pub fn process(
-    ctx: Context<RepayAndWithdraw>,
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, RepayAndWithdraw<'info>>,
     repay_amount: u64,
     withdraw_collateral_amount: u64,
 ) -> Result<()> {
@@ -45,10 +46,10 @@ pub fn process(
     Ok(())
 }
 
-fn process_impl(
-    repay_accounts: &RepayObligationLiquidity,
-    withdraw_accounts: &WithdrawObligationCollateralAndRedeemReserveCollateral,
-    remaining_accounts: &[AccountInfo],
+fn process_impl<'info>(
+    repay_accounts: &RepayObligationLiquidity<'info>,
+    withdraw_accounts: &WithdrawObligationCollateralAndRedeemReserveCollateral<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
     program_id: &Pubkey,
     repay_amount: u64,
     withdraw_collateral_amount: u64,
@@ -80,6 +81,7 @@ fn process_impl(
             repay_accounts,
             deposit_reserves_iter,
             repay_amount,
+            &[],
         )?;
     }
 
@@ -160,6 +162,7 @@ fn process_impl(
             withdraw_accounts,
             withdraw_collateral_amount,
             LtvMaxWithdrawalCheck::LiquidationThreshold,
+            &[],
         )?
     };
 
@@ -236,6 +239,7 @@ pub struct RepayAndWithdraw<'info> {
         constraint = repay_accounts.owner.key()          == withdraw_accounts.owner.key()          @ LendingError::ObligationOwnersMustMatch,
         constraint = repay_accounts.obligation.key()     == withdraw_accounts.obligation.key()     @ LendingError::ObligationsMustMatch,
         constraint = repay_accounts.lending_market.key() == withdraw_accounts.lending_market.key() @ LendingError::LendingMarketsMustMatch,
+        constraint = repay_accounts.obligation.load()?.has_authority(repay_accounts.owner.key(), ObligationDelegatePermissions::REPAY) @ LendingError::InvalidObligationOwner,
     )]
     pub repay_accounts: RepayObligationLiquidity<'info>,
     pub withdraw_accounts: WithdrawObligationCollateralAndRedeemReserveCollateral<'info>,
@@ -12,28 +12,40 @@ use crate::{
     refresh_farms,
     state::{obligation::Obligation, LendingMarket, Reserve},
     utils::{seeds, token_transfer, FatAccountLoader},
-    xmsg, LendingAction, ReserveFarmKind,
+    xmsg, LendingAction, LendingError, ReserveFarmKind,
 };
 
-pub fn process_v1(ctx: Context<RepayObligationLiquidity>, liquidity_amount: u64) -> Result<()> {
+pub fn process_v1<'info>(
+    ctx: Context<'_, '_, '_, 'info, RepayObligationLiquidity<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
     check_refresh_ixs!(
         ctx.accounts,
         ctx.accounts.repay_reserve,
         ReserveFarmKind::Debt
     );
 
+    let (deposit_reserves, transfer_hook_accounts) =
+        split_remaining_accounts(ctx.accounts, ctx.remaining_accounts)?;
     process_impl(
         ctx.accounts,
-        ctx.remaining_accounts.iter(),
+        deposit_reserves.iter(),
         liquidity_amount,
+        transfer_hook_accounts,
     )
 }
 
-pub fn process_v2(ctx: Context<RepayObligationLiquidityV2>, liquidity_amount: u64) -> Result<()> {
+pub fn process_v2<'info>(
+    ctx: Context<'_, '_, '_, 'info, RepayObligationLiquidityV2<'info>>,
+    liquidity_amount: u64,
+) -> Result<()> {
+    let (deposit_reserves, transfer_hook_accounts) =
+        split_remaining_accounts(&ctx.accounts.repay_accounts, ctx.remaining_accounts)?;
     process_impl(
         &ctx.accounts.repay_accounts,
-        ctx.remaining_accounts.iter(),
+        deposit_reserves.iter(),
         liquidity_amount,
+        transfer_hook_accounts,
     )?;
     refresh_farms!(
         ctx.accounts.repay_accounts,
@@ -47,10 +59,26 @@ pub fn process_v2(ctx: Context<RepayObligationLiquidityV2>, liquidity_amount: u6
     Ok(())
 }
 
+fn split_remaining_accounts<'a, 'info>(
+    accounts: &RepayObligationLiquidity<'info>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+    let deposits_count = accounts.obligation.load()?.deposits_count();
+    if remaining_accounts.len() < deposits_count {
+        msg!(
+            "Expected at least {} deposit reserves in remaining accounts",
+            deposits_count
+        );
+        return err!(LendingError::InvalidAccountInput);
+    }
+    Ok(remaining_accounts.split_at(deposits_count))
+}
+
 pub(super) fn process_impl<'a, 'info>(
-    accounts: &RepayObligationLiquidity,
+    accounts: &RepayObligationLiquidity<'info>,
     remaining_accounts: impl Iterator<Item = &'a AccountInfo<'info>>,
     liquidity_amount: u64,
+    transfer_hook_accounts: &[AccountInfo<'info>],
 ) -> Result<()>
 where
     'info: 'a,
@@ -94,6 +122,7 @@ where
         accounts.owner.to_account_info(),
         repay_amount,
         accounts.reserve_liquidity_mint.decimals,
+        transfer_hook_accounts,
     )?;
 
     lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
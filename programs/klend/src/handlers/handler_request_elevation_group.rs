@@ -1,8 +1,9 @@
 use anchor_lang::prelude::*;
 
 use crate::{
-    lending_market::lending_operations, utils::FatAccountLoader, LendingError, LendingMarket,
-    Obligation, ReferrerTokenState, Reserve,
+    lending_market::lending_operations,
+    utils::{FatAccountLoader, ELEVATION_GROUP_AUTO},
+    LendingError, LendingMarket, Obligation, ReferrerTokenState, Reserve,
 };
 
 pub fn process(ctx: Context<RequestElevationGroup>, new_elevation_group: u8) -> Result<()> {
@@ -44,6 +45,26 @@ pub fn process(ctx: Context<RequestElevationGroup>, new_elevation_group: u8) ->
                 FatAccountLoader::<ReferrerTokenState>::try_from(account_info).unwrap()
             });
 
+    let new_elevation_group = if new_elevation_group == ELEVATION_GROUP_AUTO {
+        let best_elevation_group = lending_operations::find_best_elevation_group(
+            obligation,
+            &lending_market,
+            slot,
+            deposit_reserves_iter.clone(),
+            borrow_reserves_iter.clone(),
+        )?;
+        if best_elevation_group == obligation.elevation_group {
+            msg!(
+                "Obligation is already in the best elevation group {}",
+                best_elevation_group
+            );
+            return Ok(());
+        }
+        best_elevation_group
+    } else {
+        new_elevation_group
+    };
+
     lending_operations::request_elevation_group(
         &crate::ID,
         obligation,
@@ -0,0 +1,67 @@
+use anchor_lang::{prelude::*, Accounts};
+
+use crate::{
+    state::{obligation::Obligation, LendingMarket},
+    utils::FULL_BPS,
+    LendingError,
+};
+
+pub fn process(
+    ctx: Context<SetObligationProtectionOrder>,
+    trigger_ltv_pct: u8,
+    target_ltv_pct: u8,
+    bonus_bps: u16,
+) -> Result<()> {
+    let obligation = &mut ctx.accounts.obligation.load_mut()?;
+
+    if trigger_ltv_pct == 0 {
+        if target_ltv_pct > 0 || bonus_bps > 0 {
+            msg!("Protection order without a trigger LTV cannot have a target or bonus");
+            return err!(LendingError::InvalidProtectionOrder);
+        }
+    } else {
+        if trigger_ltv_pct >= 100 || target_ltv_pct >= trigger_ltv_pct {
+            msg!(
+                "Protection order target LTV {}% must be lower than the trigger LTV {}%, which must be lower than 100%",
+                target_ltv_pct,
+                trigger_ltv_pct
+            );
+            return err!(LendingError::InvalidProtectionOrder);
+        }
+
+        if bonus_bps == 0 || bonus_bps >= FULL_BPS {
+            msg!("Protection order bonus {} bps is invalid", bonus_bps);
+            return err!(LendingError::InvalidProtectionOrder);
+        }
+    }
+
+    msg!(
+        "Prv protection order trigger ltv {} target ltv {} bonus bps {}",
+        obligation.protection_order_trigger_ltv_pct,
+        obligation.protection_order_target_ltv_pct,
+        obligation.protection_order_bonus_bps
+    );
+    msg!(
+        "New protection order trigger ltv {} target ltv {} bonus bps {}",
+        trigger_ltv_pct,
+        target_ltv_pct,
+        bonus_bps
+    );
+
+    obligation.set_protection_order(trigger_ltv_pct, target_ltv_pct, bonus_bps);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetObligationProtectionOrder<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+}
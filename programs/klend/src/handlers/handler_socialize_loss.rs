@@ -44,10 +44,12 @@ fn process_impl(
 ) -> Result<()> {
     let clock = Clock::get()?;
 
+    let lending_market = &accounts.lending_market.load()?;
     let repay_reserve = &mut accounts.reserve.load_mut()?;
     let obligation = &mut accounts.obligation.load_mut()?;
 
     lending_operations::socialize_loss(
+        lending_market,
         repay_reserve,
         &accounts.reserve.key(),
         obligation,
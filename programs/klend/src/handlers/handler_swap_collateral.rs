@@ -0,0 +1,365 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program,
+    },
+    Accounts,
+};
+use anchor_spl::{
+    token::Token,
+    token_interface::{self, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    gen_signer_seeds,
+    handler_refresh_obligation_farms_for_reserve::*,
+    lending_market::{lending_checks, lending_operations},
+    refresh_farms,
+    state::{obligation::Obligation, LendingMarket, Reserve, ReserveStatus},
+    utils::{seeds, token_transfer},
+    DepositLiquidityResult, LendingAction, LendingError, MaxReservesAsCollateralCheck,
+    ReferrerTokenState, ReserveFarmKind,
+};
+
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, SwapCollateral<'info>>,
+    withdraw_collateral_amount: u64,
+    min_deposit_liquidity_amount: u64,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let clock = Clock::get()?;
+    let lending_market_key = accounts.lending_market.key();
+
+    let bump_seed = {
+        let lending_market = &accounts.lending_market.load()?;
+        if lending_market.swap_adapter_program == Pubkey::default()
+            || lending_market.swap_adapter_program != accounts.swap_adapter_program.key()
+        {
+            return err!(LendingError::SwapAdapterNotWhitelisted);
+        }
+        lending_market.bump_seed as u8
+    };
+    let authority_signer_seeds = gen_signer_seeds!(lending_market_key, bump_seed);
+    // The swap accounts also carry the transfer hook extra accounts, token-2022 resolves them by key.
+    let swap_accounts = ctx.remaining_accounts;
+
+    let withdraw_value = {
+        let withdraw_reserve = &mut accounts.withdraw_reserve.load_mut()?;
+        let deposit_reserve = &accounts.deposit_reserve.load()?;
+        let obligation = &mut accounts.obligation.load_mut()?;
+        let lending_market = &accounts.lending_market.load()?;
+
+        if deposit_reserve.config.status() == ReserveStatus::Obsolete {
+            msg!("Deposit reserve is not active");
+            return err!(LendingError::ReserveObsolete);
+        }
+
+        let (withdraw_collateral_amount, withdraw_value) =
+            lending_operations::withdraw_obligation_collateral_for_swap(
+                lending_market,
+                withdraw_reserve,
+                deposit_reserve,
+                obligation,
+                withdraw_collateral_amount,
+                clock.slot,
+                accounts.withdraw_reserve.key(),
+            )?;
+
+        let initial_reserve_token_balance = token_interface::accessor::amount(
+            &accounts.withdraw_reserve_liquidity_supply.to_account_info(),
+        )?;
+        let initial_reserve_available_liquidity = withdraw_reserve.liquidity.available_amount;
+
+        let withdraw_referrer_token_state = lending_checks::supply_fee_referrer_token_state(
+            &accounts.withdraw_referrer_token_state,
+            obligation.referrer,
+            withdraw_reserve.config.supply_fees.has_withdrawal_fee(),
+            withdraw_reserve.liquidity.mint_pubkey,
+            accounts.withdraw_reserve.key(),
+        )?;
+
+        let withdraw_liquidity_amount = lending_operations::redeem_reserve_collateral(
+            withdraw_reserve,
+            withdraw_collateral_amount,
+            &clock,
+            true,
+            lending_market.referral_fee_bps,
+            withdraw_referrer_token_state,
+        )?;
+
+        msg!(
+            "Swapping collateral {} of reserve {} for reserve {}",
+            withdraw_liquidity_amount,
+            accounts.withdraw_reserve.key(),
+            accounts.deposit_reserve.key()
+        );
+
+        token_transfer::withdraw_and_redeem_reserve_collateral_transfer(
+            accounts.collateral_token_program.to_account_info(),
+            accounts.withdraw_liquidity_token_program.to_account_info(),
+            accounts.withdraw_reserve_liquidity_mint.to_account_info(),
+            accounts.withdraw_reserve_collateral_mint.to_account_info(),
+            accounts
+                .withdraw_reserve_source_collateral
+                .to_account_info(),
+            accounts.withdraw_reserve_liquidity_supply.to_account_info(),
+            accounts.user_source_liquidity.to_account_info(),
+            accounts.lending_market_authority.clone(),
+            authority_signer_seeds,
+            withdraw_collateral_amount,
+            withdraw_liquidity_amount,
+            accounts.withdraw_reserve_liquidity_mint.decimals,
+            swap_accounts,
+        )?;
+
+        lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+            token_interface::accessor::amount(
+                &accounts.withdraw_reserve_liquidity_supply.to_account_info(),
+            )
+            .unwrap(),
+            withdraw_reserve.liquidity.available_amount,
+            initial_reserve_token_balance,
+            initial_reserve_available_liquidity,
+            LendingAction::Subtractive(withdraw_liquidity_amount),
+        )?;
+
+        withdraw_value
+    };
+
+    let initial_user_destination_balance =
+        token_interface::accessor::amount(&accounts.user_destination_liquidity.to_account_info())?;
+
+    let swap_instruction = Instruction {
+        program_id: accounts.swap_adapter_program.key(),
+        accounts: swap_accounts
+            .iter()
+            .map(|account_info| AccountMeta {
+                pubkey: account_info.key(),
+                is_signer: account_info.is_signer,
+                is_writable: account_info.is_writable,
+            })
+            .collect(),
+        data: swap_data,
+    };
+    program::invoke(&swap_instruction, swap_accounts)?;
+
+    let deposit_liquidity_amount =
+        token_interface::accessor::amount(&accounts.user_destination_liquidity.to_account_info())?
+            .checked_sub(initial_user_destination_balance)
+            .ok_or_else(|| error!(LendingError::SwapCollateralSlippageExceeded))?;
+
+    if deposit_liquidity_amount < min_deposit_liquidity_amount {
+        msg!(
+            "Swap output {} is lower than the minimum accepted {}",
+            deposit_liquidity_amount,
+            min_deposit_liquidity_amount
+        );
+        return err!(LendingError::SwapCollateralSlippageExceeded);
+    }
+
+    let deposit_liquidity_amount = token_transfer::get_amount_after_transfer_fee(
+        &accounts.deposit_reserve_liquidity_mint.to_account_info(),
+        deposit_liquidity_amount,
+    )?;
+
+    {
+        let withdraw_reserve = &accounts.withdraw_reserve.load()?;
+        let deposit_reserve = &mut accounts.deposit_reserve.load_mut()?;
+        let obligation = &mut accounts.obligation.load_mut()?;
+        let lending_market = &accounts.lending_market.load()?;
+
+        let initial_reserve_token_balance = token_interface::accessor::amount(
+            &accounts.deposit_reserve_liquidity_supply.to_account_info(),
+        )?;
+        let initial_reserve_available_liquidity = deposit_reserve.liquidity.available_amount;
+
+        let deposit_referrer_token_state = lending_checks::supply_fee_referrer_token_state(
+            &accounts.deposit_referrer_token_state,
+            obligation.referrer,
+            deposit_reserve.config.supply_fees.has_deposit_fee(),
+            deposit_reserve.liquidity.mint_pubkey,
+            accounts.deposit_reserve.key(),
+        )?;
+
+        let DepositLiquidityResult {
+            liquidity_amount,
+            collateral_amount,
+        } = lending_operations::deposit_reserve_liquidity(
+            deposit_reserve,
+            &clock,
+            deposit_liquidity_amount,
+            lending_market.referral_fee_bps,
+            deposit_referrer_token_state,
+        )?;
+
+        lending_operations::refresh_reserve(
+            deposit_reserve,
+            &clock,
+            None,
+            lending_market.referral_fee_bps,
+        )?;
+
+        lending_operations::deposit_obligation_collateral(
+            lending_market,
+            deposit_reserve,
+            obligation,
+            clock.slot,
+            collateral_amount,
+            accounts.deposit_reserve.key(),
+            MaxReservesAsCollateralCheck::Perform,
+        )?;
+
+        lending_operations::post_swap_collateral_obligation_invariants(
+            lending_market,
+            obligation,
+            withdraw_reserve,
+            deposit_reserve,
+            withdraw_value,
+            collateral_amount,
+        )?;
+
+        msg!(
+            "pnl: Swap collateral deposit reserve liquidity {} and obligation collateral {}",
+            liquidity_amount,
+            collateral_amount
+        );
+
+        token_transfer::deposit_reserve_liquidity_and_obligation_collateral_transfer(
+            accounts.user_destination_liquidity.to_account_info(),
+            accounts.deposit_reserve_liquidity_supply.to_account_info(),
+            accounts.owner.to_account_info(),
+            accounts.deposit_reserve_liquidity_mint.to_account_info(),
+            accounts.deposit_liquidity_token_program.to_account_info(),
+            accounts.deposit_reserve_collateral_mint.to_account_info(),
+            accounts
+                .deposit_reserve_destination_collateral
+                .to_account_info(),
+            accounts.collateral_token_program.to_account_info(),
+            accounts.lending_market_authority.clone(),
+            authority_signer_seeds,
+            liquidity_amount,
+            accounts.deposit_reserve_liquidity_mint.decimals,
+            collateral_amount,
+            swap_accounts,
+        )?;
+
+        lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
+            token_interface::accessor::amount(
+                &accounts.deposit_reserve_liquidity_supply.to_account_info(),
+            )
+            .unwrap(),
+            deposit_reserve.liquidity.available_amount,
+            initial_reserve_token_balance,
+            initial_reserve_available_liquidity,
+            LendingAction::Additive(liquidity_amount),
+        )?;
+    }
+
+    refresh_farms!(
+        ctx.accounts,
+        [
+            (
+                ctx.accounts.withdraw_reserve,
+                ctx.accounts.withdraw_farms_accounts,
+                Collateral,
+            ),
+            (
+                ctx.accounts.deposit_reserve,
+                ctx.accounts.deposit_farms_accounts,
+                Collateral,
+            ),
+        ],
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SwapCollateral<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    #[account(mut, has_one = lending_market)]
+    pub withdraw_reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = withdraw_reserve.load()?.liquidity.mint_pubkey,
+        mint::token_program = withdraw_liquidity_token_program,
+    )]
+    pub withdraw_reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = withdraw_reserve.load()?.collateral.supply_vault)]
+    pub withdraw_reserve_source_collateral: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = withdraw_reserve.load()?.collateral.mint_pubkey)]
+    pub withdraw_reserve_collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = withdraw_reserve.load()?.liquidity.supply_vault,
+    )]
+    pub withdraw_reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        has_one = lending_market,
+        constraint = deposit_reserve.key() != withdraw_reserve.key() @ LendingError::InvalidAccountInput
+    )]
+    pub deposit_reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = deposit_reserve.load()?.liquidity.mint_pubkey,
+        mint::token_program = deposit_liquidity_token_program,
+    )]
+    pub deposit_reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = deposit_reserve.load()?.liquidity.supply_vault,
+    )]
+    pub deposit_reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = deposit_reserve.load()?.collateral.mint_pubkey)]
+    pub deposit_reserve_collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = deposit_reserve.load()?.collateral.supply_vault)]
+    pub deposit_reserve_destination_collateral: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        token::mint = withdraw_reserve.load()?.liquidity.mint_pubkey,
+        token::authority = owner,
+    )]
+    pub user_source_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        token::mint = deposit_reserve.load()?.liquidity.mint_pubkey,
+        token::authority = owner,
+    )]
+    pub user_destination_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub withdraw_referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+    #[account(mut)]
+    pub deposit_referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
+    pub swap_adapter_program: AccountInfo<'info>,
+
+    pub collateral_token_program: Program<'info, Token>,
+    pub withdraw_liquidity_token_program: Interface<'info, TokenInterface>,
+    pub deposit_liquidity_token_program: Interface<'info, TokenInterface>,
+
+    pub withdraw_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub deposit_farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub farms_program: Program<'info, farms::program::Farms>,
+}
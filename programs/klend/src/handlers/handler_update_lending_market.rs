@@ -3,7 +3,10 @@ use anchor_lang::{prelude::*, Accounts};
 use crate::{
     borsh::BorshDeserialize,
     fraction::FractionExtra,
-    state::{lending_market::ElevationGroup, LendingMarket, UpdateLendingMarketMode},
+    state::{
+        lending_market::{ElevationGroup, FeeRecipient},
+        LendingMarket, UpdateLendingMarketMode,
+    },
     utils::{
         validate_numerical_bool, Fraction, ELEVATION_GROUP_NONE, FULL_BPS,
         MAX_NUM_ELEVATION_GROUPS, MIN_INITIAL_DEPOSIT_AMOUNT,
@@ -141,6 +144,13 @@ pub fn process(
                 return err!(LendingError::InvalidElevationGroupConfig);
             }
 
+            if elevation_group.secondary_debt_reserve != Pubkey::default()
+                && elevation_group.secondary_debt_reserve == elevation_group.debt_reserve
+            {
+                msg!("Secondary debt reserve must differ from the elevation group debt reserve");
+                return err!(LendingError::InvalidElevationGroupConfig);
+            }
+
             if Fraction::from_percent(elevation_group.liquidation_threshold_pct)
                 + Fraction::from_percent(elevation_group.liquidation_threshold_pct)
                     * Fraction::from_bps(elevation_group.max_liquidation_bonus_bps)
@@ -285,6 +295,31 @@ pub fn process(
             }
             *current_value = new_value;
         }
+        UpdateLendingMarketMode::UpdateFeeRecipient => {
+            let (index, fee_recipient): (u8, FeeRecipient) =
+                BorshDeserialize::deserialize(&mut &value[..]).unwrap();
+            let index = usize::from(index);
+            msg!("Prv value is {:?}", market.fee_recipients.get(index));
+            msg!("New value is {:?}", fee_recipient);
+            market.set_fee_recipient(index, fee_recipient)?;
+        }
+        UpdateLendingMarketMode::UpdateSwapAdapterProgram => {
+            let value: [u8; 32] = value[0..32].try_into().unwrap();
+            let value = Pubkey::from(value);
+            msg!("Prv value is {:?}", market.swap_adapter_program);
+            msg!("New value is {:?}", value);
+            market.swap_adapter_program = value;
+        }
+        UpdateLendingMarketMode::UpdateMaxSwapCollateralSlippageBps => {
+            let value = u64::from_le_bytes(value[..8].try_into().unwrap());
+            msg!("Prv value is {:?}", market.max_swap_collateral_slippage_bps);
+            msg!("New value is {:?}", value);
+            if value > u64::from(FULL_BPS) {
+                msg!("Max swap collateral slippage bps must be in range [0, 10000]");
+                return err!(LendingError::InvalidConfig);
+            }
+            market.max_swap_collateral_slippage_bps = value;
+        }
     }
 
     Ok(())
@@ -0,0 +1,66 @@
+use anchor_lang::{prelude::*, Accounts};
+
+use crate::{
+    state::{
+        obligation::{Obligation, ObligationDelegatePermissions},
+        LendingMarket,
+    },
+    LendingError,
+};
+
+pub fn process(
+    ctx: Context<UpdateObligationDelegate>,
+    delegate: Pubkey,
+    permissions: u8,
+    max_borrow_ltv_pct: u8,
+) -> Result<()> {
+    let obligation = &mut ctx.accounts.obligation.load_mut()?;
+
+    let permissions = ObligationDelegatePermissions::from_bits(permissions)
+        .ok_or(error!(LendingError::InvalidConfig))?;
+
+    if delegate == obligation.owner {
+        msg!("Obligation owner cannot be its own delegate");
+        return err!(LendingError::InvalidConfig);
+    }
+
+    if max_borrow_ltv_pct > 100 {
+        msg!("Delegate max borrow LTV must be at most 100%");
+        return err!(LendingError::InvalidConfig);
+    }
+
+    if delegate == Pubkey::default() && (!permissions.is_empty() || max_borrow_ltv_pct > 0) {
+        msg!("Permissions cannot be granted to an empty delegate");
+        return err!(LendingError::InvalidConfig);
+    }
+
+    msg!(
+        "Prv delegate {} permissions {:08b} max borrow ltv {}",
+        obligation.delegate,
+        obligation.delegate_permissions,
+        obligation.delegate_max_borrow_ltv_pct
+    );
+    msg!(
+        "New delegate {} permissions {:08b} max borrow ltv {}",
+        delegate,
+        permissions.bits(),
+        max_borrow_ltv_pct
+    );
+
+    obligation.set_delegate(delegate, permissions, max_borrow_ltv_pct);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateObligationDelegate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        has_one = owner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+}
@@ -10,9 +10,12 @@ use crate::{
     handler_refresh_obligation_farms_for_reserve::*,
     lending_market::{lending_checks, lending_operations},
     refresh_farms,
-    state::{obligation::Obligation, LendingMarket, Reserve, WithdrawObligationCollateralAccounts},
+    state::{
+        obligation::{Obligation, ObligationDelegatePermissions},
+        LendingMarket, Reserve, WithdrawObligationCollateralAccounts,
+    },
     utils::{close_account_loader, seeds, token_transfer},
-    LtvMaxWithdrawalCheck, ReserveFarmKind,
+    LendingError, LtvMaxWithdrawalCheck, ReserveFarmKind,
 };
 
 pub fn process_v1(
@@ -88,7 +91,9 @@ fn process_impl(accounts: &WithdrawObligationCollateral, collateral_amount: u64)
 
         msg!("pnl: Withdraw obligation collateral {}", withdraw_amount);
 
-        obligation.deposits_empty() && obligation.borrows_empty()
+        obligation.deposits_empty()
+            && obligation.borrows_empty()
+            && accounts.owner.key() == obligation.owner
     };
 
     close_account_loader(close_obligation, &accounts.owner, &accounts.obligation)?;
@@ -101,7 +106,7 @@ pub struct WithdrawObligationCollateral<'info> {
     pub owner: Signer<'info>,
     #[account(mut,
         has_one = lending_market,
-        has_one = owner
+        constraint = obligation.load()?.has_authority(owner.key(), ObligationDelegatePermissions::WITHDRAW) @ LendingError::InvalidObligationOwner
     )]
     pub obligation: AccountLoader<'info, Obligation>,
 
@@ -125,7 +130,7 @@ pub struct WithdrawObligationCollateral<'info> {
 
     #[account(mut,
         token::mint = withdraw_reserve.load()?.collateral.mint_pubkey,
-        token::authority = owner
+        token::authority = obligation.load()?.owner
     )]
     pub user_destination_collateral: Box<InterfaceAccount<'info, TokenAccount>>,
 
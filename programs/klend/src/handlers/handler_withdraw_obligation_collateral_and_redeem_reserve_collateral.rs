@@ -13,14 +13,17 @@ use crate::{
     handler_refresh_obligation_farms_for_reserve::*,
     lending_market::{lending_checks, lending_operations},
     refresh_farms,
-    state::{obligation::Obligation, LendingMarket, Reserve},
+    state::{
+        obligation::{Obligation, ObligationDelegatePermissions},
+        LendingMarket, Reserve,
+    },
     utils::{close_account_loader, seeds, token_transfer},
-    LendingAction, LtvMaxWithdrawalCheck, ReserveFarmKind,
+    LendingAction, LendingError, LtvMaxWithdrawalCheck, ReferrerTokenState, ReserveFarmKind,
     WithdrawObligationCollateralAndRedeemReserveCollateralAccounts,
 };
 
-pub fn process_v1(
-    ctx: Context<WithdrawObligationCollateralAndRedeemReserveCollateral>,
+pub fn process_v1<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawObligationCollateralAndRedeemReserveCollateral<'info>>,
     collateral_amount: u64,
 ) -> Result<()> {
     check_refresh_ixs!(
@@ -33,19 +36,27 @@ pub fn process_v1(
         ctx.accounts,
         collateral_amount,
         LtvMaxWithdrawalCheck::MaxLtv,
+        ctx.remaining_accounts,
     )?;
 
     Ok(())
 }
 
-pub fn process_v2(
-    ctx: Context<WithdrawObligationCollateralAndRedeemReserveCollateralV2>,
+pub fn process_v2<'info>(
+    ctx: Context<
+        '_,
+        '_,
+        '_,
+        'info,
+        WithdrawObligationCollateralAndRedeemReserveCollateralV2<'info>,
+    >,
     collateral_amount: u64,
 ) -> Result<()> {
     process_impl(
         &ctx.accounts.withdraw_accounts,
         collateral_amount,
         LtvMaxWithdrawalCheck::MaxLtv,
+        ctx.remaining_accounts,
     )?;
     refresh_farms!(
         ctx.accounts.withdraw_accounts,
@@ -58,10 +69,11 @@ pub fn process_v2(
     Ok(())
 }
 
-pub(super) fn process_impl(
-    accounts: &WithdrawObligationCollateralAndRedeemReserveCollateral,
+pub(super) fn process_impl<'info>(
+    accounts: &WithdrawObligationCollateralAndRedeemReserveCollateral<'info>,
     collateral_amount: u64,
     ltv_max_withdrawal_check: LtvMaxWithdrawalCheck,
+    transfer_hook_accounts: &[AccountInfo<'info>],
 ) -> Result<bool> {
     let close_obligation = {
         lending_checks::withdraw_obligation_collateral_and_redeem_reserve_collateral_checks(
@@ -95,11 +107,20 @@ pub(super) fn process_impl(
             accounts.withdraw_reserve.key(),
             ltv_max_withdrawal_check,
         )?;
+        let referrer_token_state = lending_checks::supply_fee_referrer_token_state(
+            &accounts.referrer_token_state,
+            obligation.referrer,
+            reserve.config.supply_fees.has_withdrawal_fee(),
+            reserve.liquidity.mint_pubkey,
+            accounts.withdraw_reserve.key(),
+        )?;
         let withdraw_liquidity_amount = lending_operations::redeem_reserve_collateral(
             reserve,
             withdraw_obligation_amount,
             clock,
             true,
+            lending_market.referral_fee_bps,
+            referrer_token_state,
         )?;
         msg!(
             "pnl: Withdraw obligation collateral {} and redeem reserve collateral {}",
@@ -120,6 +141,7 @@ pub(super) fn process_impl(
             withdraw_obligation_amount,
             withdraw_liquidity_amount,
             accounts.reserve_liquidity_mint.decimals,
+            transfer_hook_accounts,
         )?;
 
         lending_checks::post_transfer_vault_balance_liquidity_reserve_checks(
@@ -131,7 +153,9 @@ pub(super) fn process_impl(
             LendingAction::Subtractive(withdraw_liquidity_amount),
         )?;
 
-        obligation.deposits_empty() && obligation.borrows_empty()
+        obligation.deposits_empty()
+            && obligation.borrows_empty()
+            && accounts.owner.key() == obligation.owner
     };
 
     close_account_loader(close_obligation, &accounts.owner, &accounts.obligation)?;
@@ -146,7 +170,7 @@ pub struct WithdrawObligationCollateralAndRedeemReserveCollateral<'info> {
 
     #[account(mut,
         has_one = lending_market,
-        has_one = owner
+        constraint = obligation.load()?.has_authority(owner.key(), ObligationDelegatePermissions::WITHDRAW) @ LendingError::InvalidObligationOwner
     )]
     pub obligation: AccountLoader<'info, Obligation>,
 
@@ -179,12 +203,15 @@ pub struct WithdrawObligationCollateralAndRedeemReserveCollateral<'info> {
 
     #[account(mut,
         token::mint = withdraw_reserve.load()?.liquidity.mint_pubkey,
-        token::authority = owner,
+        token::authority = obligation.load()?.owner,
     )]
     pub user_destination_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
 
     pub placeholder_user_destination_collateral: Option<AccountInfo<'info>>,
 
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
     pub collateral_token_program: Program<'info, Token>,
     pub liquidity_token_program: Interface<'info, TokenInterface>,
 
@@ -0,0 +1,152 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::{instructions::Instructions as SysInstructions, SysvarId},
+    Accounts,
+};
+use anchor_spl::{
+    token::{spl_token, Token},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    gen_signer_seeds,
+    handler_refresh_obligation_farms_for_reserve::*,
+    handler_withdraw_obligation_collateral_and_redeem_reserve_collateral::{
+        self, WithdrawObligationCollateralAndRedeemReserveCollateral,
+    },
+    refresh_farms,
+    state::{
+        obligation::{Obligation, ObligationDelegatePermissions},
+        LendingMarket, Reserve,
+    },
+    utils::{seeds, token_transfer},
+    LendingError, LtvMaxWithdrawalCheck, ReferrerTokenState, ReserveFarmKind,
+};
+
+pub fn process<'info>(
+    ctx: Context<
+        '_,
+        '_,
+        '_,
+        'info,
+        WithdrawObligationCollateralAndRedeemReserveCollateralNative<'info>,
+    >,
+    collateral_amount: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let withdraw_accounts = WithdrawObligationCollateralAndRedeemReserveCollateral {
+        owner: accounts.owner.clone(),
+        obligation: accounts.obligation.clone(),
+        lending_market: accounts.lending_market.clone(),
+        lending_market_authority: accounts.lending_market_authority.clone(),
+        withdraw_reserve: accounts.withdraw_reserve.clone(),
+        reserve_liquidity_mint: accounts.reserve_liquidity_mint.clone(),
+        reserve_source_collateral: accounts.reserve_source_collateral.clone(),
+        reserve_collateral_mint: accounts.reserve_collateral_mint.clone(),
+        reserve_liquidity_supply: accounts.reserve_liquidity_supply.clone(),
+        user_destination_liquidity: accounts.user_native_liquidity.clone(),
+        placeholder_user_destination_collateral: None,
+        referrer_token_state: accounts.referrer_token_state.clone(),
+        collateral_token_program: accounts.collateral_token_program.clone(),
+        liquidity_token_program: accounts.liquidity_token_program.clone(),
+        instruction_sysvar_account: accounts.instruction_sysvar_account.clone(),
+    };
+
+    handler_withdraw_obligation_collateral_and_redeem_reserve_collateral::process_impl(
+        &withdraw_accounts,
+        collateral_amount,
+        LtvMaxWithdrawalCheck::MaxLtv,
+        &[],
+    )?;
+    refresh_farms!(
+        withdraw_accounts,
+        [(
+            withdraw_accounts.withdraw_reserve,
+            accounts.farms_accounts,
+            Collateral,
+        )]
+    );
+
+    let lending_market = accounts.lending_market.load()?;
+    let lending_market_key = accounts.lending_market.key();
+    let authority_signer_seeds =
+        gen_signer_seeds!(lending_market_key.as_ref(), lending_market.bump_seed as u8);
+
+    token_transfer::close_native_liquidity_account(
+        accounts.liquidity_token_program.to_account_info(),
+        accounts.user_native_liquidity.to_account_info(),
+        accounts.obligation_owner.clone(),
+        accounts.lending_market_authority.clone(),
+        &[authority_signer_seeds],
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawObligationCollateralAndRedeemReserveCollateralNative<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = lending_market,
+        constraint = obligation.load()?.has_authority(owner.key(), ObligationDelegatePermissions::WITHDRAW) @ LendingError::InvalidObligationOwner
+    )]
+    pub obligation: AccountLoader<'info, Obligation>,
+
+    #[account(mut,
+        address = obligation.load()?.owner @ LendingError::InvalidObligationOwner,
+    )]
+    pub obligation_owner: AccountInfo<'info>,
+
+    pub lending_market: AccountLoader<'info, LendingMarket>,
+    #[account(
+        seeds = [seeds::LENDING_MARKET_AUTH, lending_market.key().as_ref()],
+        bump = lending_market.load()?.bump_seed as u8,
+    )]
+    pub lending_market_authority: AccountInfo<'info>,
+
+    #[account(mut, has_one = lending_market)]
+    pub withdraw_reserve: AccountLoader<'info, Reserve>,
+
+    #[account(
+        address = withdraw_reserve.load()?.liquidity.mint_pubkey,
+        constraint = reserve_liquidity_mint.key() == spl_token::native_mint::ID @ LendingError::ReserveNotNativeSol,
+        mint::token_program = liquidity_token_program,
+    )]
+    pub reserve_liquidity_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = withdraw_reserve.load()?.collateral.supply_vault)]
+    pub reserve_source_collateral: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = withdraw_reserve.load()?.collateral.mint_pubkey)]
+    pub reserve_collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut,
+        address = withdraw_reserve.load()?.liquidity.supply_vault,
+    )]
+    pub reserve_liquidity_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(init,
+        seeds = [seeds::NATIVE_LIQUIDITY, owner.key().as_ref()],
+        bump,
+        payer = owner,
+        token::mint = reserve_liquidity_mint,
+        token::authority = lending_market_authority,
+        token::token_program = liquidity_token_program,
+    )]
+    pub user_native_liquidity: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub referrer_token_state: Option<AccountLoader<'info, ReferrerTokenState>>,
+
+    pub collateral_token_program: Program<'info, Token>,
+    pub liquidity_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    #[account(address = SysInstructions::id())]
+    pub instruction_sysvar_account: AccountInfo<'info>,
+
+    pub farms_accounts: OptionalObligationFarmsAccounts<'info>,
+    pub farms_program: Program<'info, farms::program::Farms>,
+}
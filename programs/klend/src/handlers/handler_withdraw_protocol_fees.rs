@@ -7,10 +7,14 @@ use crate::{
     utils::{constraints, seeds, token_transfer},
 };
 
-pub fn process(ctx: Context<WithdrawProtocolFees>, amount: u64) -> Result<()> {
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawProtocolFees<'info>>,
+    amount: u64,
+) -> Result<()> {
     constraints::token_2022::validate_liquidity_token_extensions(
         &ctx.accounts.reserve_liquidity_mint.to_account_info(),
         &ctx.accounts.fee_vault.to_account_info(),
+        &ctx.accounts.reserve.load()?.config.transfer_hook_program,
     )?;
 
     let market = ctx.accounts.lending_market.load()?;
@@ -31,6 +35,7 @@ pub fn process(ctx: Context<WithdrawProtocolFees>, amount: u64) -> Result<()> {
         authority_signer_seeds,
         amount,
         ctx.accounts.reserve_liquidity_mint.decimals,
+        ctx.remaining_accounts,
     )?;
 
     Ok(())
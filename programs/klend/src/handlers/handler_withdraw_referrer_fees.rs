@@ -13,10 +13,11 @@ use crate::{
     ReferrerTokenState,
 };
 
-pub fn process(ctx: Context<WithdrawReferrerFees>) -> Result<()> {
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawReferrerFees<'info>>) -> Result<()> {
     constraints::token_2022::validate_liquidity_token_extensions(
         &ctx.accounts.reserve_liquidity_mint.to_account_info(),
         &ctx.accounts.referrer_token_account.to_account_info(),
+        &ctx.accounts.reserve.load()?.config.transfer_hook_program,
     )?;
 
     let clock = &Clock::get()?;
@@ -43,6 +44,7 @@ pub fn process(ctx: Context<WithdrawReferrerFees>) -> Result<()> {
         authority_signer_seeds,
         withdraw_amount,
         ctx.accounts.reserve_liquidity_mint.decimals,
+        ctx.remaining_accounts,
     )?;
 
     Ok(())
@@ -1,11 +1,22 @@
+pub mod handler_accept_obligation_owner;
+pub mod handler_adjust_leverage;
 pub mod handler_borrow_obligation_liquidity;
+pub mod handler_borrow_obligation_liquidity_native;
+pub mod handler_close_obligation;
+pub mod handler_close_referrer_token_state;
+pub mod handler_close_user_metadata;
 pub mod handler_delete_referrer_state_and_short_url;
 pub mod handler_deposit_and_withdraw;
 pub mod handler_deposit_obligation_collateral;
 pub mod handler_deposit_reserve_liquidity;
 pub mod handler_deposit_reserve_liquidity_and_obligation_collateral;
+pub mod handler_deposit_reserve_liquidity_and_obligation_collateral_native;
+pub mod handler_deposit_reserve_liquidity_native;
+pub mod handler_distribute_protocol_fees;
+pub mod handler_donate_to_reserve;
 pub mod handler_flash_borrow_reserve_liquidity;
 pub mod handler_flash_repay_reserve_liquidity;
+pub mod handler_get_best_elevation_group;
 pub mod handler_init_farms_for_reserve;
 pub mod handler_init_lending_market;
 pub mod handler_init_obligation;
@@ -16,32 +27,53 @@ pub mod handler_init_reserve;
 pub mod handler_init_user_metadata;
 pub mod handler_liquidate_obligation_and_redeem_reserve_collateral;
 pub mod handler_mark_obligation_for_deleveraging;
+pub mod handler_move_obligation_collateral;
+pub mod handler_move_obligation_debt;
+pub mod handler_propose_obligation_owner;
 pub mod handler_redeem_fees;
 pub mod handler_redeem_reserve_collateral;
+pub mod handler_redeem_reserve_collateral_native;
+pub mod handler_refinance_debt;
 pub mod handler_refresh_obligation;
 pub mod handler_refresh_obligation_farms_for_reserve;
 pub mod handler_refresh_reserve;
 pub mod handler_refresh_reserves_batch;
+pub mod handler_reinvest_protocol_fees;
 pub mod handler_repay_and_withdraw_redeem;
 pub mod handler_repay_obligation_liquidity;
 pub mod handler_request_elevation_group;
+pub mod handler_set_obligation_protection_order;
 pub mod handler_socialize_loss;
+pub mod handler_swap_collateral;
 pub mod handler_update_lending_market;
 pub mod handler_update_lending_market_owner;
+pub mod handler_update_obligation_delegate;
 pub mod handler_update_reserve_config;
 pub mod handler_withdraw_obligation_collateral;
 pub mod handler_withdraw_obligation_collateral_and_redeem_reserve_collateral;
+pub mod handler_withdraw_obligation_collateral_and_redeem_reserve_collateral_native;
 pub mod handler_withdraw_protocol_fees;
 pub mod handler_withdraw_referrer_fees;
 
+pub use handler_accept_obligation_owner::*;
+pub use handler_adjust_leverage::*;
 pub use handler_borrow_obligation_liquidity::*;
+pub use handler_borrow_obligation_liquidity_native::*;
+pub use handler_close_obligation::*;
+pub use handler_close_referrer_token_state::*;
+pub use handler_close_user_metadata::*;
 pub use handler_delete_referrer_state_and_short_url::*;
 pub use handler_deposit_and_withdraw::*;
 pub use handler_deposit_obligation_collateral::*;
 pub use handler_deposit_reserve_liquidity::*;
 pub use handler_deposit_reserve_liquidity_and_obligation_collateral::*;
+pub use handler_deposit_reserve_liquidity_and_obligation_collateral_native::*;
+pub use handler_deposit_reserve_liquidity_native::*;
+pub use handler_distribute_protocol_fees::*;
+pub use handler_donate_to_reserve::*;
 pub use handler_flash_borrow_reserve_liquidity::*;
 pub use handler_flash_repay_reserve_liquidity::*;
+pub use handler_get_best_elevation_group::*;
 pub use handler_init_farms_for_reserve::*;
 pub use handler_init_lending_market::*;
 pub use handler_init_obligation::*;
@@ -52,20 +84,30 @@ pub use handler_init_reserve::*;
 pub use handler_init_user_metadata::*;
 pub use handler_liquidate_obligation_and_redeem_reserve_collateral::*;
 pub use handler_mark_obligation_for_deleveraging::*;
+pub use handler_move_obligation_collateral::*;
+pub use handler_move_obligation_debt::*;
+pub use handler_propose_obligation_owner::*;
 pub use handler_redeem_fees::*;
 pub use handler_redeem_reserve_collateral::*;
+pub use handler_redeem_reserve_collateral_native::*;
+pub use handler_refinance_debt::*;
 pub use handler_refresh_obligation::*;
 pub use handler_refresh_obligation_farms_for_reserve::*;
 pub use handler_refresh_reserve::*;
 pub use handler_refresh_reserves_batch::*;
+pub use handler_reinvest_protocol_fees::*;
 pub use handler_repay_and_withdraw_redeem::*;
 pub use handler_repay_obligation_liquidity::*;
 pub use handler_request_elevation_group::*;
+pub use handler_set_obligation_protection_order::*;
 pub use handler_socialize_loss::*;
+pub use handler_swap_collateral::*;
 pub use handler_update_lending_market::*;
 pub use handler_update_lending_market_owner::*;
+pub use handler_update_obligation_delegate::*;
 pub use handler_update_reserve_config::*;
 pub use handler_withdraw_obligation_collateral::*;
 pub use handler_withdraw_obligation_collateral_and_redeem_reserve_collateral::*;
+pub use handler_withdraw_obligation_collateral_and_redeem_reserve_collateral_native::*;
 pub use handler_withdraw_protocol_fees::*;
 pub use handler_withdraw_referrer_fees::*;
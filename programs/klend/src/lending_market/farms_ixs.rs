@@ -141,3 +141,34 @@ pub fn cpi_set_stake_delegated(
     )
     .map_err(Into::into)
 }
+
+pub fn cpi_transfer_ownership<'info>(
+    farms_program: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    obligation_farm_user_state: &AccountInfo<'info>,
+    new_owner: Pubkey,
+) -> Result<()> {
+    let accounts = farms::accounts::TransferOwnership {
+        owner: owner.key(),
+        user_state: obligation_farm_user_state.key(),
+    }
+    .to_account_metas(None);
+
+    let data = farms::instruction::TransferOwnership { new_owner }.data();
+
+    let instruction = Instruction {
+        program_id: farms_program.key(),
+        accounts,
+        data,
+    };
+
+    program::invoke(
+        &instruction,
+        &[
+            owner.clone(),
+            obligation_farm_user_state.clone(),
+            farms_program.clone(),
+        ],
+    )
+    .map_err(Into::into)
+}
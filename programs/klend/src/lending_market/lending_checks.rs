@@ -1,9 +1,12 @@
+use std::cell::RefMut;
+
 use anchor_lang::{
     accounts::account_loader::AccountLoader,
     err, error,
-    prelude::{msg, Context, Pubkey},
-    require_eq, require_gte, Key, Result, ToAccountInfo,
+    prelude::{msg, AccountInfo, Clock, Context, Pubkey, SolanaSysvar},
+    require_eq, require_gte, require_keys_eq, Key, Result, ToAccountInfo,
 };
+use farms::state::UserState as FarmsUserState;
 
 use crate::{
     handlers::*,
@@ -13,9 +16,12 @@ use crate::{
         WithdrawObligationCollateralAndRedeemReserveCollateralAccounts,
     },
     utils::{
-        constraints, seeds::BASE_SEED_REFERRER_TOKEN_STATE, FatAccountLoader, PROGRAM_VERSION,
+        constraints,
+        seeds::{pda, BASE_SEED_REFERRER_TOKEN_STATE},
+        FatAccountLoader, PROGRAM_VERSION,
     },
-    LendingAction, LendingError, Obligation, ReferrerTokenState, Reserve, ReserveStatus,
+    LendingAction, LendingError, Obligation, PriceStatusFlags, ReferrerTokenState, Reserve,
+    ReserveAction, ReserveStatus,
 };
 
 pub fn borrow_obligation_liquidity_checks(accounts: &BorrowObligationLiquidity) -> Result<()> {
@@ -41,6 +47,7 @@ pub fn borrow_obligation_liquidity_checks(accounts: &BorrowObligationLiquidity)
     constraints::token_2022::validate_liquidity_token_extensions(
         &accounts.borrow_reserve_liquidity_mint.to_account_info(),
         &accounts.user_destination_liquidity.to_account_info(),
+        &borrow_reserve.config.transfer_hook_program,
     )?;
 
     Ok(())
@@ -96,6 +103,7 @@ pub fn deposit_reserve_liquidity_checks(
     constraints::token_2022::validate_liquidity_token_extensions(
         &accounts.reserve_liquidity_mint.to_account_info(),
         &accounts.user_source_liquidity.to_account_info(),
+        &reserve.config.transfer_hook_program,
     )?;
 
     Ok(())
@@ -124,6 +132,7 @@ pub fn deposit_reserve_liquidity_and_obligation_collateral_checks(
     constraints::token_2022::validate_liquidity_token_extensions(
         &accounts.reserve_liquidity_mint.to_account_info(),
         &accounts.user_source_liquidity.to_account_info(),
+        &reserve.config.transfer_hook_program,
     )?;
 
     Ok(())
@@ -168,11 +177,13 @@ pub fn liquidate_obligation_checks(
     constraints::token_2022::validate_liquidity_token_extensions(
         &accounts.repay_reserve_liquidity_mint.to_account_info(),
         &accounts.user_source_liquidity.to_account_info(),
+        &repay_reserve.config.transfer_hook_program,
     )?;
 
     constraints::token_2022::validate_liquidity_token_extensions(
         &accounts.withdraw_reserve_liquidity_mint.to_account_info(),
         &accounts.user_destination_liquidity.to_account_info(),
+        &withdraw_reserve.config.transfer_hook_program,
     )?;
 
     Ok(())
@@ -198,6 +209,7 @@ pub fn redeem_reserve_collateral_checks(accounts: &RedeemReserveCollateralAccoun
     constraints::token_2022::validate_liquidity_token_extensions(
         &accounts.reserve_liquidity_mint.to_account_info(),
         &accounts.user_destination_liquidity.to_account_info(),
+        &reserve.config.transfer_hook_program,
     )?;
 
     Ok(())
@@ -221,6 +233,7 @@ pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral_checks(
     constraints::token_2022::validate_liquidity_token_extensions(
         &accounts.reserve_liquidity_mint.to_account_info(),
         &accounts.user_destination_liquidity.to_account_info(),
+        &withdraw_reserve.config.transfer_hook_program,
     )?;
 
     Ok(())
@@ -242,6 +255,7 @@ pub fn repay_obligation_liquidity_checks(accounts: &RepayObligationLiquidity) ->
     constraints::token_2022::validate_liquidity_token_extensions(
         &accounts.reserve_liquidity_mint.to_account_info(),
         &accounts.user_source_liquidity.to_account_info(),
+        &repay_reserve.config.transfer_hook_program,
     )?;
 
     Ok(())
@@ -291,9 +305,26 @@ pub fn flash_borrow_reserve_liquidity_checks(
         return err!(LendingError::FlashLoansDisabled);
     }
 
+    let required_price_status = reserve
+        .config
+        .required_price_status
+        .for_action(ReserveAction::FlashLoan);
+    if required_price_status != PriceStatusFlags::NONE
+        && reserve
+            .last_update
+            .is_stale(Clock::get()?.slot, required_price_status)?
+    {
+        msg!(
+            "Reserve is stale and must be refreshed in the current slot, price status: {:08b}",
+            reserve.last_update.get_price_status().0
+        );
+        return err!(LendingError::ReserveStale);
+    }
+
     constraints::token_2022::validate_liquidity_token_extensions(
         &ctx.accounts.reserve_liquidity_mint.to_account_info(),
         &ctx.accounts.user_destination_liquidity.to_account_info(),
+        &reserve.config.transfer_hook_program,
     )?;
 
     Ok(())
@@ -342,6 +373,79 @@ pub fn refresh_obligation_farms_for_reserve_checks(
     Ok(())
 }
 
+pub fn obligation_farm_user_states<'a, 'info>(
+    obligation: &Obligation,
+    obligation_key: Pubkey,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<Vec<&'a AccountInfo<'info>>> {
+    let farm_reserves = obligation.farm_reserves_with_positions();
+    if remaining_accounts.len() < farm_reserves.len() {
+        msg!(
+            "Expected at least {} obligation farm reserves in remaining accounts",
+            farm_reserves.len()
+        );
+        return err!(LendingError::InvalidAccountInput);
+    }
+    let (reserve_infos, user_state_infos) = remaining_accounts.split_at(farm_reserves.len());
+    let mut user_state_infos = user_state_infos.iter();
+
+    let mut obligation_farm_user_states = Vec::new();
+    for (farm_reserve, reserve_info) in farm_reserves.iter().zip(reserve_infos) {
+        require_keys_eq!(
+            reserve_info.key(),
+            *farm_reserve,
+            LendingError::InvalidAccountInput
+        );
+        let reserve_loader = FatAccountLoader::<Reserve>::try_from(reserve_info)?;
+        let reserve = reserve_loader.load()?;
+        require_keys_eq!(
+            reserve.lending_market,
+            obligation.lending_market,
+            LendingError::InvalidAccountInput
+        );
+
+        for farm in [reserve.farm_collateral, reserve.farm_debt] {
+            if farm == Pubkey::default() {
+                continue;
+            }
+
+            let expected_user_state = pda::obligation_farm_user_state(&farm, &obligation_key);
+            let Some(user_state_info) = user_state_infos.next() else {
+                msg!(
+                    "Missing obligation farm user state {} of reserve {}",
+                    expected_user_state,
+                    farm_reserve
+                );
+                return err!(LendingError::InvalidAccountInput);
+            };
+            require_keys_eq!(
+                user_state_info.key(),
+                expected_user_state,
+                LendingError::InvalidAccountInput
+            );
+
+            if user_state_info.data_is_empty() {
+                continue;
+            }
+
+            let user_state = AccountLoader::<FarmsUserState>::try_from(user_state_info)?;
+            require_keys_eq!(
+                user_state.load()?.delegatee,
+                obligation_key,
+                LendingError::InvalidAccountInput
+            );
+            obligation_farm_user_states.push(user_state_info);
+        }
+    }
+
+    if user_state_infos.next().is_some() {
+        msg!("Unexpected obligation farm user states in remaining accounts");
+        return err!(LendingError::InvalidAccountInput);
+    }
+
+    Ok(obligation_farm_user_states)
+}
+
 pub fn initial_liquidation_reserve_liquidity_available_amount(
     repay_reserve: &AccountLoader<Reserve>,
     withdraw_reserve: &AccountLoader<Reserve>,
@@ -477,3 +581,33 @@ pub fn validate_referrer_token_state(
 
     Ok(())
 }
+
+pub fn supply_fee_referrer_token_state<'a, 'info>(
+    referrer_token_state_loader: &'a Option<AccountLoader<'info, ReferrerTokenState>>,
+    referrer: Pubkey,
+    charges_supply_fee: bool,
+    mint: Pubkey,
+    reserve_key: Pubkey,
+) -> Result<Option<RefMut<'a, ReferrerTokenState>>> {
+    if referrer == Pubkey::default() || !charges_supply_fee {
+        return Ok(None);
+    }
+
+    match referrer_token_state_loader {
+        Some(referrer_token_state_loader) => {
+            let referrer_token_state = referrer_token_state_loader.load_mut()?;
+
+            validate_referrer_token_state(
+                &crate::ID,
+                &referrer_token_state,
+                referrer_token_state_loader.key(),
+                mint,
+                referrer,
+                reserve_key,
+            )?;
+
+            Ok(Some(referrer_token_state))
+        }
+        None => err!(LendingError::ReferrerAccountMissing),
+    }
+}
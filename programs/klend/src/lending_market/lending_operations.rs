@@ -7,16 +7,19 @@ use std::{
 use anchor_lang::{err, prelude::*, require, solana_program::clock::Slot, Result};
 use borsh::BorshDeserialize;
 use solana_program::clock::{self, Clock};
+use strum::IntoEnumIterator;
 
 use self::utils::{
+    calculate_collateral_market_value_from_liquidity_amount,
+    calculate_debt_market_value_from_liquidity_amount,
     calculate_obligation_collateral_market_value, calculate_obligation_liquidity_market_value,
     check_elevation_group_borrowing_enabled, check_non_elevation_group_borrowing_enabled,
     check_obligation_collateral_deposit_reserve, check_obligation_fully_refreshed_and_not_null,
     check_obligation_liquidity_borrow_reserve, check_same_elevation_group, get_elevation_group,
     get_max_ltv_and_liquidation_threshold, post_borrow_obligation_invariants,
-    post_deposit_obligation_invariants, post_repay_obligation_invariants,
-    post_withdraw_obligation_invariants, update_elevation_group_debt_trackers_on_repay,
-    validate_obligation_asset_tiers,
+    post_deposit_obligation_invariants, post_refinance_debt_obligation_invariants,
+    post_repay_obligation_invariants, post_withdraw_obligation_invariants,
+    update_elevation_group_debt_trackers_on_repay, validate_obligation_asset_tiers,
 };
 use super::{
     validate_referrer_token_state,
@@ -31,14 +34,17 @@ use crate::{
         CalculateRepayResult, Reserve,
     },
     utils::{
-        borrow_rate_curve::BorrowRateCurve, consts::NO_DELEVERAGING_MARKER, AnyAccountLoader,
-        BigFraction, Fraction, GetPriceResult, ELEVATION_GROUP_NONE, PROGRAM_VERSION,
+        borrow_rate_curve::{BorrowRateCurve, BorrowRateCurveExtension},
+        consts::NO_DELEVERAGING_MARKER,
+        AnyAccountLoader, BigFraction, Fraction, GetPriceResult, ELEVATION_GROUP_NONE, FULL_BPS,
+        MAX_NUM_ELEVATION_GROUPS, PROGRAM_VERSION,
     },
-    xmsg, AssetTier, ElevationGroup, LendingError, LendingMarket, LiquidateAndRedeemResult,
+    xmsg, AssetTier, ElevationGroup, ElevationGroupDebtAmounts, ElevationGroupDebtKind,
+    FeeCalculation, LendingError, LendingMarket, LeverageAdjustment, LiquidateAndRedeemResult,
     LiquidateObligationResult, LtvMaxWithdrawalCheck, MaxReservesAsCollateralCheck,
-    ObligationCollateral, PriceStatusFlags, ReferrerTokenState, RefreshObligationBorrowsResult,
-    RefreshObligationDepositsResult, ReserveConfig, ReserveStatus, UpdateConfigMode,
-    WithdrawResult,
+    ObligationCollateral, OracleSlotConfiguration, PriceStatusFlags, ReferrerTokenState,
+    RefreshObligationBorrowsResult, RefreshObligationDepositsResult, ReserveAction, ReserveConfig,
+    ReserveRequiredPriceStatus, ReserveStatus, UpdateConfigMode, WithdrawResult,
 };
 use crate::{utils::zip_and_validate_same_length, DepositLiquidityResult};
 
@@ -51,16 +57,29 @@ pub fn refresh_reserve(
     let slot = clock.slot;
 
     reserve.accrue_interest(slot, referral_fee_bps)?;
+    reserve.update_utilization_ema(u64::try_from(clock.unix_timestamp).unwrap());
 
     let price_status = if let Some(GetPriceResult {
         price,
+        confidence,
         status,
         timestamp,
     }) = price
     {
         reserve.liquidity.market_price_sf = price.to_bits();
+        reserve.liquidity.market_price_confidence_sf = confidence.to_bits();
         reserve.liquidity.market_price_last_updated_ts = timestamp;
 
+        if status.contains(
+            PriceStatusFlags::PRICE_LOADED
+                | PriceStatusFlags::PRICE_AGE_CHECKED
+                | PriceStatusFlags::HEURISTIC_CHECKED,
+        ) {
+            reserve.update_price_ema(price, timestamp);
+            reserve
+                .update_price_circuit_breaker(price, u64::try_from(clock.unix_timestamp).unwrap());
+        }
+
         Some(status)
     } else if !is_saved_price_age_valid(reserve, clock.unix_timestamp) {
         Some(PriceStatusFlags::empty())
@@ -71,7 +90,6 @@ pub fn refresh_reserve(
     reserve.last_update.update_slot(slot, price_status);
 
     reserve.config.reserved_2 = [0; 2];
-    reserve.config.reserved_3 = [0; 8];
 
     Ok(())
 }
@@ -109,22 +127,37 @@ pub fn deposit_reserve_liquidity(
     reserve: &mut Reserve,
     clock: &Clock,
     liquidity_amount: u64,
+    referral_fee_bps: u16,
+    referrer_token_state: Option<RefMut<ReferrerTokenState>>,
 ) -> Result<DepositLiquidityResult> {
     if liquidity_amount == 0 {
         msg!("Liquidity amount provided cannot be zero");
         return err!(LendingError::InvalidAmount);
     }
 
+    let required_price_status = reserve
+        .config
+        .required_price_status
+        .for_action(ReserveAction::Deposit);
     if reserve
         .last_update
-        .is_stale(clock.slot, PriceStatusFlags::NONE)?
+        .is_stale(clock.slot, required_price_status)?
     {
         msg!("Reserve is stale and must be refreshed in the current slot");
         return err!(LendingError::ReserveStale);
     }
 
+    let (deposit_protocol_fee, deposit_referrer_fee) =
+        reserve.config.supply_fees.calculate_deposit_fees(
+            liquidity_amount,
+            FeeCalculation::Inclusive,
+            referral_fee_bps,
+            referrer_token_state.is_some(),
+        )?;
+    let deposit_fee = deposit_protocol_fee + deposit_referrer_fee;
+
     let deposit_result =
-        reserve.compute_depositable_amount_and_minted_collateral(liquidity_amount)?;
+        reserve.compute_depositable_amount_and_minted_collateral(liquidity_amount - deposit_fee)?;
 
     let liquidity_amount_f = Fraction::from(deposit_result.liquidity_amount);
     let deposit_limit_f = Fraction::from(reserve.config.deposit_limit);
@@ -152,9 +185,22 @@ pub fn deposit_reserve_liquidity(
         deposit_result.collateral_amount,
     )?;
 
+    if deposit_fee > 0 {
+        msg!("Charging deposit fee {}", deposit_fee);
+        add_supply_fees(
+            reserve,
+            deposit_protocol_fee,
+            deposit_referrer_fee,
+            referrer_token_state,
+        )?;
+    }
+
     reserve.last_update.mark_stale();
 
-    Ok(deposit_result)
+    Ok(DepositLiquidityResult {
+        liquidity_amount: deposit_result.liquidity_amount + deposit_fee,
+        collateral_amount: deposit_result.collateral_amount,
+    })
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -168,6 +214,105 @@ pub fn borrow_obligation_liquidity<'info, T>(
     referrer_token_state: Option<RefMut<ReferrerTokenState>>,
     deposit_reserves_iter: impl Iterator<Item = T>,
 ) -> Result<CalculateBorrowResult>
+where
+    T: AnyAccountLoader<'info, Reserve>,
+{
+    borrow_obligation_liquidity_impl(
+        lending_market,
+        borrow_reserve,
+        obligation,
+        liquidity_amount,
+        clock,
+        borrow_reserve_pk,
+        referrer_token_state,
+        deposit_reserves_iter,
+        BorrowCapacityCheck::RemainingBorrowValue,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity_for_refinance<'info, T>(
+    lending_market: &LendingMarket,
+    borrow_reserve: &mut Reserve,
+    obligation: &mut Obligation,
+    liquidity_amount: u64,
+    clock: &Clock,
+    borrow_reserve_pk: Pubkey,
+    repay_reserve_pk: Pubkey,
+    referrer_token_state: Option<RefMut<ReferrerTokenState>>,
+    deposit_reserves_iter: impl Iterator<Item = T>,
+) -> Result<CalculateBorrowResult>
+where
+    T: AnyAccountLoader<'info, Reserve>,
+{
+    if borrow_reserve_pk == repay_reserve_pk {
+        msg!("Cannot refinance a debt into the same reserve");
+        return err!(LendingError::InvalidAccountInput);
+    }
+
+    borrow_obligation_liquidity_impl(
+        lending_market,
+        borrow_reserve,
+        obligation,
+        liquidity_amount,
+        clock,
+        borrow_reserve_pk,
+        referrer_token_state,
+        deposit_reserves_iter,
+        BorrowCapacityCheck::IncludingRefinancedDebt(repay_reserve_pk),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity_for_leverage<'info, T>(
+    lending_market: &LendingMarket,
+    borrow_reserve: &mut Reserve,
+    obligation: &mut Obligation,
+    liquidity_amount: u64,
+    clock: &Clock,
+    borrow_reserve_pk: Pubkey,
+    referrer_token_state: Option<RefMut<ReferrerTokenState>>,
+    deposit_reserves_iter: impl Iterator<Item = T>,
+) -> Result<CalculateBorrowResult>
+where
+    T: AnyAccountLoader<'info, Reserve>,
+{
+    if liquidity_amount == u64::MAX {
+        msg!("Leverage borrow amount must be explicit");
+        return err!(LendingError::InvalidAmount);
+    }
+
+    borrow_obligation_liquidity_impl(
+        lending_market,
+        borrow_reserve,
+        obligation,
+        liquidity_amount,
+        clock,
+        borrow_reserve_pk,
+        referrer_token_state,
+        deposit_reserves_iter,
+        BorrowCapacityCheck::DeferredToFinalHealthCheck,
+    )
+}
+
+enum BorrowCapacityCheck {
+    RemainingBorrowValue,
+    IncludingRefinancedDebt(Pubkey),
+    DeferredToFinalHealthCheck,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn borrow_obligation_liquidity_impl<'info, T>(
+    lending_market: &LendingMarket,
+    borrow_reserve: &mut Reserve,
+    obligation: &mut Obligation,
+    liquidity_amount: u64,
+    clock: &Clock,
+    borrow_reserve_pk: Pubkey,
+    referrer_token_state: Option<RefMut<ReferrerTokenState>>,
+    deposit_reserves_iter: impl Iterator<Item = T>,
+    capacity_check: BorrowCapacityCheck,
+) -> Result<CalculateBorrowResult>
 where
     T: AnyAccountLoader<'info, Reserve>,
 {
@@ -176,9 +321,14 @@ where
         return err!(LendingError::InvalidAmount);
     }
 
+    let required_price_status = borrow_reserve
+        .config
+        .required_price_status
+        .for_action(ReserveAction::Borrow);
+
     if borrow_reserve
         .last_update
-        .is_stale(clock.slot, PriceStatusFlags::ALL_CHECKS)?
+        .is_stale(clock.slot, required_price_status)?
     {
         msg!(
             "Borrow reserve is stale and must be refreshed in the current slot, price_status: {:08b}",
@@ -192,6 +342,11 @@ where
         return err!(LendingError::BorrowingDisabled);
     }
 
+    if borrow_reserve.is_price_circuit_breaker_tripped() {
+        msg!("Borrow reserve price circuit breaker is tripped");
+        return err!(LendingError::PriceCircuitBreakerTripped);
+    }
+
     obligation.check_not_marked_for_deleveraging()?;
 
     let current_utilization = borrow_reserve.liquidity.utilization_rate();
@@ -208,9 +363,22 @@ where
         );
         return err!(LendingError::BorrowLimitExceeded);
     }
-    check_obligation_fully_refreshed_and_not_null(obligation, clock.slot)?;
+    check_obligation_fully_refreshed_and_not_null(
+        obligation,
+        clock.slot,
+        PriceStatusFlags::ALL_CHECKS,
+    )?;
 
-    let remaining_borrow_value = obligation.remaining_borrow_value();
+    let remaining_borrow_value = match capacity_check {
+        BorrowCapacityCheck::RemainingBorrowValue => obligation.remaining_borrow_value(),
+        BorrowCapacityCheck::IncludingRefinancedDebt(refinanced_reserve_pk) => {
+            let (refinanced_liquidity, _) =
+                obligation.find_liquidity_in_borrows(refinanced_reserve_pk)?;
+            obligation.remaining_borrow_value()
+                + Fraction::from_bits(refinanced_liquidity.borrow_factor_adjusted_market_value_sf)
+        }
+        BorrowCapacityCheck::DeferredToFinalHealthCheck => Fraction::MAX,
+    };
     if remaining_borrow_value == Fraction::ZERO {
         msg!("Remaining borrow value is zero");
         return err!(LendingError::BorrowTooLarge);
@@ -221,6 +389,11 @@ where
     check_elevation_group_borrowing_enabled(lending_market, obligation)?;
     check_non_elevation_group_borrowing_enabled(obligation)?;
 
+    if obligation.borrowing_disabled_by_price_circuit_breaker > 0 {
+        msg!("Obligation collateral price circuit breaker is tripped");
+        return err!(LendingError::PriceCircuitBreakerTripped);
+    }
+
     let remaining_reserve_capacity = borrow_limit_f.saturating_sub(reserve_liquidity_borrowed_f);
 
     if remaining_reserve_capacity == Fraction::ZERO {
@@ -287,7 +460,12 @@ where
     }
 
     obligation.has_debt = 1;
-    obligation.last_update.mark_stale();
+    if !matches!(
+        capacity_check,
+        BorrowCapacityCheck::DeferredToFinalHealthCheck
+    ) {
+        obligation.last_update.mark_stale();
+    }
 
     let new_utilization_rate = borrow_reserve.liquidity.utilization_rate();
     let utilization_limit = borrow_reserve
@@ -303,6 +481,20 @@ where
         return err!(LendingError::BorrowingAboveUtilizationRateDisabled);
     }
 
+    let borrow_rate_curve_extension = borrow_reserve.config.borrow_rate_curve_extension;
+    if borrow_rate_curve_extension.is_borrow_rate_capped() {
+        let new_borrow_rate = borrow_reserve.borrow_rate(new_utilization_rate)?;
+        let max_borrow_rate = Fraction::from_bps(borrow_rate_curve_extension.max_borrow_rate_bps);
+        if new_borrow_rate > max_borrow_rate {
+            msg!(
+                "Borrowing above max borrow rate is disabled, new {}, limit {}",
+                new_borrow_rate.to_display(),
+                max_borrow_rate.to_display()
+            );
+            return err!(LendingError::BorrowingAboveMaxBorrowRateDisabled);
+        }
+    }
+
     validate_obligation_asset_tiers(obligation)?;
 
     let elevation_group = lending_market.get_elevation_group(obligation.elevation_group)?;
@@ -316,13 +508,15 @@ where
         deposit_reserves_iter,
     )?;
 
-    post_borrow_obligation_invariants(
-        borrow_amount_f,
-        obligation,
-        borrow_reserve,
-        Fraction::from_bits(obligation.borrows[borrow_index].market_value_sf),
-        Fraction::from_bits(lending_market.min_net_value_in_obligation_sf),
-    )?;
+    if matches!(capacity_check, BorrowCapacityCheck::RemainingBorrowValue) {
+        post_borrow_obligation_invariants(
+            borrow_amount_f,
+            obligation,
+            borrow_reserve,
+            Fraction::from_bits(obligation.borrows[borrow_index].market_value_sf),
+            Fraction::from_bits(lending_market.min_net_value_in_obligation_sf),
+        )?;
+    }
 
     Ok(CalculateBorrowResult {
         borrow_amount_f,
@@ -346,9 +540,14 @@ pub fn deposit_obligation_collateral(
         return err!(LendingError::InvalidAmount);
     }
 
+    let required_price_status = deposit_reserve
+        .config
+        .required_price_status
+        .for_action(ReserveAction::Deposit);
+
     if deposit_reserve
         .last_update
-        .is_stale(slot, PriceStatusFlags::NONE)?
+        .is_stale(slot, required_price_status)?
     {
         msg!("Deposit reserve is stale and must be refreshed in the current slot");
         return err!(LendingError::ReserveStale);
@@ -380,12 +579,14 @@ pub fn deposit_obligation_collateral(
     }
 
     let pre_deposit_count = obligation.deposits_count();
-    let total_borrowed_amount = obligation.get_borrowed_amount_if_single_token();
+    let borrowed_amounts_in_elevation_group = elevation_group
+        .map(|elevation_group| obligation.get_borrowed_amounts_in_elevation_group(elevation_group))
+        .unwrap_or_default();
     let asset_tier = deposit_reserve.config.get_asset_tier();
 
     let new_deposit_initializer = |obligation_collateral: &mut ObligationCollateral| -> Result<()> {
         utils::update_elevation_group_debt_trackers_on_new_deposit(
-            total_borrowed_amount,
+            borrowed_amounts_in_elevation_group,
             obligation_collateral,
             pre_deposit_count,
             elevation_group,
@@ -434,55 +635,16 @@ pub fn withdraw_obligation_collateral(
     withdraw_reserve_pk: Pubkey,
     ltv_max_withdrawal_check: LtvMaxWithdrawalCheck,
 ) -> Result<u64> {
-    if collateral_amount == 0 {
-        return err!(LendingError::InvalidAmount);
-    }
-
+    let collateral_index = check_withdraw_obligation_collateral(
+        lending_market,
+        withdraw_reserve,
+        obligation,
+        collateral_amount,
+        slot,
+        withdraw_reserve_pk,
+    )?;
     let is_borrows_empty = obligation.borrows_empty();
-
-    let required_price_status = if is_borrows_empty {
-        PriceStatusFlags::NONE
-    } else {
-        PriceStatusFlags::ALL_CHECKS
-    };
-
-    if withdraw_reserve
-        .last_update
-        .is_stale(slot, required_price_status)?
-    {
-        msg!(
-            "Withdraw reserve is stale and must be refreshed in the current slot, price status: {:08b}",
-            withdraw_reserve.last_update.get_price_status().0
-        );
-        return err!(LendingError::ReserveStale);
-    }
-
-    if obligation
-        .last_update
-        .is_stale(slot, required_price_status)?
-    {
-        msg!(
-            "Obligation is stale and must be refreshed in the current slot, price status: {:08b}",
-            obligation.last_update.get_price_status().0
-        );
-        return err!(LendingError::ObligationStale);
-    }
-
-    let collateral_index = obligation.position_of_collateral_in_deposits(withdraw_reserve_pk)?;
     let collateral = &obligation.deposits[collateral_index];
-    if collateral.deposited_amount == 0 {
-        return err!(LendingError::ObligationCollateralEmpty);
-    }
-
-    if !is_borrows_empty {
-        check_elevation_group_borrowing_enabled(lending_market, obligation)?;
-    }
-
-    if obligation.num_of_obsolete_reserves > 0
-        && withdraw_reserve.config.status() == ReserveStatus::Active
-    {
-        return err!(LendingError::ObligationInDeprecatedReserve);
-    }
 
     let (reserve_max_ltv_pct, reserve_liq_threshold_ltv_pct) =
         get_max_ltv_and_liquidation_threshold(
@@ -553,8 +715,7 @@ pub fn withdraw_obligation_collateral(
         withdraw_amount
     };
 
-    let previous_debt_in_elevation_group =
-        collateral.borrowed_amount_against_this_collateral_in_elevation_group;
+    let previous_debt_in_elevation_group = collateral.get_borrowed_amounts_in_elevation_group();
     let is_full_withdrawal = obligation.withdraw(withdraw_amount, collateral_index)?;
     obligation.last_update.mark_stale();
 
@@ -577,7 +738,547 @@ pub fn withdraw_obligation_collateral(
         Fraction::from_bits(lending_market.min_net_value_in_obligation_sf),
     )?;
 
-    Ok(withdraw_amount)
+    Ok(withdraw_amount)
+}
+
+fn check_withdraw_obligation_collateral(
+    lending_market: &LendingMarket,
+    withdraw_reserve: &Reserve,
+    obligation: &Obligation,
+    collateral_amount: u64,
+    slot: Slot,
+    withdraw_reserve_pk: Pubkey,
+) -> Result<usize> {
+    if collateral_amount == 0 {
+        return err!(LendingError::InvalidAmount);
+    }
+
+    let is_borrows_empty = obligation.borrows_empty();
+
+    let (required_price_status, obligation_required_price_status) = if is_borrows_empty {
+        (PriceStatusFlags::NONE, PriceStatusFlags::NONE)
+    } else {
+        (
+            withdraw_reserve
+                .config
+                .required_price_status
+                .for_action(ReserveAction::Withdraw),
+            PriceStatusFlags::ALL_CHECKS,
+        )
+    };
+
+    if withdraw_reserve
+        .last_update
+        .is_stale(slot, required_price_status)?
+    {
+        msg!(
+            "Withdraw reserve is stale and must be refreshed in the current slot, price status: {:08b}",
+            withdraw_reserve.last_update.get_price_status().0
+        );
+        return err!(LendingError::ReserveStale);
+    }
+
+    if obligation
+        .last_update
+        .is_stale(slot, obligation_required_price_status)?
+    {
+        msg!(
+            "Obligation is stale and must be refreshed in the current slot, price status: {:08b}",
+            obligation.last_update.get_price_status().0
+        );
+        return err!(LendingError::ObligationStale);
+    }
+
+    let collateral_index = obligation.position_of_collateral_in_deposits(withdraw_reserve_pk)?;
+    if obligation.deposits[collateral_index].deposited_amount == 0 {
+        return err!(LendingError::ObligationCollateralEmpty);
+    }
+
+    if !is_borrows_empty {
+        check_elevation_group_borrowing_enabled(lending_market, obligation)?;
+    }
+
+    if obligation.num_of_obsolete_reserves > 0
+        && withdraw_reserve.config.status() == ReserveStatus::Active
+    {
+        return err!(LendingError::ObligationInDeprecatedReserve);
+    }
+
+    Ok(collateral_index)
+}
+
+pub fn withdraw_obligation_collateral_for_swap(
+    lending_market: &LendingMarket,
+    withdraw_reserve: &mut Reserve,
+    swap_target_reserve: &Reserve,
+    obligation: &mut Obligation,
+    collateral_amount: u64,
+    slot: Slot,
+    withdraw_reserve_pk: Pubkey,
+) -> Result<(u64, Fraction)> {
+    if withdraw_reserve.version != PROGRAM_VERSION as u64
+        || swap_target_reserve.version != PROGRAM_VERSION as u64
+    {
+        msg!("Reserve version does not match the program version");
+        return err!(LendingError::ReserveDeprecated);
+    }
+
+    if swap_target_reserve
+        .last_update
+        .is_stale(slot, PriceStatusFlags::ALL_CHECKS)?
+    {
+        msg!("Swap target reserve must be refreshed in the current slot");
+        return err!(LendingError::ReserveStale);
+    }
+
+    let collateral_index = check_withdraw_obligation_collateral(
+        lending_market,
+        withdraw_reserve,
+        obligation,
+        collateral_amount,
+        slot,
+        withdraw_reserve_pk,
+    )?;
+    let collateral = &obligation.deposits[collateral_index];
+
+    let withdraw_amount = collateral_amount.min(collateral.deposited_amount);
+    let withdraw_value = Fraction::from_bits(collateral.market_value_sf)
+        * Fraction::from(withdraw_amount)
+        / Fraction::from(collateral.deposited_amount);
+
+    let previous_debt_in_elevation_group = collateral.get_borrowed_amounts_in_elevation_group();
+    let is_full_withdrawal = obligation.withdraw(withdraw_amount, collateral_index)?;
+
+    if is_full_withdrawal == WithdrawResult::Full {
+        utils::update_elevation_group_debt_trackers_on_full_withdraw(
+            previous_debt_in_elevation_group,
+            obligation.elevation_group,
+            withdraw_reserve,
+        )?;
+    }
+
+    Ok((withdraw_amount, withdraw_value))
+}
+
+pub fn post_swap_collateral_obligation_invariants(
+    lending_market: &LendingMarket,
+    obligation: &Obligation,
+    withdraw_reserve: &Reserve,
+    deposit_reserve: &Reserve,
+    withdraw_value: Fraction,
+    deposit_collateral_amount: u64,
+) -> Result<()> {
+    let deposit_value = calculate_collateral_market_value_from_liquidity_amount(
+        deposit_reserve,
+        deposit_reserve
+            .collateral_exchange_rate()
+            .fraction_collateral_to_liquidity(Fraction::from(deposit_collateral_amount)),
+    );
+
+    let min_deposit_value = withdraw_value
+        * Fraction::from_bps(
+            u64::from(FULL_BPS).saturating_sub(lending_market.max_swap_collateral_slippage_bps),
+        );
+    if deposit_value < min_deposit_value {
+        msg!(
+            "Swapped collateral value {} is lower than the minimum accepted {} for a withdrawn value {}",
+            deposit_value.to_display(),
+            min_deposit_value.to_display(),
+            withdraw_value.to_display()
+        );
+        return err!(LendingError::SwapCollateralSlippageExceeded);
+    }
+
+    if obligation.borrow_factor_adjusted_debt_value_sf == 0 {
+        return Ok(());
+    }
+
+    let elevation_group = get_elevation_group(obligation.elevation_group, lending_market)?;
+    let (withdraw_max_ltv_pct, _) =
+        get_max_ltv_and_liquidation_threshold(withdraw_reserve, elevation_group);
+    let (deposit_max_ltv_pct, _) =
+        get_max_ltv_and_liquidation_threshold(deposit_reserve, elevation_group);
+
+    let new_allowed_borrow_value = (Fraction::from_bits(obligation.allowed_borrow_value_sf)
+        + deposit_value * Fraction::from_percent(deposit_max_ltv_pct))
+    .saturating_sub(withdraw_value * Fraction::from_percent(withdraw_max_ltv_pct));
+    let borrow_factor_adjusted_debt_value =
+        Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf);
+
+    if borrow_factor_adjusted_debt_value > new_allowed_borrow_value {
+        msg!(
+            "Obligation debt value {} exceeds the allowed borrow value {} after the collateral swap",
+            borrow_factor_adjusted_debt_value.to_display(),
+            new_allowed_borrow_value.to_display()
+        );
+        return err!(LendingError::SwapCollateralLtvExceeded);
+    }
+
+    check_ltv_not_worse_if_marked_for_deleveraging(
+        obligation,
+        obligation.loan_to_value(),
+        borrow_factor_adjusted_debt_value,
+        (Fraction::from_bits(obligation.deposited_value_sf) + deposit_value)
+            .saturating_sub(withdraw_value),
+    )?;
+
+    Ok(())
+}
+
+pub fn calculate_leverage_adjustment(
+    obligation: &Obligation,
+    collateral_reserve: &Reserve,
+    debt_reserve: &Reserve,
+    collateral_reserve_pk: Pubkey,
+    target_ltv_pct: u8,
+    slot: Slot,
+) -> Result<LeverageAdjustment> {
+    if target_ltv_pct >= 100 {
+        msg!("Target LTV {}% must be lower than 100%", target_ltv_pct);
+        return err!(LendingError::LeverageTargetLtvInvalid);
+    }
+
+    check_obligation_fully_refreshed_and_not_null(obligation, slot, PriceStatusFlags::ALL_CHECKS)?;
+
+    let deposited_value = Fraction::from_bits(obligation.deposited_value_sf);
+    let debt_value = Fraction::from_bits(obligation.borrowed_assets_market_value_sf);
+    let net_value = deposited_value.saturating_sub(debt_value);
+
+    let target_ltv = Fraction::from_percent(target_ltv_pct);
+    let target_debt_value = net_value * target_ltv / (Fraction::ONE - target_ltv);
+
+    msg!(
+        "Adjusting leverage, debt value {} target debt value {} net value {}",
+        debt_value.to_display(),
+        target_debt_value.to_display(),
+        net_value.to_display()
+    );
+
+    if target_debt_value > debt_value {
+        let decimals = 10u64
+            .checked_pow(debt_reserve.liquidity.mint_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        let borrow_liquidity_amount: u64 = ((target_debt_value - debt_value)
+            * u128::from(decimals)
+            / debt_reserve.liquidity.get_debt_price_f())
+        .to_floor();
+
+        if borrow_liquidity_amount == 0 {
+            msg!("Obligation is already at the target LTV");
+            return err!(LendingError::InvalidAmount);
+        }
+
+        Ok(LeverageAdjustment::Increase {
+            borrow_liquidity_amount,
+        })
+    } else {
+        let collateral = obligation.find_collateral_in_deposits(collateral_reserve_pk)?;
+        let collateral_value = Fraction::from_bits(collateral.market_value_sf);
+        if collateral_value == Fraction::ZERO {
+            return err!(LendingError::ObligationCollateralEmpty);
+        }
+
+        let withdraw_collateral_amount = min(
+            (Fraction::from(collateral.deposited_amount) * (debt_value - target_debt_value)
+                / collateral_value)
+                .to_ceil(),
+            collateral.deposited_amount,
+        );
+
+        if withdraw_collateral_amount == 0 {
+            msg!("Obligation is already at the target LTV");
+            return err!(LendingError::InvalidAmount);
+        }
+
+        Ok(LeverageAdjustment::Decrease {
+            withdraw_collateral_amount,
+        })
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn post_adjust_leverage_obligation_invariants(
+    lending_market: &LendingMarket,
+    obligation: &Obligation,
+    collateral_reserve: &Reserve,
+    debt_reserve: &Reserve,
+    debt_reserve_pk: Pubkey,
+    deposit_liquidity_amount: u64,
+    withdraw_value: Fraction,
+    borrow_amount: Fraction,
+    repay_amount: Fraction,
+) -> Result<()> {
+    let elevation_group = get_elevation_group(obligation.elevation_group, lending_market)?;
+    let (collateral_max_ltv_pct, collateral_liquidation_threshold_pct) =
+        get_max_ltv_and_liquidation_threshold(collateral_reserve, elevation_group);
+    let collateral_max_ltv = Fraction::from_percent(collateral_max_ltv_pct);
+    let collateral_liquidation_threshold =
+        Fraction::from_percent(collateral_liquidation_threshold_pct);
+    let borrow_factor =
+        debt_reserve.borrow_factor_f(obligation.elevation_group != ELEVATION_GROUP_NONE);
+
+    let deposit_value = calculate_collateral_market_value_from_liquidity_amount(
+        collateral_reserve,
+        Fraction::from(deposit_liquidity_amount),
+    );
+    let borrow_value =
+        calculate_debt_market_value_from_liquidity_amount(debt_reserve, borrow_amount);
+    let repay_value = calculate_debt_market_value_from_liquidity_amount(debt_reserve, repay_amount);
+
+    let new_deposited_value = (Fraction::from_bits(obligation.deposited_value_sf) + deposit_value)
+        .saturating_sub(withdraw_value);
+    let new_allowed_borrow_value = (Fraction::from_bits(obligation.allowed_borrow_value_sf)
+        + deposit_value * collateral_max_ltv)
+        .saturating_sub(withdraw_value * collateral_max_ltv);
+
+    let initial_bf_debt_value =
+        Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf);
+    let new_bf_debt_value = (initial_bf_debt_value + borrow_value * borrow_factor)
+        .saturating_sub(repay_value * borrow_factor);
+    let new_debt_value = (Fraction::from_bits(obligation.borrowed_assets_market_value_sf)
+        + borrow_value)
+        .saturating_sub(repay_value);
+
+    if new_debt_value > Fraction::ZERO && new_debt_value >= new_deposited_value {
+        msg!(
+            "Obligation can't have more liabilities than assets after leverage adjustment {}/{}",
+            new_debt_value.to_display(),
+            new_deposited_value.to_display()
+        );
+        return err!(LendingError::LiabilitiesBiggerThanAssets);
+    }
+
+    if new_bf_debt_value > initial_bf_debt_value {
+        if new_bf_debt_value > new_allowed_borrow_value {
+            msg!(
+                "Obligation debt value {} exceeds the allowed borrow value {} after leverage adjustment",
+                new_bf_debt_value.to_display(),
+                new_allowed_borrow_value.to_display()
+            );
+            return err!(LendingError::LeverageLtvExceeded);
+        }
+
+        let new_unhealthy_borrow_value =
+            (Fraction::from_bits(obligation.unhealthy_borrow_value_sf)
+                + deposit_value * collateral_liquidation_threshold)
+                .saturating_sub(withdraw_value * collateral_liquidation_threshold);
+        if new_bf_debt_value > new_unhealthy_borrow_value {
+            msg!(
+                "Obligation debt value {} exceeds the unhealthy borrow value {} after leverage adjustment",
+                new_bf_debt_value.to_display(),
+                new_unhealthy_borrow_value.to_display()
+            );
+            return err!(LendingError::WorseLTVThanUnhealthyLTV);
+        }
+
+        let (debt_liquidity, _) = obligation.find_liquidity_in_borrows(debt_reserve_pk)?;
+        let new_debt_liquidity_value = calculate_debt_market_value_from_liquidity_amount(
+            debt_reserve,
+            Fraction::from_bits(debt_liquidity.borrowed_amount_sf),
+        );
+        let min_accepted_net_value =
+            Fraction::from_bits(lending_market.min_net_value_in_obligation_sf);
+        if new_debt_liquidity_value > 0 && new_debt_liquidity_value < min_accepted_net_value {
+            msg!(
+                "Obligation new borrowed value after leverage adjustment {} for {}",
+                new_debt_liquidity_value.to_display(),
+                debt_reserve.token_symbol()
+            );
+            return err!(LendingError::NetValueRemainingTooSmall);
+        }
+    } else if new_bf_debt_value > Fraction::ZERO {
+        let new_ltv = new_bf_debt_value / new_deposited_value;
+        if new_ltv > obligation.loan_to_value() {
+            msg!(
+                "Obligation LTV {} is worse than {} after deleverage",
+                new_ltv.to_display(),
+                obligation.loan_to_value().to_display()
+            );
+            return err!(LendingError::WorseLTVBlocked);
+        }
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn move_obligation_collateral(
+    lending_market: &LendingMarket,
+    reserve: &mut Reserve,
+    source_obligation: &mut Obligation,
+    destination_obligation: &mut Obligation,
+    collateral_amount: u64,
+    slot: Slot,
+    reserve_pk: Pubkey,
+) -> Result<u64> {
+    let move_amount = withdraw_obligation_collateral(
+        lending_market,
+        reserve,
+        source_obligation,
+        collateral_amount,
+        slot,
+        reserve_pk,
+        LtvMaxWithdrawalCheck::MaxLtv,
+    )?;
+
+    deposit_obligation_collateral(
+        lending_market,
+        reserve,
+        destination_obligation,
+        slot,
+        move_amount,
+        reserve_pk,
+        MaxReservesAsCollateralCheck::Perform,
+    )?;
+
+    Ok(move_amount)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn move_obligation_debt<'info, T>(
+    lending_market: &LendingMarket,
+    borrow_reserve: &mut Reserve,
+    source_obligation: &mut Obligation,
+    destination_obligation: &mut Obligation,
+    liquidity_amount: u64,
+    slot: Slot,
+    borrow_reserve_pk: Pubkey,
+    source_deposit_reserves_iter: impl Iterator<Item = T>,
+    destination_deposit_reserves_iter: impl Iterator<Item = T>,
+) -> Result<Fraction>
+where
+    T: AnyAccountLoader<'info, Reserve>,
+{
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return err!(LendingError::InvalidAmount);
+    }
+
+    if borrow_reserve
+        .last_update
+        .is_stale(slot, PriceStatusFlags::ALL_CHECKS)?
+    {
+        msg!(
+            "Borrow reserve is stale and must be refreshed in the current slot, price status: {:08b}",
+            borrow_reserve.last_update.get_price_status().0
+        );
+        return err!(LendingError::ReserveStale);
+    }
+
+    if source_obligation
+        .last_update
+        .is_stale(slot, PriceStatusFlags::NONE)?
+    {
+        msg!("Source obligation is stale and must be refreshed in the current slot");
+        return err!(LendingError::ObligationStale);
+    }
+
+    check_obligation_fully_refreshed_and_not_null(
+        destination_obligation,
+        slot,
+        PriceStatusFlags::ALL_CHECKS,
+    )?;
+    destination_obligation.check_not_marked_for_deleveraging()?;
+    check_same_elevation_group(destination_obligation, borrow_reserve)?;
+    check_elevation_group_borrowing_enabled(lending_market, destination_obligation)?;
+    check_non_elevation_group_borrowing_enabled(destination_obligation)?;
+
+    let cumulative_borrow_rate_bf =
+        BigFraction::from(borrow_reserve.liquidity.cumulative_borrow_rate_bsf);
+
+    let (source_liquidity, source_liquidity_index) =
+        source_obligation.find_liquidity_in_borrows_mut(borrow_reserve_pk)?;
+    if source_liquidity.borrowed_amount_sf == 0 {
+        msg!("Liquidity borrowed amount is zero");
+        return err!(LendingError::ObligationLiquidityEmpty);
+    }
+    source_liquidity.accrue_interest(cumulative_borrow_rate_bf)?;
+
+    let move_amount_f = min(
+        Fraction::from(liquidity_amount),
+        Fraction::from_bits(source_liquidity.borrowed_amount_sf),
+    );
+    let move_amount = move_amount_f.to_ceil();
+
+    let move_value_bf =
+        calculate_debt_market_value_from_liquidity_amount(borrow_reserve, move_amount_f)
+            * borrow_reserve
+                .borrow_factor_f(destination_obligation.elevation_group != ELEVATION_GROUP_NONE);
+    if move_value_bf > destination_obligation.remaining_borrow_value() {
+        msg!(
+            "Moved debt value {} exceeds the destination remaining borrow value {}",
+            move_value_bf.to_display(),
+            destination_obligation.remaining_borrow_value().to_display()
+        );
+        return err!(LendingError::BorrowTooLarge);
+    }
+
+    let source_elevation_group =
+        lending_market.get_elevation_group(source_obligation.elevation_group)?;
+    update_elevation_group_debt_trackers_on_repay(
+        move_amount,
+        source_obligation,
+        source_liquidity_index,
+        source_elevation_group,
+        borrow_reserve,
+        source_deposit_reserves_iter,
+    )?;
+
+    source_obligation.repay(move_amount_f, source_liquidity_index);
+    source_obligation.update_has_debt();
+    source_obligation.last_update.mark_stale();
+
+    post_repay_obligation_invariants(
+        move_amount_f,
+        source_obligation,
+        borrow_reserve,
+        Fraction::from_bits(source_obligation.borrows[source_liquidity_index].market_value_sf),
+        Fraction::from_bits(lending_market.min_net_value_in_obligation_sf),
+    )?;
+
+    let destination_liquidity_index = {
+        let (destination_liquidity, destination_liquidity_index) = destination_obligation
+            .find_or_add_liquidity_to_borrows(
+                borrow_reserve_pk,
+                cumulative_borrow_rate_bf,
+                borrow_reserve.config.get_asset_tier(),
+            )?;
+
+        destination_liquidity.borrow(move_amount_f);
+
+        destination_liquidity_index
+    };
+
+    destination_obligation.has_debt = 1;
+    destination_obligation.last_update.mark_stale();
+
+    validate_obligation_asset_tiers(destination_obligation)?;
+
+    let elevation_group =
+        lending_market.get_elevation_group(destination_obligation.elevation_group)?;
+    utils::update_elevation_group_debt_trackers_on_borrow(
+        move_amount,
+        destination_obligation,
+        destination_liquidity_index,
+        elevation_group,
+        &borrow_reserve_pk,
+        borrow_reserve,
+        destination_deposit_reserves_iter,
+    )?;
+
+    post_borrow_obligation_invariants(
+        move_amount_f,
+        destination_obligation,
+        borrow_reserve,
+        Fraction::from_bits(
+            destination_obligation.borrows[destination_liquidity_index].market_value_sf,
+        ),
+        Fraction::from_bits(lending_market.min_net_value_in_obligation_sf),
+    )?;
+
+    borrow_reserve.last_update.mark_stale();
+
+    Ok(move_amount_f)
 }
 
 pub fn redeem_reserve_collateral(
@@ -585,6 +1286,8 @@ pub fn redeem_reserve_collateral(
     collateral_amount: u64,
     clock: &Clock,
     add_amount_to_withdrawal_caps: bool,
+    referral_fee_bps: u16,
+    referrer_token_state: Option<RefMut<ReferrerTokenState>>,
 ) -> Result<u64> {
     if collateral_amount == 0 {
         msg!("Collateral amount provided cannot be zero");
@@ -599,7 +1302,27 @@ pub fn redeem_reserve_collateral(
         return err!(LendingError::ReserveStale);
     }
 
-    let liquidity_amount = reserve.redeem_collateral(collateral_amount)?;
+    let redeemed_liquidity_amount = reserve.redeem_collateral(collateral_amount)?;
+
+    let (withdrawal_protocol_fee, withdrawal_referrer_fee) =
+        reserve.config.supply_fees.calculate_withdrawal_fees(
+            redeemed_liquidity_amount,
+            FeeCalculation::Inclusive,
+            referral_fee_bps,
+            referrer_token_state.is_some(),
+        )?;
+    let withdrawal_fee = withdrawal_protocol_fee + withdrawal_referrer_fee;
+    if withdrawal_fee > 0 {
+        msg!("Charging withdrawal fee {}", withdrawal_fee);
+        add_supply_fees(
+            reserve,
+            withdrawal_protocol_fee,
+            withdrawal_referrer_fee,
+            referrer_token_state,
+        )?;
+    }
+    let liquidity_amount = redeemed_liquidity_amount - withdrawal_fee;
+
     let timestamp = u64::try_from(clock.unix_timestamp).unwrap();
     refresh_reserve_limit_timestamps(reserve, timestamp);
     reserve.last_update.mark_stale();
@@ -636,6 +1359,70 @@ pub fn redeem_fees(reserve: &mut Reserve, slot: Slot) -> Result<u64> {
     Ok(withdraw_amount)
 }
 
+pub fn donate_to_reserve(
+    reserve: &mut Reserve,
+    clock: &Clock,
+    liquidity_amount: u64,
+) -> Result<()> {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return err!(LendingError::InvalidAmount);
+    }
+
+    if reserve
+        .last_update
+        .is_stale(clock.slot, PriceStatusFlags::NONE)?
+    {
+        msg!("Reserve is stale and must be refreshed in the current slot");
+        return err!(LendingError::ReserveStale);
+    }
+
+    if reserve.collateral.mint_total_supply == 0 {
+        msg!("Cannot donate to a reserve without depositors");
+        return err!(LendingError::InvalidAmount);
+    }
+
+    let new_reserve_liquidity_supply_f =
+        reserve.liquidity.total_supply() + Fraction::from(liquidity_amount);
+    if new_reserve_liquidity_supply_f > Fraction::from(reserve.config.deposit_limit) {
+        msg!(
+            "Cannot donate liquidity above the reserve deposit limit. New total deposit: {} > limit: {}",
+            new_reserve_liquidity_supply_f,
+            reserve.config.deposit_limit
+        );
+        return err!(LendingError::DepositLimitExceeded);
+    }
+
+    reserve.liquidity.deposit(liquidity_amount)?;
+    reserve.last_update.mark_stale();
+
+    Ok(())
+}
+
+pub fn reinvest_protocol_fees(reserve: &mut Reserve, slot: Slot, amount: u64) -> Result<u64> {
+    if reserve.last_update.is_stale(slot, PriceStatusFlags::NONE)? {
+        msg!(
+            "reserve is stale and must be refreshed in the current slot, price status: {:08b}",
+            reserve.last_update.get_price_status().0
+        );
+        return err!(LendingError::ReserveStale);
+    }
+
+    let reinvest_amount = min(
+        amount,
+        Fraction::from_bits(reserve.liquidity.accumulated_protocol_fees_sf).to_floor(),
+    );
+
+    if reinvest_amount == 0 {
+        return err!(LendingError::InsufficientProtocolFeesToRedeem);
+    }
+
+    reserve.liquidity.reinvest_protocol_fees(reinvest_amount)?;
+    reserve.last_update.mark_stale();
+
+    Ok(reinvest_amount)
+}
+
 pub fn repay_obligation_liquidity<'info, T>(
     repay_reserve: &mut Reserve,
     obligation: &mut Obligation,
@@ -653,9 +1440,14 @@ where
         return err!(LendingError::InvalidAmount);
     }
 
+    let required_price_status = repay_reserve
+        .config
+        .required_price_status
+        .for_action(ReserveAction::Repay);
+
     if repay_reserve
         .last_update
-        .is_stale(clock.slot, PriceStatusFlags::NONE)?
+        .is_stale(clock.slot, required_price_status)?
     {
         msg!("Repay reserve is stale and must be refreshed in the current slot");
         return err!(LendingError::ReserveStale);
@@ -698,10 +1490,12 @@ where
         u64::try_from(clock.unix_timestamp).unwrap(),
     )?;
 
+    let elevation_group = lending_market.get_elevation_group(obligation.elevation_group)?;
     update_elevation_group_debt_trackers_on_repay(
         repay_amount,
         obligation,
         liquidity_index,
+        elevation_group,
         repay_reserve,
         deposit_reserves_iter,
     )?;
@@ -724,6 +1518,102 @@ where
     Ok(repay_amount)
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn repay_obligation_liquidity_for_refinance<'info, T>(
+    lending_market: &LendingMarket,
+    repay_reserve: &mut Reserve,
+    borrow_reserve: &Reserve,
+    obligation: &mut Obligation,
+    clock: &Clock,
+    liquidity_amount: u64,
+    repay_reserve_pk: Pubkey,
+    borrow_reserve_pk: Pubkey,
+    borrow_amount_f: Fraction,
+    deposit_reserves_iter: impl Iterator<Item = T>,
+) -> Result<u64>
+where
+    T: AnyAccountLoader<'info, Reserve>,
+{
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return err!(LendingError::InvalidAmount);
+    }
+
+    if repay_reserve
+        .last_update
+        .is_stale(clock.slot, PriceStatusFlags::ALL_CHECKS)?
+    {
+        msg!(
+            "Repay reserve is stale and must be refreshed in the current slot, price_status: {:08b}",
+            repay_reserve.last_update.get_price_status().0
+        );
+        return err!(LendingError::ReserveStale);
+    }
+
+    let (liquidity, liquidity_index) =
+        obligation.find_liquidity_in_borrows_mut(repay_reserve_pk)?;
+    if liquidity.borrowed_amount_sf == 0 {
+        msg!("Liquidity borrowed amount is zero");
+        return err!(LendingError::ObligationLiquidityEmpty);
+    }
+
+    let cumulative_borrow_rate =
+        BigFraction::from(repay_reserve.liquidity.cumulative_borrow_rate_bsf);
+    liquidity.accrue_interest(cumulative_borrow_rate)?;
+
+    let repay_liquidity_mv = Fraction::from_bits(liquidity.market_value_sf);
+
+    let CalculateRepayResult {
+        settle_amount_f: settle_amount,
+        repay_amount,
+    } = repay_reserve.calculate_repay(
+        liquidity_amount,
+        Fraction::from_bits(liquidity.borrowed_amount_sf),
+    );
+
+    if repay_amount == 0 {
+        msg!("Repay amount is too small to transfer liquidity");
+        return err!(LendingError::RepayTooSmall);
+    }
+
+    sub_from_withdrawal_accum(
+        &mut repay_reserve.config.debt_withdrawal_cap,
+        repay_amount,
+        u64::try_from(clock.unix_timestamp).unwrap(),
+    )?;
+
+    let elevation_group = lending_market.get_elevation_group(obligation.elevation_group)?;
+    update_elevation_group_debt_trackers_on_repay(
+        repay_amount,
+        obligation,
+        liquidity_index,
+        elevation_group,
+        repay_reserve,
+        deposit_reserves_iter,
+    )?;
+
+    repay_reserve.liquidity.repay(repay_amount, settle_amount)?;
+    repay_reserve.last_update.mark_stale();
+
+    obligation.repay(settle_amount, liquidity_index);
+    obligation.update_has_debt();
+    obligation.last_update.mark_stale();
+
+    let (borrow_liquidity, _) = obligation.find_liquidity_in_borrows(borrow_reserve_pk)?;
+    post_refinance_debt_obligation_invariants(
+        obligation,
+        borrow_reserve,
+        borrow_amount_f,
+        Fraction::from_bits(borrow_liquidity.market_value_sf),
+        repay_reserve,
+        settle_amount,
+        repay_liquidity_mv,
+        Fraction::from_bits(lending_market.min_net_value_in_obligation_sf),
+    )?;
+
+    Ok(repay_amount)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn request_elevation_group<'info, T, U>(
     program_id: &Pubkey,
@@ -739,7 +1629,7 @@ where
     T: AnyAccountLoader<'info, Reserve>,
     U: AnyAccountLoader<'info, ReferrerTokenState>,
 {
-    check_obligation_fully_refreshed_and_not_null(obligation, slot)?;
+    check_obligation_fully_refreshed_and_not_null(obligation, slot, PriceStatusFlags::ALL_CHECKS)?;
 
     require!(
         obligation.elevation_group != new_elevation_group,
@@ -777,7 +1667,7 @@ where
 
     let RefreshObligationBorrowsResult {
         borrow_factor_adjusted_debt_value_f: borrow_factor_adjusted_debt_value,
-        borrowed_amount_in_elevation_group,
+        borrowed_amounts_in_elevation_group,
         ..
     } = refresh_obligation_borrows(
         program_id,
@@ -801,7 +1691,7 @@ where
         MaxReservesAsCollateralCheck::Perform,
         elevation_group,
         deposit_reserves_iter.clone(),
-        borrowed_amount_in_elevation_group,
+        borrowed_amounts_in_elevation_group,
     )?;
 
     if allowed_borrow_value < borrow_factor_adjusted_debt_value {
@@ -841,6 +1731,191 @@ where
     Ok(())
 }
 
+pub fn find_best_elevation_group<'info, T>(
+    obligation: &Obligation,
+    lending_market: &LendingMarket,
+    slot: Slot,
+    deposit_reserves_iter: impl Iterator<Item = T> + Clone,
+    borrow_reserves_iter: impl Iterator<Item = T> + Clone,
+) -> Result<u8>
+where
+    T: AnyAccountLoader<'info, Reserve>,
+{
+    check_obligation_fully_refreshed_and_not_null(obligation, slot, PriceStatusFlags::ALL_CHECKS)?;
+
+    let mut best: Option<(u8, Fraction)> = None;
+    for elevation_group_id in ELEVATION_GROUP_NONE..=MAX_NUM_ELEVATION_GROUPS {
+        let elevation_group = match get_elevation_group(elevation_group_id, lending_market) {
+            Ok(elevation_group) => elevation_group,
+            Err(_) => continue,
+        };
+
+        if let Some(elevation_group) = elevation_group {
+            if elevation_group.new_loans_disabled()
+                || elevation_group.debt_reserve == Pubkey::default()
+                || usize::from(elevation_group.max_reserves_as_collateral)
+                    < obligation.deposits_count()
+            {
+                continue;
+            }
+        }
+
+        let remaining_borrow_value = get_remaining_borrow_value_in_elevation_group(
+            obligation,
+            elevation_group,
+            deposit_reserves_iter.clone(),
+            borrow_reserves_iter.clone(),
+        )?;
+
+        if let Some(remaining_borrow_value) = remaining_borrow_value {
+            if best.map_or(true, |(_, best_value)| remaining_borrow_value > best_value) {
+                best = Some((elevation_group_id, remaining_borrow_value));
+            }
+        }
+    }
+
+    let (best_elevation_group, _) =
+        best.ok_or_else(|| error!(LendingError::InconsistentElevationGroup))?;
+    msg!("Best elevation group: {}", best_elevation_group);
+
+    Ok(best_elevation_group)
+}
+
+fn get_remaining_borrow_value_in_elevation_group<'info, T>(
+    obligation: &Obligation,
+    elevation_group: Option<&ElevationGroup>,
+    deposit_reserves_iter: impl Iterator<Item = T>,
+    borrow_reserves_iter: impl Iterator<Item = T>,
+) -> Result<Option<Fraction>>
+where
+    T: AnyAccountLoader<'info, Reserve>,
+{
+    let is_current_elevation_group =
+        obligation.elevation_group == elevation_group.map_or(ELEVATION_GROUP_NONE, |eg| eg.id);
+
+    let mut allowed_borrow_value = Fraction::ZERO;
+    for deposit_and_reserve in zip_and_validate_same_length(
+        obligation
+            .deposits
+            .iter()
+            .filter(|deposit| deposit.deposit_reserve != Pubkey::default()),
+        deposit_reserves_iter,
+    ) {
+        let (deposit, deposit_reserve) =
+            deposit_and_reserve.map_err(|_| error!(LendingError::InvalidAccountInput))?;
+        require_keys_eq!(
+            deposit.deposit_reserve,
+            deposit_reserve.get_pubkey(),
+            LendingError::InvalidAccountInput
+        );
+        let deposit_reserve = deposit_reserve.get()?;
+
+        match elevation_group {
+            Some(elevation_group) => {
+                if !deposit_reserve
+                    .config
+                    .elevation_groups
+                    .contains(&elevation_group.id)
+                    || elevation_group.is_debt_reserve(&deposit.deposit_reserve)
+                {
+                    return Ok(None);
+                }
+
+                let elevation_group_index = elevation_group.get_index();
+                let borrowed_amounts_in_elevation_group =
+                    obligation.get_borrowed_amounts_in_elevation_group(elevation_group);
+                for debt_kind in ElevationGroupDebtKind::iter() {
+                    let previous_debt = if is_current_elevation_group {
+                        deposit.get_borrowed_amount_in_elevation_group(debt_kind)
+                    } else {
+                        0
+                    };
+                    let new_debt = deposit_reserve
+                        .get_borrowed_amount_in_elevation_group(elevation_group_index, debt_kind)
+                        .saturating_sub(previous_debt)
+                        .saturating_add(borrowed_amounts_in_elevation_group.get(debt_kind));
+                    if new_debt
+                        > deposit_reserve
+                            .config
+                            .get_borrow_limit_in_elevation_group(elevation_group_index, debt_kind)
+                    {
+                        return Ok(None);
+                    }
+                }
+            }
+            None => {
+                if deposit_reserve.config.disable_usage_as_coll_outside_emode > 0
+                    && !obligation.borrows_empty()
+                {
+                    return Ok(None);
+                }
+            }
+        }
+
+        let (ltv_pct, _) = get_max_ltv_and_liquidation_threshold(&deposit_reserve, elevation_group);
+        allowed_borrow_value +=
+            Fraction::from_bits(deposit.market_value_sf) * Fraction::from_percent(ltv_pct);
+    }
+
+    let mut borrow_factor_adjusted_debt_value = Fraction::ZERO;
+    for borrow_and_reserve in zip_and_validate_same_length(
+        obligation
+            .borrows
+            .iter()
+            .filter(|borrow| borrow.borrow_reserve != Pubkey::default()),
+        borrow_reserves_iter,
+    ) {
+        let (borrow, borrow_reserve) =
+            borrow_and_reserve.map_err(|_| error!(LendingError::InvalidAccountInput))?;
+        require_keys_eq!(
+            borrow.borrow_reserve,
+            borrow_reserve.get_pubkey(),
+            LendingError::InvalidAccountInput
+        );
+        let borrow_reserve = borrow_reserve.get()?;
+
+        match elevation_group {
+            Some(elevation_group) => {
+                if !borrow_reserve
+                    .config
+                    .elevation_groups
+                    .contains(&elevation_group.id)
+                    || !elevation_group.is_debt_reserve(&borrow.borrow_reserve)
+                {
+                    return Ok(None);
+                }
+            }
+            None => {
+                let previous_debt = if is_current_elevation_group {
+                    borrow.borrowed_amount_outside_elevation_groups
+                } else {
+                    0
+                };
+                let new_debt = borrow_reserve
+                    .borrowed_amount_outside_elevation_group
+                    .saturating_sub(previous_debt)
+                    .saturating_add(
+                        Fraction::from_bits(borrow.borrowed_amount_sf).to_ceil::<u64>(),
+                    );
+                if new_debt > borrow_reserve.config.borrow_limit_outside_elevation_group {
+                    return Ok(None);
+                }
+            }
+        }
+
+        borrow_factor_adjusted_debt_value += Fraction::from_bits(borrow.market_value_sf)
+            * borrow_reserve.borrow_factor_f(elevation_group.is_some());
+    }
+
+    if allowed_borrow_value < borrow_factor_adjusted_debt_value {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        allowed_borrow_value - borrow_factor_adjusted_debt_value,
+    ))
+}
+
 fn check_ltv_not_worse_if_marked_for_deleveraging(
     obligation: &Obligation,
     initial_ltv: Fraction,
@@ -890,12 +1965,14 @@ where
 
             let mut reserve = reserve.get_mut()?;
 
-            reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                [elevation_group_index] = reserve
-                .borrowed_amounts_against_this_reserve_in_elevation_groups[elevation_group_index]
-                .saturating_sub(deposit.borrowed_amount_against_this_collateral_in_elevation_group);
+            for debt_kind in ElevationGroupDebtKind::iter() {
+                let reserve_debt = reserve
+                    .get_borrowed_amount_in_elevation_group_mut(elevation_group_index, debt_kind);
+                *reserve_debt = reserve_debt
+                    .saturating_sub(deposit.get_borrowed_amount_in_elevation_group(debt_kind));
 
-            deposit.borrowed_amount_against_this_collateral_in_elevation_group = 0;
+                *deposit.get_borrowed_amount_in_elevation_group_mut(debt_kind) = 0;
+            }
         }
     } else {
         let borrows_and_reserves = zip_and_validate_same_length(
@@ -927,7 +2004,7 @@ pub fn refresh_obligation_deposits<'info, T>(
     max_reserves_as_collateral_check: MaxReservesAsCollateralCheck,
     elevation_group: Option<&ElevationGroup>,
     mut reserves_iter: impl Iterator<Item = T>,
-    borrowed_amount_in_elevation_group: Option<u64>,
+    borrowed_amounts_in_elevation_group: Option<ElevationGroupDebtAmounts>,
 ) -> Result<RefreshObligationDepositsResult>
 where
     T: AnyAccountLoader<'info, Reserve>,
@@ -940,18 +2017,20 @@ where
     let mut num_of_obsolete_reserves = 0;
     let mut prices_state = PriceStatusFlags::all();
     let mut borrowing_disabled = false;
+    let mut borrowing_disabled_by_price_circuit_breaker = false;
     let mut collaterals_count = 0;
+    let mut farm_reserves = Vec::new();
 
-    let elevation_group_and_borrowed_amount: Option<(&ElevationGroup, u64)> = match (
-        elevation_group,
-        borrowed_amount_in_elevation_group,
-    ) {
-        (Some(elevation_group), Some(borrowed_amount)) => Some((elevation_group, borrowed_amount)),
-        (None, None) => None,
-        _ => {
-            panic!("Elevation group and borrowed amount must be both set or both unset when refreshing deposits.");
-        }
-    };
+    let elevation_group_and_borrowed_amounts: Option<(&ElevationGroup, ElevationGroupDebtAmounts)> =
+        match (elevation_group, borrowed_amounts_in_elevation_group) {
+            (Some(elevation_group), Some(borrowed_amounts)) => {
+                Some((elevation_group, borrowed_amounts))
+            }
+            (None, None) => None,
+            _ => {
+                panic!("Elevation group and borrowed amounts must be both set or both unset when refreshing deposits.");
+            }
+        };
 
     for (index, deposit) in obligation
         .deposits
@@ -975,10 +2054,18 @@ where
             borrowing_disabled = true;
         }
 
+        if deposit_reserve.is_price_circuit_breaker_tripped() {
+            borrowing_disabled_by_price_circuit_breaker = true;
+        }
+
         if deposit_reserve.config.status() == ReserveStatus::Obsolete {
             num_of_obsolete_reserves += 1;
         }
 
+        if deposit_reserve.farm_collateral != Pubkey::default() {
+            farm_reserves.push(deposit_reserve_info_key);
+        }
+
         check_obligation_collateral_deposit_reserve(
             deposit,
             &deposit_reserve,
@@ -991,7 +2078,7 @@ where
             collaterals_count += 1;
         }
 
-        if let Some((elevation_group, debt_amount)) = elevation_group_and_borrowed_amount {
+        if let Some((elevation_group, debt_amounts)) = elevation_group_and_borrowed_amounts {
             let elevation_group_index = elevation_group.get_index();
             require!(
                 deposit_reserve
@@ -1001,19 +2088,20 @@ where
                 LendingError::InconsistentElevationGroup
             );
 
-            require_keys_neq!(
-                deposit_reserve_info_key,
-                elevation_group.debt_reserve,
+            require!(
+                !elevation_group.is_debt_reserve(&deposit_reserve_info_key),
                 LendingError::ElevationGroupDebtReserveAsCollateral
             );
 
-            deposit_reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                [elevation_group_index] = deposit_reserve
-                .borrowed_amounts_against_this_reserve_in_elevation_groups[elevation_group_index]
-                .saturating_sub(deposit.borrowed_amount_against_this_collateral_in_elevation_group);
-            deposit_reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                [elevation_group_index] += debt_amount;
-            deposit.borrowed_amount_against_this_collateral_in_elevation_group = debt_amount;
+            for debt_kind in ElevationGroupDebtKind::iter() {
+                let debt_amount = debt_amounts.get(debt_kind);
+                let reserve_debt = deposit_reserve
+                    .get_borrowed_amount_in_elevation_group_mut(elevation_group_index, debt_kind);
+                *reserve_debt = reserve_debt
+                    .saturating_sub(deposit.get_borrowed_amount_in_elevation_group(debt_kind));
+                *reserve_debt += debt_amount;
+                *deposit.get_borrowed_amount_in_elevation_group_mut(debt_kind) = debt_amount;
+            }
         }
 
         let market_value_f =
@@ -1052,6 +2140,8 @@ where
         );
     }
 
+    track_obligation_farm_reserves(obligation, farm_reserves);
+
     if max_reserves_as_collateral_check == MaxReservesAsCollateralCheck::Perform {
         if let Some(elevation_group) = elevation_group {
             require_gte!(
@@ -1071,9 +2161,21 @@ where
         unhealthy_borrow_value_f: unhealthy_borrow_value,
         prices_state,
         borrowing_disabled,
+        borrowing_disabled_by_price_circuit_breaker,
     })
 }
 
+fn track_obligation_farm_reserves(obligation: &mut Obligation, farm_reserves: Vec<Pubkey>) {
+    for farm_reserve in farm_reserves {
+        if !obligation.track_farm_reserve(farm_reserve) {
+            msg!(
+                "Obligation cannot track farms of reserve {}, all farm reserve slots are used",
+                farm_reserve
+            );
+        }
+    }
+}
+
 pub fn refresh_obligation_borrows<'info, T, U>(
     program_id: &Pubkey,
     obligation: &mut Obligation,
@@ -1093,8 +2195,9 @@ where
     let mut highest_borrow_factor_f = Fraction::ONE;
 
     let obligation_has_referrer = obligation.has_referrer();
-    let mut borrowed_amounts_accumulator_for_elevation_group = 0_u64;
+    let mut borrowed_amounts_accumulator_for_elevation_group = ElevationGroupDebtAmounts::default();
     let mut num_borrow_reserves = 0;
+    let mut farm_reserves = Vec::new();
 
     for (index, borrow) in obligation
         .borrows
@@ -1121,6 +2224,10 @@ where
             slot,
         )?;
 
+        if borrow_reserve.farm_debt != Pubkey::default() {
+            farm_reserves.push(borrow_reserve_info_key);
+        }
+
         let cumulative_borrow_rate_bf =
             BigFraction::from(borrow_reserve.liquidity.cumulative_borrow_rate_bsf);
 
@@ -1130,7 +2237,6 @@ where
 
         let borrowed_amount_f = Fraction::from_bits(borrow.borrowed_amount_sf);
         let borrowed_amount = borrowed_amount_f.to_ceil::<u64>();
-        borrowed_amounts_accumulator_for_elevation_group += borrowed_amount;
         {
             if let Some(elevation_group) = elevation_group {
                 require!(
@@ -1140,11 +2246,11 @@ where
                         .contains(&elevation_group.id),
                     LendingError::InconsistentElevationGroup
                 );
-                require_keys_eq!(
-                    borrow_reserve_info_key,
-                    elevation_group.debt_reserve,
-                    LendingError::ElevationGroupHasAnotherDebtReserve
-                );
+                let debt_kind = elevation_group
+                    .get_debt_kind(&borrow_reserve_info_key)
+                    .ok_or_else(|| error!(LendingError::ElevationGroupHasAnotherDebtReserve))?;
+                *borrowed_amounts_accumulator_for_elevation_group.get_mut(debt_kind) +=
+                    borrowed_amount;
             } else {
                 borrow_reserve.borrowed_amount_outside_elevation_group = borrow_reserve
                     .borrowed_amount_outside_elevation_group
@@ -1200,9 +2306,11 @@ where
         );
     }
 
-    let borrowed_amount_in_elevation_group = if elevation_group.is_some() {
+    track_obligation_farm_reserves(obligation, farm_reserves);
+
+    let borrowed_amounts_in_elevation_group = if let Some(elevation_group) = elevation_group {
         require!(
-            num_borrow_reserves <= 1,
+            num_borrow_reserves <= elevation_group.debt_reserves().count(),
             LendingError::InconsistentElevationGroup
         );
         Some(borrowed_amounts_accumulator_for_elevation_group)
@@ -1213,7 +2321,7 @@ where
     Ok(RefreshObligationBorrowsResult {
         borrowed_assets_market_value_f: borrowed_assets_market_value,
         borrow_factor_adjusted_debt_value_f: borrow_factor_adjusted_debt_value,
-        borrowed_amount_in_elevation_group,
+        borrowed_amounts_in_elevation_group,
         prices_state,
         highest_borrow_factor_pct: highest_borrow_factor_f.to_percent::<u64>().unwrap(),
     })
@@ -1240,7 +2348,7 @@ where
         borrow_factor_adjusted_debt_value_f,
         borrowed_assets_market_value_f,
         prices_state: borrows_prices_state,
-        borrowed_amount_in_elevation_group,
+        borrowed_amounts_in_elevation_group,
         highest_borrow_factor_pct,
     } = refresh_obligation_borrows(
         program_id,
@@ -1261,6 +2369,7 @@ where
         unhealthy_borrow_value_f: unhealthy_borrow_value,
         prices_state: deposits_prices_state,
         borrowing_disabled,
+        borrowing_disabled_by_price_circuit_breaker,
     } = refresh_obligation_deposits(
         obligation,
         lending_market,
@@ -1268,7 +2377,7 @@ where
         max_reserves_as_collateral_check,
         elevation_group,
         &mut deposit_reserves_iter,
-        borrowed_amount_in_elevation_group,
+        borrowed_amounts_in_elevation_group,
     )?;
 
     obligation.borrowed_assets_market_value_sf = borrowed_assets_market_value_f.to_bits();
@@ -1292,6 +2401,8 @@ where
     obligation.num_of_obsolete_reserves = num_of_obsolete_reserves;
 
     obligation.borrowing_disabled = borrowing_disabled.into();
+    obligation.borrowing_disabled_by_price_circuit_breaker =
+        borrowing_disabled_by_price_circuit_breaker.into();
     obligation.highest_borrow_factor_pct = highest_borrow_factor_pct;
 
     let prices_state = deposits_prices_state.intersection(borrows_prices_state);
@@ -1438,7 +2549,7 @@ where
     drop(repay_reserve_ref);
     drop(withdraw_reserve_ref);
 
-    let previous_borrowed_amount_against_this_collateral_in_elevation_group;
+    let previous_borrowed_amounts_against_this_collateral_in_elevation_group;
     {
         let mut repay_reserve_ref_mut = repay_reserve.get_mut()?;
 
@@ -1446,13 +2557,13 @@ where
             repay_amount,
             obligation,
             liquidity_index,
+            elevation_group,
             &mut repay_reserve_ref_mut,
             deposit_reserves_iter,
         )?;
 
-        previous_borrowed_amount_against_this_collateral_in_elevation_group = obligation.deposits
-            [collateral_index]
-            .borrowed_amount_against_this_collateral_in_elevation_group;
+        previous_borrowed_amounts_against_this_collateral_in_elevation_group =
+            obligation.deposits[collateral_index].get_borrowed_amounts_in_elevation_group();
 
         utils::repay_and_withdraw_from_obligation_post_liquidation(
             obligation,
@@ -1481,7 +2592,7 @@ where
 
     if is_full_withdrawal {
         utils::update_elevation_group_debt_trackers_on_full_withdraw(
-            previous_borrowed_amount_against_this_collateral_in_elevation_group,
+            previous_borrowed_amounts_against_this_collateral_in_elevation_group,
             obligation.elevation_group,
             &mut withdraw_reserve_ref_mut,
         )?;
@@ -1506,8 +2617,14 @@ pub(crate) fn post_liquidate_redeem(
     clock: &Clock,
 ) -> Result<Option<(u64, u64)>> {
     if withdraw_collateral_amount != 0 {
-        let withdraw_liquidity_amount =
-            redeem_reserve_collateral(withdraw_reserve, withdraw_collateral_amount, clock, false)?;
+        let withdraw_liquidity_amount = redeem_reserve_collateral(
+            withdraw_reserve,
+            withdraw_collateral_amount,
+            clock,
+            false,
+            0,
+            None,
+        )?;
         let protocol_fee = liquidation_operations::calculate_protocol_liquidation_fee(
             withdraw_liquidity_amount,
             liquidation_bonus_rate,
@@ -1603,6 +2720,7 @@ where
 }
 
 pub fn socialize_loss<'info, T>(
+    lending_market: &LendingMarket,
     reserve: &mut Reserve,
     reserve_pk: &Pubkey,
     obligation: &mut Obligation,
@@ -1658,10 +2776,12 @@ where
 
     msg!("Forgiving debt amount {}", forgive_amount_f);
 
+    let elevation_group = lending_market.get_elevation_group(obligation.elevation_group)?;
     utils::update_elevation_group_debt_trackers_on_repay(
         forgive_amount_f.to_ceil(),
         obligation,
         liquidity_index,
+        elevation_group,
         reserve,
         deposit_reserves_iter,
     )?;
@@ -1719,6 +2839,29 @@ pub fn mark_obligation_for_deleveraging(
     Ok(())
 }
 
+fn add_supply_fees(
+    reserve: &mut Reserve,
+    protocol_fee: u64,
+    referrer_fee: u64,
+    referrer_token_state: Option<RefMut<ReferrerTokenState>>,
+) -> Result<()> {
+    reserve.liquidity.add_supply_fee(protocol_fee)?;
+
+    if let Some(mut referrer_token_state) = referrer_token_state {
+        if referrer_fee > 0 {
+            add_referrer_fee(
+                reserve,
+                &mut referrer_token_state,
+                Fraction::from_num(referrer_fee),
+            )?;
+
+            reserve.liquidity.deposit(referrer_fee)?;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn add_referrer_fee(
     borrow_reserve: &mut Reserve,
     referrer_token_state: &mut ReferrerTokenState,
@@ -2040,6 +3183,117 @@ pub fn update_reserve_config(reserve: &mut Reserve, mode: UpdateConfigMode, valu
             msg!("Prv Value is {:?}", prv);
             msg!("New Value is {:?}", new);
         }
+        UpdateConfigMode::UpdateExtendedBorrowRateCurve => {
+            let (new_curve, new_extension): (BorrowRateCurve, BorrowRateCurveExtension) =
+                BorshDeserialize::deserialize(&mut &value[..]).unwrap();
+            let prv_curve = reserve.config.borrow_rate_curve;
+            let prv_extension = reserve.config.borrow_rate_curve_extension;
+            reserve.config.borrow_rate_curve = new_curve;
+            reserve.config.borrow_rate_curve_extension = new_extension;
+            msg!("Prv Value is {:?} {:?}", prv_curve, prv_extension);
+            msg!("New Value is {:?} {:?}", new_curve, new_extension);
+        }
+        UpdateConfigMode::UpdateUtilizationEmaHalfLifeSecs => {
+            let new = u64::from_le_bytes(value[..8].try_into().unwrap());
+            let prv = reserve.config.utilization_ema_half_life_secs;
+            reserve.config.utilization_ema_half_life_secs = new;
+            msg!("Prv Value is {:?}", prv);
+            msg!("New Value is {:?}", new);
+        }
+        UpdateConfigMode::UpdateDepositFee => {
+            let (fee_bps, min_fee): (u16, u64) =
+                BorshDeserialize::deserialize(&mut &value[..]).unwrap();
+            let prv_fee_bps = reserve.config.supply_fees.deposit_fee_bps;
+            let prv_min_fee = reserve.config.supply_fees.min_deposit_fee;
+            reserve.config.supply_fees.deposit_fee_bps = fee_bps;
+            reserve.config.supply_fees.min_deposit_fee = min_fee;
+            msg!("Prv Value is {:?} {:?}", prv_fee_bps, prv_min_fee);
+            msg!("New Value is {:?} {:?}", fee_bps, min_fee);
+        }
+        UpdateConfigMode::UpdateWithdrawalFee => {
+            let (fee_bps, min_fee): (u16, u64) =
+                BorshDeserialize::deserialize(&mut &value[..]).unwrap();
+            let prv_fee_bps = reserve.config.supply_fees.withdrawal_fee_bps;
+            let prv_min_fee = reserve.config.supply_fees.min_withdrawal_fee;
+            reserve.config.supply_fees.withdrawal_fee_bps = fee_bps;
+            reserve.config.supply_fees.min_withdrawal_fee = min_fee;
+            msg!("Prv Value is {:?} {:?}", prv_fee_bps, prv_min_fee);
+            msg!("New Value is {:?} {:?}", fee_bps, min_fee);
+        }
+        UpdateConfigMode::UpdateTokenInfoPriceEma => {
+            let new_half_life_secs = u64::from_le_bytes(value[..8].try_into().unwrap());
+            let new_min_sample_age_secs = u64::from_le_bytes(value[8..16].try_into().unwrap());
+            let prv_half_life_secs = reserve.config.token_info.price_ema_half_life_secs;
+            let prv_min_sample_age_secs = reserve.config.token_info.price_ema_min_sample_age_secs;
+            reserve.config.token_info.price_ema_half_life_secs = new_half_life_secs;
+            reserve.config.token_info.price_ema_min_sample_age_secs = new_min_sample_age_secs;
+            msg!(
+                "Prv Value is {:?} {:?}",
+                prv_half_life_secs,
+                prv_min_sample_age_secs
+            );
+            msg!(
+                "New Value is {:?} {:?}",
+                new_half_life_secs,
+                new_min_sample_age_secs
+            );
+        }
+        UpdateConfigMode::UpdateTokenInfoPythVerificationAndConfidence => {
+            let new_min_verification_signatures = value[0];
+            let new_confidence_adjustment_factor = value[1];
+            let prv_min_verification_signatures =
+                reserve.config.token_info.pyth_min_verification_signatures;
+            let prv_confidence_adjustment_factor =
+                reserve.config.token_info.pyth_confidence_adjustment_factor;
+            reserve.config.token_info.pyth_min_verification_signatures =
+                new_min_verification_signatures;
+            reserve.config.token_info.pyth_confidence_adjustment_factor =
+                new_confidence_adjustment_factor;
+            msg!(
+                "Prv Value is {:?} {:?}",
+                prv_min_verification_signatures,
+                prv_confidence_adjustment_factor
+            );
+            msg!(
+                "New Value is {:?} {:?}",
+                new_min_verification_signatures,
+                new_confidence_adjustment_factor
+            );
+        }
+        UpdateConfigMode::UpdateTokenInfoPriceCircuitBreaker => {
+            let new_max_move_bps = u64::from_le_bytes(value[..8].try_into().unwrap());
+            let new_window_secs = u64::from_le_bytes(value[8..16].try_into().unwrap());
+            let prv_max_move_bps = reserve.config.token_info.price_circuit_breaker_max_move_bps;
+            let prv_window_secs = reserve.config.token_info.price_circuit_breaker_window_secs;
+            reserve.config.token_info.price_circuit_breaker_max_move_bps = new_max_move_bps;
+            reserve.config.token_info.price_circuit_breaker_window_secs = new_window_secs;
+            msg!("Prv Value is {:?} {:?}", prv_max_move_bps, prv_window_secs);
+            msg!("New Value is {:?} {:?}", new_max_move_bps, new_window_secs);
+        }
+        UpdateConfigMode::UpdateTransferHookProgram => {
+            let new: [u8; 32] = value[0..32].try_into().unwrap();
+            let new = Pubkey::new_from_array(new);
+            let prv = reserve.config.transfer_hook_program;
+            reserve.config.transfer_hook_program = new;
+            msg!("Prv Value is {:?}", prv);
+            msg!("New Value is {:?}", new);
+        }
+        UpdateConfigMode::UpdateRequiredPriceStatus => {
+            let new: ReserveRequiredPriceStatus =
+                BorshDeserialize::deserialize(&mut &value[..]).unwrap();
+            let prv = reserve.config.required_price_status;
+            reserve.config.required_price_status = new;
+            msg!("Prv Value is {:?}", prv);
+            msg!("New Value is {:?}", new);
+        }
+        UpdateConfigMode::UpdateOracleSlot => {
+            let new: OracleSlotConfiguration =
+                BorshDeserialize::deserialize(&mut &value[..]).unwrap();
+            let prv = reserve.config.token_info.oracle_slot;
+            reserve.config.token_info.oracle_slot = new;
+            msg!("Prv Value is {:?}", prv);
+            msg!("New Value is {:?}", new);
+        }
         UpdateConfigMode::UpdateEntireReserveConfig => {
             let new: ReserveConfig = BorshDeserialize::deserialize(&mut &value[..]).unwrap();
             reserve.config = new;
@@ -2194,6 +3448,24 @@ pub fn update_reserve_config(reserve: &mut Reserve, mode: UpdateConfigMode, valu
                     .borrow_limit_against_this_collateral_in_elevation_group
             );
         }
+        UpdateConfigMode::UpdateSecondaryDebtBorrowLimitsInElevationGroupAgainstThisReserve => {
+            msg!(
+                "Prv Value is {:?}",
+                reserve
+                    .config
+                    .secondary_debt_borrow_limit_against_this_collateral_in_elevation_group
+            );
+            reserve
+                .config
+                .secondary_debt_borrow_limit_against_this_collateral_in_elevation_group =
+                BorshDeserialize::try_from_slice(value).unwrap();
+            msg!(
+                "New Value is {:?}",
+                reserve
+                    .config
+                    .secondary_debt_borrow_limit_against_this_collateral_in_elevation_group
+            );
+        }
         UpdateConfigMode::UpdateFarmCollateral => {
             let new: [u8; 32] = value[0..32].try_into().unwrap();
             let new = Pubkey::new_from_array(new);
@@ -2307,16 +3579,36 @@ pub mod utils {
         Ok(())
     }
 
-    pub(crate) fn calculate_market_value_from_liquidity_amount(
+    pub(crate) fn calculate_collateral_market_value_from_liquidity_amount(
+        reserve: &Reserve,
+        liquidity_amount: Fraction,
+    ) -> Fraction {
+        calculate_market_value_from_liquidity_amount_at_price(
+            reserve,
+            liquidity_amount,
+            reserve.liquidity.get_collateral_price_f(),
+        )
+    }
+
+    pub(crate) fn calculate_debt_market_value_from_liquidity_amount(
+        reserve: &Reserve,
+        liquidity_amount: Fraction,
+    ) -> Fraction {
+        calculate_market_value_from_liquidity_amount_at_price(
+            reserve,
+            liquidity_amount,
+            reserve.liquidity.get_debt_price_f(),
+        )
+    }
+
+    fn calculate_market_value_from_liquidity_amount_at_price(
         reserve: &Reserve,
         liquidity_amount: Fraction,
+        price_f: Fraction,
     ) -> Fraction {
         let mint_decimal_factor: u128 =
             ten_pow(reserve.liquidity.mint_decimals.try_into().unwrap()).into();
-        let market_price_f = reserve.liquidity.get_market_price_f();
-        liquidity_amount
-            .mul(market_price_f)
-            .div(mint_decimal_factor)
+        liquidity_amount.mul(price_f).div(mint_decimal_factor)
     }
 
     pub(crate) fn calculate_obligation_collateral_market_value(
@@ -2327,7 +3619,7 @@ pub mod utils {
             .collateral_exchange_rate()
             .fraction_collateral_to_liquidity(deposit.deposited_amount.into());
 
-        calculate_market_value_from_liquidity_amount(
+        calculate_collateral_market_value_from_liquidity_amount(
             deposit_reserve,
             liquidity_amount_from_collateral,
         )
@@ -2337,7 +3629,7 @@ pub mod utils {
         borrow_reserve: &Reserve,
         borrow: &ObligationLiquidity,
     ) -> Fraction {
-        calculate_market_value_from_liquidity_amount(
+        calculate_debt_market_value_from_liquidity_amount(
             borrow_reserve,
             Fraction::from_bits(borrow.borrowed_amount_sf),
         )
@@ -2474,9 +3766,8 @@ pub mod utils {
                             .contains(&elevation_group.id),
                         LendingError::InconsistentElevationGroup
                     );
-                    require_keys_eq!(
-                        reserve_pk,
-                        elevation_group.debt_reserve,
+                    require!(
+                        elevation_group.is_debt_reserve(&reserve_pk),
                         LendingError::ElevationGroupHasAnotherDebtReserve
                     );
                 } else {
@@ -2517,21 +3808,24 @@ pub mod utils {
                             .contains(&elevation_group.id),
                         LendingError::InconsistentElevationGroup
                     );
-                    require_keys_neq!(
-                        reserve_pk,
-                        elevation_group.debt_reserve,
+                    require!(
+                        !elevation_group.is_debt_reserve(&reserve_pk),
                         LendingError::ElevationGroupDebtReserveAsCollateral
                     );
 
-                    require_gte!(
-                        deposit_reserve
-                            .config
-                            .borrow_limit_against_this_collateral_in_elevation_group
-                            [elevation_group_index],
-                        deposit_reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                            [elevation_group_index],
-                        LendingError::ElevationGroupBorrowLimitExceeded,
-                    );
+                    for debt_kind in ElevationGroupDebtKind::iter() {
+                        require_gte!(
+                            deposit_reserve.config.get_borrow_limit_in_elevation_group(
+                                elevation_group_index,
+                                debt_kind
+                            ),
+                            deposit_reserve.get_borrowed_amount_in_elevation_group(
+                                elevation_group_index,
+                                debt_kind
+                            ),
+                            LendingError::ElevationGroupBorrowLimitExceeded,
+                        );
+                    }
                 } else {
                 }
             }
@@ -2558,11 +3852,9 @@ pub mod utils {
         if let Some(elevation_group) = elevation_group {
             let elevation_group_index = elevation_group.get_index();
 
-            require_keys_eq!(
-                elevation_group.debt_reserve,
-                *borrow_reserve_pk,
-                LendingError::ElevationGroupHasAnotherDebtReserve
-            );
+            let debt_kind = elevation_group
+                .get_debt_kind(borrow_reserve_pk)
+                .ok_or_else(|| error!(LendingError::ElevationGroupHasAnotherDebtReserve))?;
             for obligation_deposit in obligation
                 .deposits
                 .iter_mut()
@@ -2580,10 +3872,10 @@ pub mod utils {
 
                 let debt_limit = deposit_reserve
                     .config
-                    .borrow_limit_against_this_collateral_in_elevation_group[elevation_group_index];
+                    .get_borrow_limit_in_elevation_group(elevation_group_index, debt_kind);
                 let prev_borrowed_amounts_against_this_reserve_in_elevation_groups =
-                    deposit_reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                        [elevation_group_index];
+                    deposit_reserve
+                        .get_borrowed_amount_in_elevation_group(elevation_group_index, debt_kind);
                 let new_borrowed_amounts_against_this_reserve_in_elevation_groups =
                     prev_borrowed_amounts_against_this_reserve_in_elevation_groups
                         .checked_add(new_borrowed_amount)
@@ -2598,11 +3890,11 @@ pub mod utils {
                     new_borrowed_amounts_against_this_reserve_in_elevation_groups,
                     LendingError::ElevationGroupBorrowLimitExceeded
                 );
-                deposit_reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                    [elevation_group_index] =
+                *deposit_reserve
+                    .get_borrowed_amount_in_elevation_group_mut(elevation_group_index, debt_kind) =
                     new_borrowed_amounts_against_this_reserve_in_elevation_groups;
 
-                obligation_deposit.borrowed_amount_against_this_collateral_in_elevation_group +=
+                *obligation_deposit.get_borrowed_amount_in_elevation_group_mut(debt_kind) +=
                     new_borrowed_amount;
             }
         } else {
@@ -2633,14 +3925,21 @@ pub mod utils {
         repay_amount: u64,
         obligation: &mut Obligation,
         obligation_borrow_index: usize,
+        elevation_group: Option<&ElevationGroup>,
         borrow_reserve: &mut Reserve,
         mut deposit_reserves_iter: impl Iterator<Item = T>,
     ) -> Result<()>
     where
         T: AnyAccountLoader<'info, Reserve>,
     {
-        if obligation.elevation_group != ELEVATION_GROUP_NONE {
-            let elevation_group_index = obligation.elevation_group as usize - 1;
+        if let Some(elevation_group) = elevation_group {
+            let elevation_group_index = elevation_group.get_index();
+            let Some(debt_kind) = elevation_group
+                .get_debt_kind(&obligation.borrows[obligation_borrow_index].borrow_reserve)
+            else {
+                msg!("Repaid reserve is not a debt reserve of the elevation group, trackers are resynced on refresh");
+                return Ok(());
+            };
             for obligation_deposit in obligation
                 .deposits
                 .iter_mut()
@@ -2656,21 +3955,20 @@ pub mod utils {
                 let mut deposit_reserve = deposit_reserve.get_mut()?;
                 let debt_limit = deposit_reserve
                     .config
-                    .borrow_limit_against_this_collateral_in_elevation_group[elevation_group_index];
+                    .get_borrow_limit_in_elevation_group(elevation_group_index, debt_kind);
                 let pre_debt_amount = deposit_reserve
-                    .borrowed_amounts_against_this_reserve_in_elevation_groups
-                    [elevation_group_index];
+                    .get_borrowed_amount_in_elevation_group(elevation_group_index, debt_kind);
                 let new_debt_amount = pre_debt_amount.saturating_sub(repay_amount);
 
                 msg!("Refreshed debt in elevation group reserve {} before {pre_debt_amount} after {new_debt_amount} limit {debt_limit}",
                     obligation_deposit.deposit_reserve,
                 );
-                deposit_reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                    [elevation_group_index] = new_debt_amount;
-                obligation_deposit.borrowed_amount_against_this_collateral_in_elevation_group =
-                    obligation_deposit
-                        .borrowed_amount_against_this_collateral_in_elevation_group
-                        .saturating_sub(repay_amount);
+                *deposit_reserve
+                    .get_borrowed_amount_in_elevation_group_mut(elevation_group_index, debt_kind) =
+                    new_debt_amount;
+                let collateral_debt_amount =
+                    obligation_deposit.get_borrowed_amount_in_elevation_group_mut(debt_kind);
+                *collateral_debt_amount = collateral_debt_amount.saturating_sub(repay_amount);
             }
         } else {
             let new_total_borrow_amount = borrow_reserve
@@ -2692,7 +3990,7 @@ pub mod utils {
     }
 
     pub fn update_elevation_group_debt_trackers_on_new_deposit(
-        total_borrowed: Option<u64>,
+        borrowed_amounts: ElevationGroupDebtAmounts,
         obligation_collateral: &mut ObligationCollateral,
         pre_deposit_count: usize,
         elevation_group: Option<&ElevationGroup>,
@@ -2701,9 +3999,8 @@ pub mod utils {
         max_reserves_as_collateral_check: MaxReservesAsCollateralCheck,
     ) -> Result<()> {
         if let Some(elevation_group) = elevation_group {
-            require_keys_neq!(
-                elevation_group.debt_reserve,
-                *deposit_reserve_pk,
+            require!(
+                !elevation_group.is_debt_reserve(deposit_reserve_pk),
                 LendingError::ElevationGroupDebtReserveAsCollateral
             );
 
@@ -2717,29 +4014,32 @@ pub mod utils {
 
             let elevation_group_index = elevation_group.get_index();
 
-            let total_borrowed = total_borrowed
-                .ok_or_else(|| error!(LendingError::ObligationElevationGroupMultipleDebtReserve))?;
-
-            deposit_reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                [elevation_group_index] += total_borrowed;
-            obligation_collateral.borrowed_amount_against_this_collateral_in_elevation_group =
-                total_borrowed;
+            for debt_kind in ElevationGroupDebtKind::iter() {
+                *deposit_reserve.get_borrowed_amount_in_elevation_group_mut(
+                    elevation_group_index,
+                    debt_kind,
+                ) += borrowed_amounts.get(debt_kind);
+                *obligation_collateral.get_borrowed_amount_in_elevation_group_mut(debt_kind) =
+                    borrowed_amounts.get(debt_kind);
+            }
         }
         Ok(())
     }
 
     pub fn update_elevation_group_debt_trackers_on_full_withdraw(
-        previous_debt_in_elevation_group: u64,
+        previous_debt_in_elevation_group: ElevationGroupDebtAmounts,
         elevation_group_id: u8,
         deposit_reserve: &mut Reserve,
     ) -> Result<()> {
         if elevation_group_id != ELEVATION_GROUP_NONE {
             let elevation_group_index = elevation_group_id as usize - 1;
 
-            deposit_reserve.borrowed_amounts_against_this_reserve_in_elevation_groups
-                [elevation_group_index] = deposit_reserve
-                .borrowed_amounts_against_this_reserve_in_elevation_groups[elevation_group_index]
-                .saturating_sub(previous_debt_in_elevation_group);
+            for debt_kind in ElevationGroupDebtKind::iter() {
+                let reserve_debt = deposit_reserve
+                    .get_borrowed_amount_in_elevation_group_mut(elevation_group_index, debt_kind);
+                *reserve_debt =
+                    reserve_debt.saturating_sub(previous_debt_in_elevation_group.get(debt_kind));
+            }
         }
         Ok(())
     }
@@ -2772,7 +4072,7 @@ pub mod utils {
         previous_collateral_asset_mv: Fraction,
         min_accepted_net_value: Fraction,
     ) -> Result<()> {
-        let asset_mv = calculate_market_value_from_liquidity_amount(reserve, amount);
+        let asset_mv = calculate_collateral_market_value_from_liquidity_amount(reserve, amount);
 
         let new_total_deposited_mv = Fraction::from_bits(obligation.deposited_value_sf) + asset_mv;
 
@@ -2812,13 +4112,14 @@ pub mod utils {
         previous_collateral_asset_mv: Fraction,
         min_accepted_net_value: Fraction,
     ) -> Result<()> {
-        let asset_mv = calculate_market_value_from_liquidity_amount(reserve, amount);
+        let asset_mv = calculate_collateral_market_value_from_liquidity_amount(reserve, amount);
 
-        let new_total_deposited_mv = Fraction::from_bits(obligation.deposited_value_sf) - asset_mv;
+        let new_total_deposited_mv =
+            Fraction::from_bits(obligation.deposited_value_sf).saturating_sub(asset_mv);
         let debt_value_bf = Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf);
 
         if previous_collateral_asset_mv != 0 {
-            let new_collateral_asset_mv = previous_collateral_asset_mv - asset_mv;
+            let new_collateral_asset_mv = previous_collateral_asset_mv.saturating_sub(asset_mv);
 
             if new_collateral_asset_mv > 0 && new_collateral_asset_mv < min_accepted_net_value {
                 msg!(
@@ -2852,9 +4153,10 @@ pub mod utils {
 
             let new_ltv = debt_value_bf / new_total_deposited_mv;
 
-            let new_unhealthy_borrow_value =
-                Fraction::from_bits(obligation.unhealthy_borrow_value_sf)
-                    - asset_mv * Fraction::from_percent(reserve_liquidation_threshold_pct);
+            let new_unhealthy_borrow_value = Fraction::from_bits(
+                obligation.unhealthy_borrow_value_sf,
+            )
+            .saturating_sub(asset_mv * Fraction::from_percent(reserve_liquidation_threshold_pct));
 
             let new_unhealthy_ltv = new_unhealthy_borrow_value / new_total_deposited_mv;
 
@@ -2991,7 +4293,7 @@ pub mod utils {
         liquidity_asset_mv: Fraction,
         min_accepted_net_value: Fraction,
     ) -> Result<()> {
-        let asset_mv = calculate_market_value_from_liquidity_amount(reserve, amount);
+        let asset_mv = calculate_debt_market_value_from_liquidity_amount(reserve, amount);
 
         let new_total_bf_debt_mv =
             Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf)
@@ -3033,6 +4335,33 @@ pub mod utils {
         Ok(())
     }
 
+    pub fn check_delegate_borrow_ltv(
+        amount: Fraction,
+        obligation: &Obligation,
+        reserve: &Reserve,
+    ) -> Result<()> {
+        let asset_mv = calculate_debt_market_value_from_liquidity_amount(reserve, amount);
+
+        let new_total_bf_debt_mv =
+            Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf)
+                + asset_mv
+                    * reserve.borrow_factor_f(obligation.elevation_group != ELEVATION_GROUP_NONE);
+        let new_ltv = new_total_bf_debt_mv / Fraction::from_bits(obligation.deposited_value_sf);
+        let max_ltv = Fraction::from_percent(obligation.delegate_max_borrow_ltv_pct);
+
+        if new_ltv > max_ltv {
+            msg!(
+                "Obligation new LTV/delegate max LTV after borrow {:.2}/{:.2} of {}",
+                new_ltv.to_display(),
+                max_ltv.to_display(),
+                reserve.token_symbol()
+            );
+            return err!(LendingError::DelegateBorrowLtvExceeded);
+        }
+
+        Ok(())
+    }
+
     pub fn post_repay_obligation_invariants(
         amount: Fraction,
         obligation: &Obligation,
@@ -3040,15 +4369,16 @@ pub mod utils {
         liquidity_asset_mv: Fraction,
         min_accepted_net_value: Fraction,
     ) -> Result<()> {
-        let asset_mv = calculate_market_value_from_liquidity_amount(reserve, amount);
+        let asset_mv = calculate_debt_market_value_from_liquidity_amount(reserve, amount);
         let new_total_bf_debt_mv =
-            Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf)
-                - asset_mv
-                    * reserve.borrow_factor_f(obligation.elevation_group != ELEVATION_GROUP_NONE);
+            Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf).saturating_sub(
+                asset_mv
+                    * reserve.borrow_factor_f(obligation.elevation_group != ELEVATION_GROUP_NONE),
+            );
         let total_deposited_mv = Fraction::from_bits(obligation.deposited_value_sf);
 
         if liquidity_asset_mv != 0 {
-            let new_liquidity_asset_mv = liquidity_asset_mv - asset_mv;
+            let new_liquidity_asset_mv = liquidity_asset_mv.saturating_sub(asset_mv);
 
             if new_liquidity_asset_mv > 0 && new_liquidity_asset_mv < min_accepted_net_value {
                 msg!(
@@ -3076,6 +4406,85 @@ pub mod utils {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn post_refinance_debt_obligation_invariants(
+        obligation: &Obligation,
+        borrow_reserve: &Reserve,
+        borrow_amount: Fraction,
+        borrow_liquidity_mv: Fraction,
+        repay_reserve: &Reserve,
+        repay_amount: Fraction,
+        repay_liquidity_mv: Fraction,
+        min_accepted_net_value: Fraction,
+    ) -> Result<()> {
+        let is_in_elevation_group = obligation.elevation_group != ELEVATION_GROUP_NONE;
+        let borrow_mv =
+            calculate_debt_market_value_from_liquidity_amount(borrow_reserve, borrow_amount);
+        let repay_mv =
+            calculate_debt_market_value_from_liquidity_amount(repay_reserve, repay_amount);
+
+        let initial_bf_debt_mv =
+            Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf);
+        let new_total_bf_debt_mv = (initial_bf_debt_mv
+            + borrow_mv * borrow_reserve.borrow_factor_f(is_in_elevation_group))
+        .saturating_sub(repay_mv * repay_reserve.borrow_factor_f(is_in_elevation_group));
+        let new_total_no_bf_debt_mv =
+            (Fraction::from_bits(obligation.borrowed_assets_market_value_sf) + borrow_mv)
+                .saturating_sub(repay_mv);
+        let total_deposited_mv = Fraction::from_bits(obligation.deposited_value_sf);
+
+        let new_borrow_liquidity_mv = borrow_liquidity_mv + borrow_mv;
+        if new_borrow_liquidity_mv < min_accepted_net_value {
+            msg!(
+                "Obligation new borrowed value after refinance {} for {}",
+                new_borrow_liquidity_mv.to_display(),
+                borrow_reserve.token_symbol()
+            );
+            return err!(LendingError::NetValueRemainingTooSmall);
+        }
+
+        let new_repay_liquidity_mv = repay_liquidity_mv.saturating_sub(repay_mv);
+        if new_repay_liquidity_mv > 0 && new_repay_liquidity_mv < min_accepted_net_value {
+            msg!(
+                "Obligation new borrowed value after refinance {} for {}",
+                new_repay_liquidity_mv.to_display(),
+                repay_reserve.token_symbol()
+            );
+            return err!(LendingError::NetValueRemainingTooSmall);
+        }
+
+        if new_total_bf_debt_mv > initial_bf_debt_mv
+            && new_total_bf_debt_mv > Fraction::from_bits(obligation.allowed_borrow_value_sf)
+        {
+            msg!(
+                "Obligation debt after refinance {} exceeds the allowed borrow value {}",
+                new_total_bf_debt_mv.to_display(),
+                Fraction::from_bits(obligation.allowed_borrow_value_sf).to_display()
+            );
+            return err!(LendingError::BorrowTooLarge);
+        }
+
+        let new_ltv = new_total_bf_debt_mv / total_deposited_mv;
+        if new_ltv > obligation.unhealthy_loan_to_value() {
+            msg!(
+                "Obligation new LTV/new unhealthy LTV after refinance {:.2}/{:.2}",
+                new_ltv.to_display(),
+                obligation.unhealthy_loan_to_value().to_display()
+            );
+            return err!(LendingError::WorseLTVThanUnhealthyLTV);
+        }
+
+        if new_total_no_bf_debt_mv >= total_deposited_mv {
+            msg!(
+                "Obligation can't have more liabilities than assets after refinance {}",
+                new_total_no_bf_debt_mv.to_display()
+            );
+            return err!(LendingError::LiabilitiesBiggerThanAssets);
+        }
+
+        Ok(())
+    }
+
     pub fn get_elevation_group(
         elevation_group_id: u8,
         market: &LendingMarket,
@@ -3122,10 +4531,11 @@ pub mod utils {
     pub fn check_obligation_fully_refreshed_and_not_null(
         obligation: &Obligation,
         slot: Slot,
+        required_price_status: PriceStatusFlags,
     ) -> Result<()> {
         if obligation
             .last_update
-            .is_stale(slot, PriceStatusFlags::ALL_CHECKS)?
+            .is_stale(slot, required_price_status)?
         {
             msg!(
             "Obligation is stale and must be refreshed in the current slot, price status: {:08b}",
@@ -3157,9 +4567,18 @@ pub mod utils {
             return err!(LendingError::InvalidAmount);
         }
 
+        let repay_required_price_status = repay_reserve
+            .config
+            .required_price_status
+            .for_action(ReserveAction::Liquidate);
+        let withdraw_required_price_status = withdraw_reserve
+            .config
+            .required_price_status
+            .for_action(ReserveAction::Liquidate);
+
         if repay_reserve
             .last_update
-            .is_stale(slot, PriceStatusFlags::LIQUIDATION_CHECKS)?
+            .is_stale(slot, repay_required_price_status)?
         {
             msg!(
                 "Repay reserve is stale and must be refreshed in the current slot, price status: {:08b}",
@@ -3170,7 +4589,7 @@ pub mod utils {
 
         if withdraw_reserve
             .last_update
-            .is_stale(slot, PriceStatusFlags::LIQUIDATION_CHECKS)?
+            .is_stale(slot, withdraw_required_price_status)?
         {
             msg!(
                 "Withdraw reserve is stale and must be refreshed in the current slot, price status: {:08b}",
@@ -3241,6 +4660,10 @@ pub mod utils {
             msg!("Invalid reserve token info");
             return err!(LendingError::InvalidOracleConfig);
         }
+        if !config.required_price_status.is_valid() {
+            msg!("Invalid reserve required price status");
+            return err!(LendingError::InvalidConfig);
+        }
         if !config.token_info.is_twap_config_valid() {
             msg!("Invalid reserve token twap config");
             return err!(LendingError::InvalidTwapConfig);
@@ -3296,7 +4719,7 @@ pub mod utils {
                     return err!(LendingError::InvalidConfig);
                 }
 
-                if elevation_group.debt_reserve != reserve_address {
+                if !elevation_group.is_debt_reserve(&reserve_address) {
                     if elevation_group.max_liquidation_bonus_bps > config.max_liquidation_bonus_bps
                     {
                         msg!("Invalid max liquidation bonus in elevation group {elevation_group_id}, elevation group's liquidation bonus must be less than the config's",);
@@ -3332,12 +4755,21 @@ pub mod utils {
             }
         }
 
+        if config.supply_fees.deposit_fee_bps >= FULL_BPS
+            || config.supply_fees.withdrawal_fee_bps >= FULL_BPS
+        {
+            msg!("Deposit and withdrawal fees must be lower than 100%");
+            return err!(LendingError::InvalidConfig);
+        }
+
         if config.utilization_limit_block_borrowing_above_pct > 100 {
             msg!("Utilization limit to block borrows above cannot be bigger than 100%");
             return err!(LendingError::InvalidConfig);
         }
 
-        config.borrow_rate_curve.validate()?;
+        config
+            .borrow_rate_curve
+            .validate_extended(&config.borrow_rate_curve_extension)?;
         Ok(())
     }
 
@@ -3403,3 +4835,339 @@ pub mod utils {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Ref, RefCell};
+
+    use super::*;
+
+    const SLOT: Slot = 1_000;
+
+    fn refreshed_obligation(
+        collateral_reserve_pk: Pubkey,
+        deposited_amount: u64,
+        deposited_value: u64,
+        debt_value: u64,
+    ) -> Obligation {
+        let mut obligation = Obligation::default();
+        obligation.deposits[0].deposit_reserve = collateral_reserve_pk;
+        obligation.deposits[0].deposited_amount = deposited_amount;
+        obligation.deposits[0].market_value_sf = Fraction::from(deposited_value).to_bits();
+        obligation.deposited_value_sf = Fraction::from(deposited_value).to_bits();
+        obligation.borrowed_assets_market_value_sf = Fraction::from(debt_value).to_bits();
+        obligation
+            .last_update
+            .update_slot(SLOT, PriceStatusFlags::ALL_CHECKS);
+        obligation
+    }
+
+    fn debt_reserve(mint_decimals: u64, price: u64) -> Reserve {
+        let mut reserve = Reserve::default();
+        reserve.liquidity.mint_decimals = mint_decimals;
+        reserve.liquidity.market_price_sf = Fraction::from(price).to_bits();
+        reserve
+    }
+
+    #[test]
+    fn test_calculate_leverage_adjustment_increase() {
+        let collateral_reserve_pk = Pubkey::new_unique();
+        let obligation = refreshed_obligation(collateral_reserve_pk, 1_000, 1_000, 0);
+
+        let adjustment = calculate_leverage_adjustment(
+            &obligation,
+            &Reserve::default(),
+            &debt_reserve(6, 1),
+            collateral_reserve_pk,
+            50,
+            SLOT,
+        )
+        .unwrap();
+
+        assert_eq!(
+            adjustment,
+            LeverageAdjustment::Increase {
+                borrow_liquidity_amount: 1_000_000_000
+            }
+        );
+    }
+
+    #[test]
+    fn test_calculate_leverage_adjustment_increase_with_existing_debt() {
+        let collateral_reserve_pk = Pubkey::new_unique();
+        let obligation = refreshed_obligation(collateral_reserve_pk, 2_000, 2_000, 500);
+
+        let adjustment = calculate_leverage_adjustment(
+            &obligation,
+            &Reserve::default(),
+            &debt_reserve(9, 2),
+            collateral_reserve_pk,
+            75,
+            SLOT,
+        )
+        .unwrap();
+
+        assert_eq!(
+            adjustment,
+            LeverageAdjustment::Increase {
+                borrow_liquidity_amount: 2_000_000_000_000
+            }
+        );
+    }
+
+    #[test]
+    fn test_calculate_leverage_adjustment_decrease() {
+        let collateral_reserve_pk = Pubkey::new_unique();
+        let obligation = refreshed_obligation(collateral_reserve_pk, 1_000, 1_000, 750);
+
+        let adjustment = calculate_leverage_adjustment(
+            &obligation,
+            &Reserve::default(),
+            &debt_reserve(6, 1),
+            collateral_reserve_pk,
+            50,
+            SLOT,
+        )
+        .unwrap();
+
+        assert_eq!(
+            adjustment,
+            LeverageAdjustment::Decrease {
+                withdraw_collateral_amount: 500
+            }
+        );
+    }
+
+    #[test]
+    fn test_calculate_leverage_adjustment_decrease_capped_at_deposit() {
+        let collateral_reserve_pk = Pubkey::new_unique();
+        let mut obligation = refreshed_obligation(collateral_reserve_pk, 500, 500, 900);
+        obligation.deposited_value_sf = Fraction::from(2_000_u64).to_bits();
+
+        let adjustment = calculate_leverage_adjustment(
+            &obligation,
+            &Reserve::default(),
+            &debt_reserve(6, 1),
+            collateral_reserve_pk,
+            0,
+            SLOT,
+        )
+        .unwrap();
+
+        assert_eq!(
+            adjustment,
+            LeverageAdjustment::Decrease {
+                withdraw_collateral_amount: 500
+            }
+        );
+    }
+
+    #[test]
+    fn test_calculate_leverage_adjustment_already_at_target() {
+        let collateral_reserve_pk = Pubkey::new_unique();
+        let obligation = refreshed_obligation(collateral_reserve_pk, 1_000, 1_000, 500);
+
+        let res = calculate_leverage_adjustment(
+            &obligation,
+            &Reserve::default(),
+            &debt_reserve(6, 1),
+            collateral_reserve_pk,
+            50,
+            SLOT,
+        );
+
+        assert_eq!(res.unwrap_err(), LendingError::InvalidAmount.into());
+    }
+
+    #[test]
+    fn test_calculate_leverage_adjustment_invalid_target() {
+        let collateral_reserve_pk = Pubkey::new_unique();
+        let obligation = refreshed_obligation(collateral_reserve_pk, 1_000, 1_000, 0);
+
+        let res = calculate_leverage_adjustment(
+            &obligation,
+            &Reserve::default(),
+            &debt_reserve(6, 1),
+            collateral_reserve_pk,
+            100,
+            SLOT,
+        );
+
+        assert_eq!(
+            res.unwrap_err(),
+            LendingError::LeverageTargetLtvInvalid.into()
+        );
+    }
+
+    #[test]
+    fn test_calculate_leverage_adjustment_stale_obligation() {
+        let collateral_reserve_pk = Pubkey::new_unique();
+        let mut obligation = refreshed_obligation(collateral_reserve_pk, 1_000, 1_000, 0);
+        obligation.last_update.mark_stale();
+
+        let res = calculate_leverage_adjustment(
+            &obligation,
+            &Reserve::default(),
+            &debt_reserve(6, 1),
+            collateral_reserve_pk,
+            50,
+            SLOT,
+        );
+
+        assert_eq!(res.unwrap_err(), LendingError::ObligationStale.into());
+    }
+
+    struct TestReserveLoader {
+        pubkey: Pubkey,
+        reserve: RefCell<Reserve>,
+    }
+
+    impl TestReserveLoader {
+        fn new(elevation_groups: &[u8]) -> Self {
+            let mut reserve = Reserve::default();
+            reserve.config.loan_to_value_pct = 50;
+            reserve.config.elevation_groups[..elevation_groups.len()]
+                .copy_from_slice(elevation_groups);
+            Self {
+                pubkey: Pubkey::new_unique(),
+                reserve: RefCell::new(reserve),
+            }
+        }
+    }
+
+    impl<'info> AnyAccountLoader<'info, Reserve> for &TestReserveLoader {
+        fn get_mut(&self) -> Result<RefMut<Reserve>> {
+            Ok(self.reserve.borrow_mut())
+        }
+
+        fn get(&self) -> Result<Ref<Reserve>> {
+            Ok(self.reserve.borrow())
+        }
+
+        fn get_pubkey(&self) -> Pubkey {
+            self.pubkey
+        }
+    }
+
+    fn lending_market_with_elevation_groups(debt_reserve: Pubkey) -> LendingMarket {
+        let mut lending_market = LendingMarket::default();
+        for (id, ltv_pct) in [(1, 80), (2, 90)] {
+            let mut elevation_group = ElevationGroup::default();
+            elevation_group.id = id;
+            elevation_group.ltv_pct = ltv_pct;
+            elevation_group.liquidation_threshold_pct = ltv_pct + 5;
+            elevation_group.allow_new_loans = 1;
+            elevation_group.debt_reserve = debt_reserve;
+            lending_market.set_elevation_group(elevation_group).unwrap();
+        }
+        lending_market
+    }
+
+    #[test]
+    fn test_find_best_elevation_group_highest_remaining_borrow_value() {
+        let collateral = TestReserveLoader::new(&[1, 2]);
+        let debt = TestReserveLoader::new(&[1, 2]);
+        let lending_market = lending_market_with_elevation_groups(debt.pubkey);
+        let obligation = refreshed_obligation(collateral.pubkey, 1_000, 1_000, 0);
+
+        let best = find_best_elevation_group(
+            &obligation,
+            &lending_market,
+            SLOT,
+            [&collateral].into_iter(),
+            [].into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(best, 2);
+    }
+
+    #[test]
+    fn test_find_best_elevation_group_skips_disabled_and_unsupported_groups() {
+        let collateral = TestReserveLoader::new(&[1, 2]);
+        let debt = TestReserveLoader::new(&[1, 2]);
+        let mut lending_market = lending_market_with_elevation_groups(debt.pubkey);
+        lending_market.elevation_groups[1].allow_new_loans = 0;
+        let obligation = refreshed_obligation(collateral.pubkey, 1_000, 1_000, 0);
+
+        let best = find_best_elevation_group(
+            &obligation,
+            &lending_market,
+            SLOT,
+            [&collateral].into_iter(),
+            [].into_iter(),
+        )
+        .unwrap();
+        assert_eq!(best, 1);
+
+        let collateral = TestReserveLoader::new(&[]);
+        let obligation = refreshed_obligation(collateral.pubkey, 1_000, 1_000, 0);
+
+        let best = find_best_elevation_group(
+            &obligation,
+            &lending_market,
+            SLOT,
+            [&collateral].into_iter(),
+            [].into_iter(),
+        )
+        .unwrap();
+        assert_eq!(best, ELEVATION_GROUP_NONE);
+    }
+
+    #[test]
+    fn test_find_best_elevation_group_respects_borrow_limits() {
+        let collateral = TestReserveLoader::new(&[1, 2]);
+        let debt = TestReserveLoader::new(&[1, 2]);
+        let lending_market = lending_market_with_elevation_groups(debt.pubkey);
+        {
+            let mut collateral_reserve = collateral.reserve.borrow_mut();
+            collateral_reserve
+                .config
+                .borrow_limit_against_this_collateral_in_elevation_group[0] = 1_000;
+            collateral_reserve
+                .config
+                .borrow_limit_against_this_collateral_in_elevation_group[1] = 50;
+        }
+
+        let mut obligation = refreshed_obligation(collateral.pubkey, 1_000, 1_000, 100);
+        obligation.borrows[0].borrow_reserve = debt.pubkey;
+        obligation.borrows[0].borrowed_amount_sf = Fraction::from(100_u64).to_bits();
+        obligation.borrows[0].market_value_sf = Fraction::from(100_u64).to_bits();
+
+        let best = find_best_elevation_group(
+            &obligation,
+            &lending_market,
+            SLOT,
+            [&collateral].into_iter(),
+            [&debt].into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(best, 1);
+    }
+
+    #[test]
+    fn test_find_best_elevation_group_no_eligible_group() {
+        let collateral = TestReserveLoader::new(&[1, 2]);
+        let debt = TestReserveLoader::new(&[]);
+        let lending_market = lending_market_with_elevation_groups(debt.pubkey);
+
+        let mut obligation = refreshed_obligation(collateral.pubkey, 1_000, 1_000, 100);
+        obligation.borrows[0].borrow_reserve = debt.pubkey;
+        obligation.borrows[0].borrowed_amount_sf = Fraction::from(100_u64).to_bits();
+        obligation.borrows[0].market_value_sf = Fraction::from(100_u64).to_bits();
+
+        let res = find_best_elevation_group(
+            &obligation,
+            &lending_market,
+            SLOT,
+            [&collateral].into_iter(),
+            [&debt].into_iter(),
+        );
+
+        assert_eq!(
+            res.unwrap_err(),
+            LendingError::InconsistentElevationGroup.into()
+        );
+    }
+}
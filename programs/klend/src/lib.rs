@@ -70,14 +70,28 @@ pub mod kamino_lending {
         handler_update_reserve_config::process(ctx, mode, &value, skip_validation)
     }
 
-    pub fn redeem_fees(ctx: Context<RedeemFees>) -> Result<()> {
+    pub fn redeem_fees<'info>(ctx: Context<'_, '_, '_, 'info, RedeemFees<'info>>) -> Result<()> {
         handler_redeem_fees::process(ctx)
     }
 
-    pub fn withdraw_protocol_fee(ctx: Context<WithdrawProtocolFees>, amount: u64) -> Result<()> {
+    pub fn withdraw_protocol_fee<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawProtocolFees<'info>>,
+        amount: u64,
+    ) -> Result<()> {
         handler_withdraw_protocol_fees::process(ctx, amount)
     }
 
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn distribute_protocol_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, DistributeProtocolFees<'info>>,
+    ) -> Result<()> {
+        handler_distribute_protocol_fees::process(ctx)
+    }
+
+    pub fn reinvest_protocol_fees(ctx: Context<ReinvestProtocolFees>, amount: u64) -> Result<()> {
+        handler_reinvest_protocol_fees::process(ctx, amount)
+    }
+
     #[deprecated(
         since = "1.8.0",
         note = "Please use `_v2` variant of the handler instead"
@@ -110,25 +124,68 @@ pub mod kamino_lending {
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn deposit_reserve_liquidity(
-        ctx: Context<DepositReserveLiquidity>,
+    pub fn deposit_reserve_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositReserveLiquidity<'info>>,
         liquidity_amount: u64,
     ) -> Result<()> {
         handler_deposit_reserve_liquidity::process(ctx, liquidity_amount)
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn redeem_reserve_collateral(
-        ctx: Context<RedeemReserveCollateral>,
+    pub fn deposit_reserve_liquidity_native<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositReserveLiquidityNative<'info>>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        handler_deposit_reserve_liquidity_native::process(ctx, liquidity_amount)
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn donate_to_reserve<'info>(
+        ctx: Context<'_, '_, '_, 'info, DonateToReserve<'info>>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        handler_donate_to_reserve::process(ctx, liquidity_amount)
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn redeem_reserve_collateral<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemReserveCollateral<'info>>,
         collateral_amount: u64,
     ) -> Result<()> {
         handler_redeem_reserve_collateral::process(ctx, collateral_amount)
     }
 
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn redeem_reserve_collateral_native<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemReserveCollateralNative<'info>>,
+        collateral_amount: u64,
+    ) -> Result<()> {
+        handler_redeem_reserve_collateral_native::process(ctx, collateral_amount)
+    }
+
     pub fn init_obligation(ctx: Context<InitObligation>, args: InitObligationArgs) -> Result<()> {
         handler_init_obligation::process(ctx, args)
     }
 
+    pub fn close_obligation<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseObligation<'info>>,
+    ) -> Result<()> {
+        handler_close_obligation::process(ctx)
+    }
+
+    pub fn propose_obligation_owner(
+        ctx: Context<ProposeObligationOwner>,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        handler_propose_obligation_owner::process(ctx, new_owner)
+    }
+
+    pub fn accept_obligation_owner<'info>(
+        ctx: Context<'_, '_, '_, 'info, AcceptObligationOwner<'info>>,
+    ) -> Result<()> {
+        handler_accept_obligation_owner::process(ctx)
+    }
+
     pub fn init_obligation_farms_for_reserve(
         ctx: Context<InitObligationFarmsForReserve>,
         mode: u8,
@@ -211,29 +268,37 @@ pub mod kamino_lending {
         handler_borrow_obligation_liquidity::process_v2(ctx, liquidity_amount)
     }
 
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn borrow_obligation_liquidity_native<'info>(
+        ctx: Context<'_, '_, '_, 'info, BorrowObligationLiquidityNative<'info>>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        handler_borrow_obligation_liquidity_native::process(ctx, liquidity_amount)
+    }
+
     #[deprecated(
         since = "1.8.0",
         note = "Please use `_v2` variant of the handler instead"
     )]
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn repay_obligation_liquidity(
-        ctx: Context<RepayObligationLiquidity>,
+    pub fn repay_obligation_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, RepayObligationLiquidity<'info>>,
         liquidity_amount: u64,
     ) -> Result<()> {
         handler_repay_obligation_liquidity::process_v1(ctx, liquidity_amount)
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.repay_accounts.lending_market))]
-    pub fn repay_obligation_liquidity_v2(
-        ctx: Context<RepayObligationLiquidityV2>,
+    pub fn repay_obligation_liquidity_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, RepayObligationLiquidityV2<'info>>,
         liquidity_amount: u64,
     ) -> Result<()> {
         handler_repay_obligation_liquidity::process_v2(ctx, liquidity_amount)
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.repay_accounts.lending_market))]
-    pub fn repay_and_withdraw_and_redeem(
-        ctx: Context<RepayAndWithdraw>,
+    pub fn repay_and_withdraw_and_redeem<'info>(
+        ctx: Context<'_, '_, '_, 'info, RepayAndWithdraw<'info>>,
         repay_amount: u64,
         withdraw_collateral_amount: u64,
     ) -> Result<()> {
@@ -254,8 +319,8 @@ pub mod kamino_lending {
         note = "Please use `_v2` variant of the handler instead"
     )]
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn deposit_reserve_liquidity_and_obligation_collateral(
-        ctx: Context<DepositReserveLiquidityAndObligationCollateral>,
+    pub fn deposit_reserve_liquidity_and_obligation_collateral<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositReserveLiquidityAndObligationCollateral<'info>>,
         liquidity_amount: u64,
     ) -> Result<()> {
         handler_deposit_reserve_liquidity_and_obligation_collateral::process_v1(
@@ -265,8 +330,8 @@ pub mod kamino_lending {
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.deposit_accounts.lending_market))]
-    pub fn deposit_reserve_liquidity_and_obligation_collateral_v2(
-        ctx: Context<DepositReserveLiquidityAndObligationCollateralV2>,
+    pub fn deposit_reserve_liquidity_and_obligation_collateral_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositReserveLiquidityAndObligationCollateralV2<'info>>,
         liquidity_amount: u64,
     ) -> Result<()> {
         handler_deposit_reserve_liquidity_and_obligation_collateral::process_v2(
@@ -275,13 +340,36 @@ pub mod kamino_lending {
         )
     }
 
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn deposit_reserve_liquidity_and_obligation_collateral_native<'info>(
+        ctx: Context<
+            '_,
+            '_,
+            '_,
+            'info,
+            DepositReserveLiquidityAndObligationCollateralNative<'info>,
+        >,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        handler_deposit_reserve_liquidity_and_obligation_collateral_native::process(
+            ctx,
+            liquidity_amount,
+        )
+    }
+
     #[deprecated(
         since = "1.8.0",
         note = "Please use `_v2` variant of the handler instead"
     )]
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral(
-        ctx: Context<WithdrawObligationCollateralAndRedeemReserveCollateral>,
+    pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral<'info>(
+        ctx: Context<
+            '_,
+            '_,
+            '_,
+            'info,
+            WithdrawObligationCollateralAndRedeemReserveCollateral<'info>,
+        >,
         collateral_amount: u64,
     ) -> Result<()> {
         handler_withdraw_obligation_collateral_and_redeem_reserve_collateral::process_v1(
@@ -292,8 +380,14 @@ pub mod kamino_lending {
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.withdraw_accounts.lending_market))]
-    pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral_v2(
-        ctx: Context<WithdrawObligationCollateralAndRedeemReserveCollateralV2>,
+    pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral_v2<'info>(
+        ctx: Context<
+            '_,
+            '_,
+            '_,
+            'info,
+            WithdrawObligationCollateralAndRedeemReserveCollateralV2<'info>,
+        >,
         collateral_amount: u64,
     ) -> Result<()> {
         handler_withdraw_obligation_collateral_and_redeem_reserve_collateral::process_v2(
@@ -302,13 +396,30 @@ pub mod kamino_lending {
         )
     }
 
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral_native<'info>(
+        ctx: Context<
+            '_,
+            '_,
+            '_,
+            'info,
+            WithdrawObligationCollateralAndRedeemReserveCollateralNative<'info>,
+        >,
+        collateral_amount: u64,
+    ) -> Result<()> {
+        handler_withdraw_obligation_collateral_and_redeem_reserve_collateral_native::process(
+            ctx,
+            collateral_amount,
+        )
+    }
+
     #[deprecated(
         since = "1.8.0",
         note = "Please use `_v2` variant of the handler instead"
     )]
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn liquidate_obligation_and_redeem_reserve_collateral(
-        ctx: Context<LiquidateObligationAndRedeemReserveCollateral>,
+    pub fn liquidate_obligation_and_redeem_reserve_collateral<'info>(
+        ctx: Context<'_, '_, '_, 'info, LiquidateObligationAndRedeemReserveCollateral<'info>>,
         liquidity_amount: u64,
         min_acceptable_received_liquidity_amount: u64,
         max_allowed_ltv_override_percent: u64,
@@ -322,8 +433,8 @@ pub mod kamino_lending {
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.liquidation_accounts.lending_market))]
-    pub fn liquidate_obligation_and_redeem_reserve_collateral_v2(
-        ctx: Context<LiquidateObligationAndRedeemReserveCollateralV2>,
+    pub fn liquidate_obligation_and_redeem_reserve_collateral_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, LiquidateObligationAndRedeemReserveCollateralV2<'info>>,
         liquidity_amount: u64,
         min_acceptable_received_liquidity_amount: u64,
         max_allowed_ltv_override_percent: u64,
@@ -337,8 +448,64 @@ pub mod kamino_lending {
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn flash_repay_reserve_liquidity(
-        ctx: Context<FlashRepayReserveLiquidity>,
+    pub fn move_obligation_collateral(
+        ctx: Context<MoveObligationCollateral>,
+        collateral_amount: u64,
+    ) -> Result<()> {
+        handler_move_obligation_collateral::process(ctx, collateral_amount)
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn move_obligation_debt<'info>(
+        ctx: Context<'_, '_, '_, 'info, MoveObligationDebt<'info>>,
+        liquidity_amount: u64,
+    ) -> Result<()> {
+        handler_move_obligation_debt::process(ctx, liquidity_amount)
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn adjust_leverage<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdjustLeverage<'info>>,
+        target_ltv_pct: u8,
+        min_swap_out_amount: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        handler_adjust_leverage::process(ctx, target_ltv_pct, min_swap_out_amount, swap_data)
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn refinance_debt<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefinanceDebt<'info>>,
+        borrow_liquidity_amount: u64,
+        min_repay_liquidity_amount: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        handler_refinance_debt::process(
+            ctx,
+            borrow_liquidity_amount,
+            min_repay_liquidity_amount,
+            swap_data,
+        )
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn swap_collateral<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapCollateral<'info>>,
+        withdraw_collateral_amount: u64,
+        min_deposit_liquidity_amount: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        handler_swap_collateral::process(
+            ctx,
+            withdraw_collateral_amount,
+            min_deposit_liquidity_amount,
+            swap_data,
+        )
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn flash_repay_reserve_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashRepayReserveLiquidity<'info>>,
         liquidity_amount: u64,
         borrow_instruction_index: u8,
     ) -> Result<()> {
@@ -350,8 +517,8 @@ pub mod kamino_lending {
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn flash_borrow_reserve_liquidity(
-        ctx: Context<FlashBorrowReserveLiquidity>,
+    pub fn flash_borrow_reserve_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashBorrowReserveLiquidity<'info>>,
         liquidity_amount: u64,
     ) -> Result<()> {
         handler_flash_borrow_reserve_liquidity::process(ctx, liquidity_amount)
@@ -365,6 +532,35 @@ pub mod kamino_lending {
         handler_request_elevation_group::process(ctx, elevation_group)
     }
 
+    pub fn get_best_elevation_group(ctx: Context<GetBestElevationGroup>) -> Result<u8> {
+        handler_get_best_elevation_group::process(ctx)
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn update_obligation_delegate(
+        ctx: Context<UpdateObligationDelegate>,
+        delegate: Pubkey,
+        permissions: u8,
+        max_borrow_ltv_pct: u8,
+    ) -> Result<()> {
+        handler_update_obligation_delegate::process(ctx, delegate, permissions, max_borrow_ltv_pct)
+    }
+
+    #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
+    pub fn set_obligation_protection_order(
+        ctx: Context<SetObligationProtectionOrder>,
+        trigger_ltv_pct: u8,
+        target_ltv_pct: u8,
+        bonus_bps: u16,
+    ) -> Result<()> {
+        handler_set_obligation_protection_order::process(
+            ctx,
+            trigger_ltv_pct,
+            target_ltv_pct,
+            bonus_bps,
+        )
+    }
+
     pub fn init_referrer_token_state(ctx: Context<InitReferrerTokenState>) -> Result<()> {
         handler_init_referrer_token_state::process(ctx)
     }
@@ -377,10 +573,20 @@ pub mod kamino_lending {
     }
 
     #[access_control(emergency_mode_disabled(&ctx.accounts.lending_market))]
-    pub fn withdraw_referrer_fees(ctx: Context<WithdrawReferrerFees>) -> Result<()> {
+    pub fn withdraw_referrer_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawReferrerFees<'info>>,
+    ) -> Result<()> {
         handler_withdraw_referrer_fees::process(ctx)
     }
 
+    pub fn close_referrer_token_state(ctx: Context<CloseReferrerTokenState>) -> Result<()> {
+        handler_close_referrer_token_state::process(ctx)
+    }
+
+    pub fn close_user_metadata(ctx: Context<CloseUserMetadata>) -> Result<()> {
+        handler_close_user_metadata::process(ctx)
+    }
+
     pub fn init_referrer_state_and_short_url(
         ctx: Context<InitReferrerStateAndShortUrl>,
         short_url: String,
@@ -626,7 +832,7 @@ pub enum LendingError {
     ElevationGroupDebtReserveAsCollateral,
     #[msg("Obligation have more collateral than the maximum allowed by the elevation group")]
     ObligationCollateralExceedsElevationGroupLimit,
-    #[msg("Obligation is an elevation group but have more than one debt reserve")]
+    #[msg("[DEPRECATED] Obligation is an elevation group but have more than one debt reserve")]
     ObligationElevationGroupMultipleDebtReserve,
     #[msg("Mint has a token (2022) extension that is not supported")]
     UnsupportedTokenExtension,
@@ -658,6 +864,48 @@ pub enum LendingError {
     RepayTooSmallForFullLiquidation,
     #[msg("Liquidator provided repay amount lower than required by liquidation rules")]
     InsufficientRepayAmount,
+    #[msg("Borrowing above the reserve max borrow rate is disabled")]
+    BorrowingAboveMaxBorrowRateDisabled,
+    #[msg("Deposit or withdrawal amount is too small to cover the supply fees")]
+    AmountTooSmallForSupplyFees,
+    #[msg("No fee recipients are configured for the lending market")]
+    FeeRecipientsNotConfigured,
+    #[msg("Borrow would exceed the max LTV allowed for the obligation delegate")]
+    DelegateBorrowLtvExceeded,
+    #[msg("Obligation still has deposits or borrows")]
+    ObligationNotEmpty,
+    #[msg("Obligation farms must be refreshed to a zero stake before closing")]
+    ObligationFarmsNotSettled,
+    #[msg("Referrer token state still has unclaimed fees")]
+    ReferrerTokenStateNotEmpty,
+    #[msg("Obligation referrer cannot change while the obligation has debt")]
+    ObligationReferrerChangeWithDebt,
+    #[msg("Swap adapter program is not whitelisted for the lending market")]
+    SwapAdapterNotWhitelisted,
+    #[msg("Swap output is lower than the minimum accepted amount")]
+    SwapCollateralSlippageExceeded,
+    #[msg("Obligation would exceed its max LTV after the collateral swap")]
+    SwapCollateralLtvExceeded,
+    #[msg("Swap output is lower than the minimum accepted debt repayment")]
+    RefinanceDebtSlippageExceeded,
+    #[msg("Target LTV for the leverage adjustment is invalid")]
+    LeverageTargetLtvInvalid,
+    #[msg("Swap output is lower than the minimum accepted leverage adjustment amount")]
+    AdjustLeverageSlippageExceeded,
+    #[msg("Obligation would exceed its max LTV after the leverage adjustment")]
+    LeverageLtvExceeded,
+    #[msg("Obligation protection order parameters are invalid")]
+    InvalidProtectionOrder,
+    #[msg("Oracle slot price account does not match configuration")]
+    InvalidOracleSlotAccount,
+    #[msg("Borrowing is paused because a reserve price circuit breaker is tripped")]
+    PriceCircuitBreakerTripped,
+    #[msg("Token transfer fee does not match the expected amount")]
+    InvalidTransferFee,
+    #[msg("Native SOL instructions are only supported for the wrapped SOL reserve")]
+    ReserveNotNativeSol,
+    #[msg("Obligation cannot track farms for more reserves")]
+    ObligationFarmReservesFull,
 }
 
 pub type LendingResult<T = ()> = std::result::Result<T, LendingError>;
@@ -32,6 +32,7 @@ bitflags! {
         const TWAP_AGE_CHECKED =    0b_0000_1000;
         const HEURISTIC_CHECKED =   0b_0001_0000;
         const PRICE_USAGE_ALLOWED = 0b_0010_0000;
+        const PEG_CHECKED =         0b_0100_0000;
     }
 }
 
@@ -9,10 +9,10 @@ use serde_values::*;
 use super::{serde_bool_u8, serde_string, serde_utf_string};
 use crate::{
     utils::{
-        CLOSE_TO_INSOLVENCY_RISKY_LTV, DEFAULT_MIN_DEPOSIT_AMOUNT, ELEVATION_GROUP_NONE,
+        CLOSE_TO_INSOLVENCY_RISKY_LTV, DEFAULT_MIN_DEPOSIT_AMOUNT, ELEVATION_GROUP_NONE, FULL_BPS,
         GLOBAL_ALLOWED_BORROW_VALUE, LENDING_MARKET_SIZE, LIQUIDATION_CLOSE_FACTOR,
-        LIQUIDATION_CLOSE_VALUE, MAX_LIQUIDATABLE_VALUE_AT_ONCE, MIN_NET_VALUE_IN_OBLIGATION,
-        PROGRAM_VERSION,
+        LIQUIDATION_CLOSE_VALUE, MAX_FEE_RECIPIENTS, MAX_LIQUIDATABLE_VALUE_AT_ONCE,
+        MIN_NET_VALUE_IN_OBLIGATION, PROGRAM_VERSION,
     },
     LendingError,
 };
@@ -97,17 +97,26 @@ pub struct LendingMarket {
 
     pub min_initial_deposit_amount: u64,
 
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fee_recipients: [FeeRecipient; MAX_FEE_RECIPIENTS],
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_string", default))]
+    pub swap_adapter_program: Pubkey,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_swap_collateral_slippage_bps: u64,
+
     #[cfg_attr(
         feature = "serde",
-        serde(skip_deserializing, skip_serializing, default = "default_padding_170")
+        serde(skip_deserializing, skip_serializing, default = "default_padding_145")
     )]
     #[derivative(Debug = "ignore")]
-    pub padding1: [u64; 170],
+    pub padding1: [u64; 145],
 }
 
 #[cfg(feature = "serde")]
-fn default_padding_170() -> [u64; 170] {
-    [0; 170]
+fn default_padding_145() -> [u64; 145] {
+    [0; 145]
 }
 
 #[cfg(feature = "serde")]
@@ -144,7 +153,10 @@ impl Default for LendingMarket {
             name: [0; 32],
             individual_autodeleverage_margin_call_period_secs: 0,
             min_initial_deposit_amount: DEFAULT_MIN_DEPOSIT_AMOUNT,
-            padding1: [0; 170],
+            fee_recipients: [FeeRecipient::default(); MAX_FEE_RECIPIENTS],
+            swap_adapter_program: Pubkey::default(),
+            max_swap_collateral_slippage_bps: 0,
+            padding1: [0; 145],
         }
     }
 }
@@ -190,6 +202,45 @@ impl LendingMarket {
     pub fn is_autodeleverage_enabled(&self) -> bool {
         self.autodeleverage_enabled != false as u8
     }
+
+    pub fn active_fee_recipients(&self) -> impl Iterator<Item = &FeeRecipient> {
+        self.fee_recipients
+            .iter()
+            .filter(|fee_recipient| fee_recipient.is_active())
+    }
+
+    pub fn set_fee_recipient(&mut self, index: usize, fee_recipient: FeeRecipient) -> Result<()> {
+        if index >= MAX_FEE_RECIPIENTS {
+            msg!(
+                "Fee recipient index must be lower than {}",
+                MAX_FEE_RECIPIENTS
+            );
+            return err!(LendingError::InvalidConfig);
+        }
+
+        if fee_recipient.is_active() && fee_recipient.recipient == Pubkey::default() {
+            msg!("Fee recipient with a non-zero share must have a recipient");
+            return err!(LendingError::InvalidConfig);
+        }
+
+        self.fee_recipients[index] = fee_recipient;
+
+        let total_share_bps: u64 = self
+            .fee_recipients
+            .iter()
+            .map(|fee_recipient| u64::from(fee_recipient.share_bps))
+            .sum();
+        if total_share_bps > u64::from(FULL_BPS) {
+            msg!(
+                "Total fee recipients share {} cannot exceed {}",
+                total_share_bps,
+                FULL_BPS
+            );
+            return err!(LendingError::InvalidConfig);
+        }
+
+        Ok(())
+    }
 }
 
 pub struct InitLendingMarketParams {
@@ -221,12 +272,8 @@ pub struct ElevationGroup {
 
     #[cfg_attr(feature = "serde", serde(with = "serde_string", default))]
     pub debt_reserve: Pubkey,
-    #[derivative(Debug = "ignore")]
-    #[cfg_attr(
-        feature = "serde",
-        serde(skip_deserializing, skip_serializing, default)
-    )]
-    pub padding_1: [u64; 4],
+    #[cfg_attr(feature = "serde", serde(with = "serde_string", default))]
+    pub secondary_debt_reserve: Pubkey,
 }
 
 impl Default for ElevationGroup {
@@ -237,6 +284,36 @@ impl Default for ElevationGroup {
     }
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Derivative, PartialEq, Eq, Default)]
+#[derivative(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[zero_copy]
+#[repr(C)]
+pub struct FeeRecipient {
+    #[cfg_attr(feature = "serde", serde(with = "serde_string", default))]
+    pub recipient: Pubkey,
+    pub share_bps: u16,
+
+    #[derivative(Debug = "ignore")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_deserializing, skip_serializing, default)
+    )]
+    pub padding: [u8; 6],
+}
+
+impl FeeRecipient {
+    pub fn is_active(&self) -> bool {
+        self.share_bps > 0
+    }
+
+    pub fn share_of(&self, amount: u64) -> u64 {
+        let share = u128::from(amount) * u128::from(self.share_bps) / u128::from(FULL_BPS);
+        share.try_into().unwrap()
+    }
+}
+
 impl ElevationGroup {
     pub fn new_loans_disabled(&self) -> bool {
         self.allow_new_loans == 0
@@ -245,6 +322,34 @@ impl ElevationGroup {
     pub fn get_index(&self) -> usize {
         self.id as usize - 1
     }
+
+    pub fn debt_reserves(&self) -> impl Iterator<Item = &Pubkey> {
+        [&self.debt_reserve, &self.secondary_debt_reserve]
+            .into_iter()
+            .filter(|debt_reserve| **debt_reserve != Pubkey::default())
+    }
+
+    pub fn is_debt_reserve(&self, reserve: &Pubkey) -> bool {
+        self.get_debt_kind(reserve).is_some()
+    }
+
+    pub fn get_debt_kind(&self, reserve: &Pubkey) -> Option<ElevationGroupDebtKind> {
+        if *reserve == Pubkey::default() {
+            None
+        } else if *reserve == self.debt_reserve {
+            Some(ElevationGroupDebtKind::Primary)
+        } else if *reserve == self.secondary_debt_reserve {
+            Some(ElevationGroupDebtKind::Secondary)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, strum::EnumIter)]
+pub enum ElevationGroupDebtKind {
+    Primary,
+    Secondary,
 }
 
 #[cfg(feature = "serde")]
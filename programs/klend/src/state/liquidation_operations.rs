@@ -69,6 +69,7 @@ pub fn calculate_liquidation(
     let LiquidationParams {
         user_ltv,
         liquidation_bonus_rate,
+        max_liquidatable_debt_value,
     } = get_liquidation_params(
         lending_market,
         collateral_reserve,
@@ -112,6 +113,11 @@ pub fn calculate_liquidation(
             lending_market.insolvency_risk_unhealthy_ltv_pct,
         )
         .min(debt_amount_to_liquidate)
+        .min(
+            max_liquidatable_debt_value
+                .map(|max_value| borrowed_amount_f * (max_value / borrowed_value_f))
+                .unwrap_or(borrowed_amount_f),
+        )
     };
 
     let liquidation_ratio = debt_liquidation_amount_f / borrowed_amount_f;
@@ -198,6 +204,14 @@ pub fn get_liquidation_params(
             params.liquidation_bonus_rate.to_bps::<u64>().unwrap()
         );
         Ok(params)
+    } else if let Some(params) =
+        check_protection_order_obligation(collateral_reserve, debt_reserve, obligation)
+    {
+        xmsg!(
+            "Obligation is eligible for protection order deleveraging with liquidation bonus: {}bps",
+            params.liquidation_bonus_rate.to_bps::<u64>().unwrap()
+        );
+        Ok(params)
     } else {
         xmsg!(
             "Obligation is healthy and cannot be liquidated, LTV: {}",
@@ -247,6 +261,7 @@ pub fn check_liquidate_obligation(
                 user_no_bf_ltv,
                 emode_max_liquidation_bonus_bps,
             ),
+            max_liquidatable_debt_value: None,
         });
     }
     None
@@ -443,6 +458,58 @@ fn check_individual_autodeleverage_obligation(
     Some(LiquidationParams {
         user_ltv,
         liquidation_bonus_rate,
+        max_liquidatable_debt_value: None,
+    })
+}
+
+fn check_protection_order_obligation(
+    collateral_reserve: &Reserve,
+    debt_reserve: &Reserve,
+    obligation: &Obligation,
+) -> Option<LiquidationParams> {
+    if !obligation.has_protection_order() {
+        return None;
+    }
+
+    let user_ltv = obligation.loan_to_value();
+    let trigger_ltv = Fraction::from_percent(obligation.protection_order_trigger_ltv_pct);
+    if user_ltv < trigger_ltv {
+        xmsg!("Obligation has a protection order, but its LTV is below the trigger: {user_ltv}/{trigger_ltv}");
+        return None;
+    }
+
+    let min_reserve_bonus_bps = max(
+        collateral_reserve.config.min_liquidation_bonus_bps,
+        debt_reserve.config.min_liquidation_bonus_bps,
+    );
+    let liquidation_bonus_rate = min(
+        Fraction::from_bps(min(
+            obligation.protection_order_bonus_bps,
+            min_reserve_bonus_bps,
+        )),
+        Fraction::ONE.saturating_sub(obligation.no_bf_loan_to_value()),
+    );
+
+    let target_ltv = Fraction::from_percent(obligation.protection_order_target_ltv_pct);
+    let borrow_factor =
+        debt_reserve.borrow_factor_f(obligation.elevation_group != ELEVATION_GROUP_NONE);
+    let debt_reduction_per_value =
+        borrow_factor.saturating_sub(target_ltv * (Fraction::ONE + liquidation_bonus_rate));
+    if debt_reduction_per_value == Fraction::ZERO {
+        xmsg!("Protection order cannot bring the obligation LTV down to the target with this debt reserve");
+        return None;
+    }
+
+    let debt_value = Fraction::from_bits(obligation.borrow_factor_adjusted_debt_value_sf);
+    let target_debt_value = Fraction::from_bits(obligation.deposited_value_sf) * target_ltv;
+    let max_liquidatable_debt_value =
+        debt_value.saturating_sub(target_debt_value) / debt_reduction_per_value;
+
+    xmsg!("Protection order triggered, LTV: {user_ltv}/{trigger_ltv}, target LTV: {target_ltv}, max liquidatable debt value: {max_liquidatable_debt_value}, liquidation bonus: {liquidation_bonus_rate}");
+    Some(LiquidationParams {
+        user_ltv,
+        liquidation_bonus_rate,
+        max_liquidatable_debt_value: Some(max_liquidatable_debt_value),
     })
 }
 
@@ -518,6 +585,7 @@ fn get_autodeleverage_liquidation_params(
         Some(LiquidationParams {
             user_ltv,
             liquidation_bonus_rate,
+            max_liquidatable_debt_value: None,
         })
     } else {
         xmsg!("LTV is below the current auto-deleverage threshold: {user_ltv}/{autodeleverage_ltv_threshold}, seconds since deleveraging started: {secs_since_deleveraging_started}", );
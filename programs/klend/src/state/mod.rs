@@ -22,7 +22,10 @@ use strum::EnumString;
 pub use token_info::*;
 pub use types::*;
 
-use crate::utils::{borrow_rate_curve::BorrowRateCurve, RESERVE_CONFIG_SIZE};
+use crate::utils::{
+    borrow_rate_curve::{BorrowRateCurve, BorrowRateCurveExtension},
+    RESERVE_CONFIG_SIZE,
+};
 
 pub const VALUE_BYTE_ARRAY_LEN_RESERVE: usize = RESERVE_CONFIG_SIZE;
 pub const VALUE_BYTE_ARRAY_LEN_SHORT_UPDATE: usize = 32;
@@ -39,8 +42,10 @@ pub enum UpdateReserveConfigValue {
     ScopeChain([u16; 4]),
     Name([u8; 32]),
     BorrowRateCurve(BorrowRateCurve),
+    ExtendedBorrowRateCurve(BorrowRateCurve, BorrowRateCurveExtension),
     Full(Box<ReserveConfig>),
     WithdrawalCap(u64, u64),
+    SupplyFee(u16, u64),
     ElevationGroups([u8; 20]),
     ElevationGroupBorrowLimits([u64; 32]),
 }
@@ -61,9 +66,15 @@ impl UpdateReserveConfigValue {
             UpdateReserveConfigValue::Name(v) => v.to_vec(),
             UpdateReserveConfigValue::Full(config) => config.try_to_vec().unwrap(),
             UpdateReserveConfigValue::BorrowRateCurve(curve) => curve.try_to_vec().unwrap(),
+            UpdateReserveConfigValue::ExtendedBorrowRateCurve(curve, extension) => {
+                (*curve, *extension).try_to_vec().unwrap()
+            }
             UpdateReserveConfigValue::WithdrawalCap(cap, interval) => {
                 (*cap, *interval).try_to_vec().unwrap()
             }
+            UpdateReserveConfigValue::SupplyFee(fee_bps, min_fee) => {
+                (*fee_bps, *min_fee).try_to_vec().unwrap()
+            }
             UpdateReserveConfigValue::ElevationGroups(groups) => groups.to_vec(),
             UpdateReserveConfigValue::U8Tuple(mode, value) => (*mode, *value).try_to_vec().unwrap(),
             UpdateReserveConfigValue::ElevationGroupBorrowLimits(e) => e.try_to_vec().unwrap(),
@@ -134,6 +145,17 @@ pub enum UpdateConfigMode {
     UpdateHostFixedInterestRateBps = 47,
     UpdateAutodeleverageEnabled = 48,
     UpdateDeleveragingBonusIncreaseBpsPerDay = 49,
+    UpdateExtendedBorrowRateCurve = 50,
+    UpdateUtilizationEmaHalfLifeSecs = 51,
+    UpdateDepositFee = 52,
+    UpdateWithdrawalFee = 53,
+    UpdateOracleSlot = 54,
+    UpdateTokenInfoPriceEma = 55,
+    UpdateTokenInfoPriceCircuitBreaker = 56,
+    UpdateTokenInfoPythVerificationAndConfidence = 57,
+    UpdateRequiredPriceStatus = 58,
+    UpdateTransferHookProgram = 59,
+    UpdateSecondaryDebtBorrowLimitsInElevationGroupAgainstThisReserve = 60,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, PartialEq, Eq, Clone, Debug)]
@@ -147,6 +169,7 @@ pub enum UpdateLendingMarketConfigValue {
     Pubkey(Pubkey),
     ElevationGroup(ElevationGroup),
     Name([u8; 32]),
+    FeeRecipient(u8, FeeRecipient),
 }
 
 impl UpdateLendingMarketConfigValue {
@@ -180,6 +203,10 @@ impl UpdateLendingMarketConfigValue {
             UpdateLendingMarketConfigValue::Name(v) => {
                 val[..v.len()].copy_from_slice(v);
             }
+            UpdateLendingMarketConfigValue::FeeRecipient(index, fee_recipient) => {
+                let bytes = (*index, *fee_recipient).try_to_vec().unwrap();
+                val[..bytes.len()].copy_from_slice(bytes.as_slice());
+            }
         }
         val
     }
@@ -221,6 +248,9 @@ pub enum UpdateLendingMarketMode {
     UpdateName = 19,
     UpdateIndividualAutodeleverageMarginCallPeriodSecs = 20,
     UpdateInitialDepositAmount = 21,
+    UpdateFeeRecipient = 22,
+    UpdateSwapAdapterProgram = 23,
+    UpdateMaxSwapCollateralSlippageBps = 24,
 }
 
 #[cfg(feature = "serde")]
@@ -4,11 +4,18 @@ use std::{
 };
 
 use anchor_lang::{account, err, prelude::*, solana_program::clock::Slot, Result};
+use bitflags::bitflags;
 use derivative::Derivative;
 
-use super::{LastUpdate, LtvMaxWithdrawalCheck};
+use super::{
+    ElevationGroup, ElevationGroupDebtAmounts, ElevationGroupDebtKind, LastUpdate,
+    LtvMaxWithdrawalCheck,
+};
 use crate::{
-    utils::{BigFraction, Fraction, FractionExtra, ELEVATION_GROUP_NONE, OBLIGATION_SIZE, U256},
+    utils::{
+        BigFraction, Fraction, FractionExtra, ELEVATION_GROUP_NONE, MAX_OBLIGATION_FARM_RESERVES,
+        OBLIGATION_SIZE, U256,
+    },
     xmsg, AssetTier, BigFractionBytes, LendingError,
 };
 
@@ -50,15 +57,33 @@ pub struct Obligation {
 
     pub lowest_reserve_deposit_max_ltv_pct: u8,
 
+    pub borrowing_disabled_by_price_circuit_breaker: u8,
+
     #[derivative(Debug = "ignore")]
-    pub reserved: [u8; 5],
+    pub reserved: [u8; 4],
 
     pub highest_borrow_factor_pct: u64,
 
     pub autodeleverage_margin_call_started_timestamp: u64,
 
+    pub delegate: Pubkey,
+    pub delegate_permissions: u8,
+    pub delegate_max_borrow_ltv_pct: u8,
+    #[derivative(Debug = "ignore")]
+    pub delegate_padding: [u8; 6],
+
+    pub pending_owner: Pubkey,
+
+    pub protection_order_trigger_ltv_pct: u8,
+    pub protection_order_target_ltv_pct: u8,
+    pub protection_order_bonus_bps: u16,
     #[derivative(Debug = "ignore")]
-    pub padding_3: [u64; 125],
+    pub protection_order_padding: [u8; 4],
+
+    pub farm_reserves: [Pubkey; 16],
+
+    #[derivative(Debug = "ignore")]
+    pub padding_3: [u64; 51],
 }
 
 impl Default for Obligation {
@@ -84,11 +109,22 @@ impl Default for Obligation {
             borrowing_disabled: 0,
             highest_borrow_factor_pct: 0,
             lowest_reserve_deposit_max_ltv_pct: 0,
-            reserved: [0; 5],
-            padding_3: [0; 125],
+            borrowing_disabled_by_price_circuit_breaker: 0,
+            reserved: [0; 4],
+            padding_3: [0; 51],
             referrer: Pubkey::default(),
             autodeleverage_target_ltv_pct: 0,
             autodeleverage_margin_call_started_timestamp: 0,
+            delegate: Pubkey::default(),
+            delegate_permissions: 0,
+            delegate_max_borrow_ltv_pct: 0,
+            delegate_padding: [0; 6],
+            pending_owner: Pubkey::default(),
+            protection_order_trigger_ltv_pct: 0,
+            protection_order_target_ltv_pct: 0,
+            protection_order_bonus_bps: 0,
+            protection_order_padding: [0; 4],
+            farm_reserves: [Pubkey::default(); MAX_OBLIGATION_FARM_RESERVES],
         }
     }
 }
@@ -136,6 +172,19 @@ impl Display for Obligation {
     }
 }
 
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ObligationDelegatePermissions(u8);
+
+#[rustfmt::skip]
+bitflags! {
+    impl ObligationDelegatePermissions: u8 {
+        const DEPOSIT = 0b_0000_0001;
+        const WITHDRAW = 0b_0000_0010;
+        const BORROW = 0b_0000_0100;
+        const REPAY = 0b_0000_1000;
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum WithdrawResult {
     Full,
@@ -158,6 +207,57 @@ impl Obligation {
         self.borrows_asset_tiers = [u8::MAX; 5];
     }
 
+    pub fn has_authority(
+        &self,
+        signer: Pubkey,
+        permissions: ObligationDelegatePermissions,
+    ) -> bool {
+        signer == self.owner
+            || (self.is_delegate(signer) && self.get_delegate_permissions().contains(permissions))
+    }
+
+    pub fn is_delegate(&self, signer: Pubkey) -> bool {
+        self.delegate != Pubkey::default() && signer == self.delegate
+    }
+
+    pub fn get_delegate_permissions(&self) -> ObligationDelegatePermissions {
+        ObligationDelegatePermissions::from_bits_truncate(self.delegate_permissions)
+    }
+
+    pub fn set_delegate(
+        &mut self,
+        delegate: Pubkey,
+        permissions: ObligationDelegatePermissions,
+        max_borrow_ltv_pct: u8,
+    ) {
+        self.delegate = delegate;
+        self.delegate_permissions = permissions.bits();
+        self.delegate_max_borrow_ltv_pct = max_borrow_ltv_pct;
+    }
+
+    pub fn transfer_ownership(&mut self, new_owner: Pubkey, referrer: Pubkey) {
+        self.owner = new_owner;
+        self.referrer = referrer;
+        self.pending_owner = Pubkey::default();
+        self.set_delegate(Pubkey::default(), ObligationDelegatePermissions::empty(), 0);
+        self.set_protection_order(0, 0, 0);
+    }
+
+    pub fn set_protection_order(
+        &mut self,
+        trigger_ltv_pct: u8,
+        target_ltv_pct: u8,
+        bonus_bps: u16,
+    ) {
+        self.protection_order_trigger_ltv_pct = trigger_ltv_pct;
+        self.protection_order_target_ltv_pct = target_ltv_pct;
+        self.protection_order_bonus_bps = bonus_bps;
+    }
+
+    pub fn has_protection_order(&self) -> bool {
+        self.protection_order_trigger_ltv_pct > 0
+    }
+
     pub fn loan_to_value(&self) -> Fraction {
         Fraction::from_bits(self.borrow_factor_adjusted_debt_value_sf)
             / Fraction::from_bits(self.deposited_value_sf)
@@ -368,6 +468,55 @@ impl Obligation {
             .all(|l| l.borrow_reserve == Pubkey::default())
     }
 
+    pub fn add_farm_reserve(&mut self, reserve: Pubkey) -> Result<()> {
+        if !self.track_farm_reserve(reserve) {
+            msg!(
+                "Obligation already has farms for {} reserves",
+                MAX_OBLIGATION_FARM_RESERVES
+            );
+            return err!(LendingError::ObligationFarmReservesFull);
+        }
+        Ok(())
+    }
+
+    pub fn track_farm_reserve(&mut self, reserve: Pubkey) -> bool {
+        if self.farm_reserves.contains(&reserve) {
+            return true;
+        }
+
+        match self
+            .farm_reserves
+            .iter_mut()
+            .find(|farm_reserve| **farm_reserve == Pubkey::default())
+        {
+            Some(farm_reserve) => {
+                *farm_reserve = reserve;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn farm_reserves_with_positions(&self) -> Vec<Pubkey> {
+        let mut reserves: Vec<Pubkey> = self
+            .farm_reserves
+            .iter()
+            .copied()
+            .filter(|farm_reserve| *farm_reserve != Pubkey::default())
+            .collect();
+        let position_reserves = self
+            .deposits
+            .iter()
+            .map(|deposit| deposit.deposit_reserve)
+            .chain(self.borrows.iter().map(|borrow| borrow.borrow_reserve));
+        for reserve in position_reserves {
+            if reserve != Pubkey::default() && !reserves.contains(&reserve) {
+                reserves.push(reserve);
+            }
+        }
+        reserves
+    }
+
     pub fn deposits_count(&self) -> usize {
         self.deposits
             .iter()
@@ -410,15 +559,18 @@ impl Obligation {
             .collect::<Vec<AssetTier>>()
     }
 
-    pub fn get_borrowed_amount_if_single_token(&self) -> Option<u64> {
-        if self.borrows_count() > 1 {
-            None
-        } else {
-            Some(
-                Fraction::from_bits(self.borrows.iter().map(|l| l.borrowed_amount_sf).sum())
-                    .to_ceil::<u64>(),
-            )
+    pub fn get_borrowed_amounts_in_elevation_group(
+        &self,
+        elevation_group: &ElevationGroup,
+    ) -> ElevationGroupDebtAmounts {
+        let mut borrowed_amounts = ElevationGroupDebtAmounts::default();
+        for borrow in self.borrows.iter() {
+            if let Some(debt_kind) = elevation_group.get_debt_kind(&borrow.borrow_reserve) {
+                *borrowed_amounts.get_mut(debt_kind) +=
+                    Fraction::from_bits(borrow.borrowed_amount_sf).to_ceil::<u64>();
+            }
         }
+        borrowed_amounts
     }
 
     pub fn get_bf_adjusted_debt_value(&self) -> Fraction {
@@ -498,7 +650,8 @@ pub struct ObligationCollateral {
     pub deposited_amount: u64,
     pub market_value_sf: u128,
     pub borrowed_amount_against_this_collateral_in_elevation_group: u64,
-    pub padding: [u64; 9],
+    pub secondary_debt_borrowed_amount_against_this_collateral_in_elevation_group: u64,
+    pub padding: [u64; 8],
 }
 
 impl ObligationCollateral {
@@ -508,7 +661,41 @@ impl ObligationCollateral {
             deposited_amount: 0,
             market_value_sf: 0,
             borrowed_amount_against_this_collateral_in_elevation_group: 0,
-            padding: [0; 9],
+            secondary_debt_borrowed_amount_against_this_collateral_in_elevation_group: 0,
+            padding: [0; 8],
+        }
+    }
+
+    pub fn get_borrowed_amount_in_elevation_group(&self, debt_kind: ElevationGroupDebtKind) -> u64 {
+        match debt_kind {
+            ElevationGroupDebtKind::Primary => {
+                self.borrowed_amount_against_this_collateral_in_elevation_group
+            }
+            ElevationGroupDebtKind::Secondary => {
+                self.secondary_debt_borrowed_amount_against_this_collateral_in_elevation_group
+            }
+        }
+    }
+
+    pub fn get_borrowed_amount_in_elevation_group_mut(
+        &mut self,
+        debt_kind: ElevationGroupDebtKind,
+    ) -> &mut u64 {
+        match debt_kind {
+            ElevationGroupDebtKind::Primary => {
+                &mut self.borrowed_amount_against_this_collateral_in_elevation_group
+            }
+            ElevationGroupDebtKind::Secondary => {
+                &mut self.secondary_debt_borrowed_amount_against_this_collateral_in_elevation_group
+            }
+        }
+    }
+
+    pub fn get_borrowed_amounts_in_elevation_group(&self) -> ElevationGroupDebtAmounts {
+        ElevationGroupDebtAmounts {
+            primary: self.borrowed_amount_against_this_collateral_in_elevation_group,
+            secondary: self
+                .secondary_debt_borrowed_amount_against_this_collateral_in_elevation_group,
         }
     }
 
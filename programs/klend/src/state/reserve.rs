@@ -16,13 +16,19 @@ use num_enum::{IntoPrimitive, TryFromPrimitive};
 use serde;
 
 #[cfg(feature = "serde")]
-use super::serde_bool_u8;
-use super::{DepositLiquidityResult, LastUpdate, TokenInfo};
+use super::{serde_bool_u8, serde_string};
+use super::{
+    DepositLiquidityResult, ElevationGroupDebtKind, LastUpdate, PriceStatusFlags, ReserveAction,
+    TokenInfo,
+};
 use crate::{
     fraction::FractionExtra,
     utils::{
-        borrow_rate_curve::BorrowRateCurve, BigFraction, Fraction, INITIAL_COLLATERAL_RATE,
-        PROGRAM_VERSION, RESERVE_CONFIG_SIZE, RESERVE_SIZE, SLOTS_PER_YEAR, U256,
+        borrow_rate_curve::{BorrowRateCurve, BorrowRateCurveExtension},
+        fraction::fraction,
+        BigFraction, Fraction, ReservePriceEma, FULL_BPS, INITIAL_COLLATERAL_RATE,
+        PRICE_CIRCUIT_BREAKER_SAMPLES, PROGRAM_VERSION, RESERVE_CONFIG_SIZE, RESERVE_SIZE,
+        SLOTS_PER_YEAR, U256,
     },
     CalculateBorrowResult, CalculateRepayResult, LendingError, LendingResult, ReferrerTokenState,
 };
@@ -79,14 +85,16 @@ pub struct Reserve {
     pub config: ReserveConfig,
 
     #[derivative(Debug = "ignore")]
-    pub config_padding: [u64; 116],
+    pub config_padding: [u64; 50],
 
     pub borrowed_amount_outside_elevation_group: u64,
 
     pub borrowed_amounts_against_this_reserve_in_elevation_groups: [u64; 32],
 
+    pub secondary_debt_borrowed_amounts_against_this_reserve_in_elevation_groups: [u64; 32],
+
     #[derivative(Debug = "ignore")]
-    pub padding: [u64; 207],
+    pub padding: [u64; 175],
 }
 
 impl Default for Reserve {
@@ -102,10 +110,11 @@ impl Default for Reserve {
             farm_debt: Pubkey::default(),
             reserve_liquidity_padding: [0; 150],
             reserve_collateral_padding: [0; 150],
-            config_padding: [0; 116],
+            config_padding: [0; 50],
             borrowed_amount_outside_elevation_group: 0,
             borrowed_amounts_against_this_reserve_in_elevation_groups: [0; 32],
-            padding: [0; 207],
+            secondary_debt_borrowed_amounts_against_this_reserve_in_elevation_groups: [0; 32],
+            padding: [0; 175],
         }
     }
 }
@@ -140,11 +149,166 @@ impl Reserve {
     }
 
     pub fn current_borrow_rate(&self) -> Result<Fraction> {
-        let utilization_rate = self.liquidity.utilization_rate();
+        let utilization_rate = if self.config.is_utilization_ema_enabled() {
+            self.liquidity.utilization_ema()
+        } else {
+            self.liquidity.utilization_rate()
+        };
+
+        self.borrow_rate(utilization_rate)
+    }
 
+    pub fn borrow_rate(&self, utilization_rate: Fraction) -> Result<Fraction> {
         self.config
             .borrow_rate_curve
-            .get_borrow_rate(utilization_rate)
+            .get_borrow_rate_extended(&self.config.borrow_rate_curve_extension, utilization_rate)
+    }
+
+    pub fn update_utilization_ema(&mut self, timestamp: u64) {
+        let utilization_rate = self.liquidity.utilization_rate();
+        let half_life_secs = self.config.utilization_ema_half_life_secs;
+        let last_updated_ts = self.liquidity.utilization_ema_last_updated_ts;
+
+        let utilization_ema = if half_life_secs == 0 || last_updated_ts == 0 {
+            utilization_rate
+        } else {
+            let elapsed_secs = timestamp.saturating_sub(last_updated_ts);
+            let decay = approximate_half_life_decay(elapsed_secs, half_life_secs);
+            self.liquidity.utilization_ema() * decay + utilization_rate * (Fraction::ONE - decay)
+        };
+
+        self.liquidity.utilization_ema_sf = utilization_ema.to_bits();
+        self.liquidity.utilization_ema_last_updated_ts = timestamp;
+    }
+
+    pub fn update_price_ema(&mut self, price: Fraction, timestamp: u64) {
+        let half_life_secs = self.config.token_info.price_ema_half_life_secs;
+        if half_life_secs == 0 {
+            self.liquidity.price_ema_sf = 0;
+            self.liquidity.price_ema_first_updated_ts = 0;
+            self.liquidity.price_ema_last_updated_ts = 0;
+            return;
+        }
+
+        let last_updated_ts = self.liquidity.price_ema_last_updated_ts;
+        if last_updated_ts == 0 {
+            self.liquidity.price_ema_sf = price.to_bits();
+            self.liquidity.price_ema_first_updated_ts = timestamp;
+            self.liquidity.price_ema_last_updated_ts = timestamp;
+            return;
+        }
+
+        if timestamp <= last_updated_ts {
+            return;
+        }
+
+        let decay = approximate_half_life_decay(timestamp - last_updated_ts, half_life_secs);
+        let price_ema = self.liquidity.price_ema() * decay + price * (Fraction::ONE - decay);
+
+        self.liquidity.price_ema_sf = price_ema.to_bits();
+        self.liquidity.price_ema_last_updated_ts = timestamp;
+    }
+
+    pub fn update_price_circuit_breaker(&mut self, price: Fraction, timestamp: u64) {
+        let token_info = &self.config.token_info;
+        if !token_info.is_price_circuit_breaker_enabled() {
+            self.liquidity.price_circuit_breaker_samples_ts = [0; PRICE_CIRCUIT_BREAKER_SAMPLES];
+            self.liquidity.price_circuit_breaker_samples_min_price_sf =
+                [0; PRICE_CIRCUIT_BREAKER_SAMPLES];
+            self.liquidity.price_circuit_breaker_samples_max_price_sf =
+                [0; PRICE_CIRCUIT_BREAKER_SAMPLES];
+            self.liquidity.price_circuit_breaker_tripped_ts = 0;
+            return;
+        }
+
+        let window_secs = token_info.price_circuit_breaker_window_secs;
+        let max_move_bps = token_info.price_circuit_breaker_max_move_bps;
+
+        let sample_secs = window_secs.div_ceil(PRICE_CIRCUIT_BREAKER_SAMPLES as u64);
+        let sample_ts = timestamp - timestamp % sample_secs;
+        let sample_index =
+            ((timestamp / sample_secs) % PRICE_CIRCUIT_BREAKER_SAMPLES as u64) as usize;
+        let liquidity = &mut self.liquidity;
+        if liquidity.price_circuit_breaker_samples_ts[sample_index] != sample_ts {
+            liquidity.price_circuit_breaker_samples_ts[sample_index] = sample_ts;
+            liquidity.price_circuit_breaker_samples_min_price_sf[sample_index] = price.to_bits();
+            liquidity.price_circuit_breaker_samples_max_price_sf[sample_index] = price.to_bits();
+        } else {
+            let min_price = Fraction::from_bits(
+                liquidity.price_circuit_breaker_samples_min_price_sf[sample_index],
+            );
+            let max_price = Fraction::from_bits(
+                liquidity.price_circuit_breaker_samples_max_price_sf[sample_index],
+            );
+            liquidity.price_circuit_breaker_samples_min_price_sf[sample_index] =
+                min_price.min(price).to_bits();
+            liquidity.price_circuit_breaker_samples_max_price_sf[sample_index] =
+                max_price.max(price).to_bits();
+        }
+
+        let tripped_ts = liquidity.price_circuit_breaker_tripped_ts;
+        if tripped_ts != 0 {
+            if timestamp.saturating_sub(tripped_ts) < window_secs {
+                return;
+            }
+            liquidity.price_circuit_breaker_tripped_ts = 0;
+        }
+
+        let (window_min_price, window_max_price) = (0..PRICE_CIRCUIT_BREAKER_SAMPLES)
+            .filter(|&index| {
+                timestamp.saturating_sub(liquidity.price_circuit_breaker_samples_ts[index])
+                    < window_secs
+            })
+            .fold(
+                (price, price),
+                |(window_min_price, window_max_price), index| {
+                    (
+                        window_min_price.min(Fraction::from_bits(
+                            liquidity.price_circuit_breaker_samples_min_price_sf[index],
+                        )),
+                        window_max_price.max(Fraction::from_bits(
+                            liquidity.price_circuit_breaker_samples_max_price_sf[index],
+                        )),
+                    )
+                },
+            );
+
+        if (price - window_min_price) * u128::from(FULL_BPS)
+            > window_min_price * u128::from(max_move_bps)
+            || (window_max_price - price) * u128::from(FULL_BPS)
+                > window_max_price * u128::from(max_move_bps)
+        {
+            msg!(
+                "Price circuit breaker tripped token=[{}] window_min_price={} window_max_price={} price={} max_move_bps={}",
+                token_info.symbol(),
+                window_min_price.to_display(),
+                window_max_price.to_display(),
+                price.to_display(),
+                max_move_bps
+            );
+            liquidity.price_circuit_breaker_tripped_ts = timestamp;
+        }
+    }
+
+    pub fn is_price_circuit_breaker_tripped(&self) -> bool {
+        self.liquidity.price_circuit_breaker_tripped_ts != 0
+    }
+
+    pub fn get_trusted_price_ema(&self, current_ts: u64) -> Option<ReservePriceEma> {
+        let token_info = &self.config.token_info;
+        let first_updated_ts = self.liquidity.price_ema_first_updated_ts;
+        if !token_info.is_price_ema_enabled()
+            || first_updated_ts == 0
+            || current_ts.saturating_sub(first_updated_ts)
+                < token_info.price_ema_min_sample_age_secs
+        {
+            return None;
+        }
+
+        Some(ReservePriceEma {
+            price: self.liquidity.price_ema(),
+            timestamp: self.liquidity.price_ema_last_updated_ts,
+        })
     }
 
     pub fn borrow_factor_f(&self, is_in_elevation_group: bool) -> Fraction {
@@ -155,6 +319,40 @@ impl Reserve {
         }
     }
 
+    pub fn get_borrowed_amount_in_elevation_group(
+        &self,
+        elevation_group_index: usize,
+        debt_kind: ElevationGroupDebtKind,
+    ) -> u64 {
+        match debt_kind {
+            ElevationGroupDebtKind::Primary => {
+                self.borrowed_amounts_against_this_reserve_in_elevation_groups
+                    [elevation_group_index]
+            }
+            ElevationGroupDebtKind::Secondary => {
+                self.secondary_debt_borrowed_amounts_against_this_reserve_in_elevation_groups
+                    [elevation_group_index]
+            }
+        }
+    }
+
+    pub fn get_borrowed_amount_in_elevation_group_mut(
+        &mut self,
+        elevation_group_index: usize,
+        debt_kind: ElevationGroupDebtKind,
+    ) -> &mut u64 {
+        match debt_kind {
+            ElevationGroupDebtKind::Primary => {
+                &mut self.borrowed_amounts_against_this_reserve_in_elevation_groups
+                    [elevation_group_index]
+            }
+            ElevationGroupDebtKind::Secondary => {
+                &mut self.secondary_debt_borrowed_amounts_against_this_reserve_in_elevation_groups
+                    [elevation_group_index]
+            }
+        }
+    }
+
     pub fn get_farm(&self, mode: ReserveFarmKind) -> Pubkey {
         match mode {
             ReserveFarmKind::Collateral => self.farm_collateral,
@@ -277,11 +475,11 @@ impl Reserve {
         let decimals = 10u64
             .checked_pow(self.liquidity.mint_decimals as u32)
             .ok_or(LendingError::MathOverflow)?;
-        let market_price_f = self.liquidity.get_market_price_f();
+        let debt_price_f = self.liquidity.get_debt_price_f();
 
         if amount_to_borrow == u64::MAX {
             let borrow_amount_f = (max_borrow_factor_adjusted_debt_value * u128::from(decimals)
-                / market_price_f
+                / debt_price_f
                 / self.borrow_factor_f(is_in_elevation_group))
             .min(remaining_reserve_borrow)
             .min(self.liquidity.available_amount.into());
@@ -312,7 +510,7 @@ impl Reserve {
 
             borrow_amount_f += Fraction::from_num(borrow_fee + referrer_fee);
             let borrow_factor_adjusted_debt_value = borrow_amount_f
-                .mul(market_price_f)
+                .mul(debt_price_f)
                 .div(u128::from(decimals))
                 .mul(self.borrow_factor_f(is_in_elevation_group));
             if borrow_factor_adjusted_debt_value > max_borrow_factor_adjusted_debt_value {
@@ -405,8 +603,18 @@ pub struct ReserveLiquidity {
     pub absolute_referral_rate_sf: u128,
     pub token_program: Pubkey,
 
-    pub padding2: [u64; 51],
-    pub padding3: [u128; 32],
+    pub utilization_ema_last_updated_ts: u64,
+    pub price_ema_first_updated_ts: u64,
+    pub price_ema_last_updated_ts: u64,
+    pub price_circuit_breaker_tripped_ts: u64,
+    pub price_circuit_breaker_samples_ts: [u64; PRICE_CIRCUIT_BREAKER_SAMPLES],
+    pub padding2: [u64; 39],
+    pub utilization_ema_sf: u128,
+    pub price_ema_sf: u128,
+    pub price_circuit_breaker_samples_min_price_sf: [u128; PRICE_CIRCUIT_BREAKER_SAMPLES],
+    pub price_circuit_breaker_samples_max_price_sf: [u128; PRICE_CIRCUIT_BREAKER_SAMPLES],
+    pub market_price_confidence_sf: u128,
+    pub padding3: [u128; 13],
 }
 
 impl Default for ReserveLiquidity {
@@ -428,8 +636,18 @@ impl Default for ReserveLiquidity {
             absolute_referral_rate_sf: 0,
             market_price_last_updated_ts: 0,
             token_program: Pubkey::default(),
-            padding2: [0; 51],
-            padding3: [0; 32],
+            utilization_ema_last_updated_ts: 0,
+            price_ema_first_updated_ts: 0,
+            price_ema_last_updated_ts: 0,
+            price_circuit_breaker_tripped_ts: 0,
+            price_circuit_breaker_samples_ts: [0; PRICE_CIRCUIT_BREAKER_SAMPLES],
+            padding2: [0; 39],
+            utilization_ema_sf: 0,
+            price_ema_sf: 0,
+            price_circuit_breaker_samples_min_price_sf: [0; PRICE_CIRCUIT_BREAKER_SAMPLES],
+            price_circuit_breaker_samples_max_price_sf: [0; PRICE_CIRCUIT_BREAKER_SAMPLES],
+            market_price_confidence_sf: 0,
+            padding3: [0; 13],
         }
     }
 }
@@ -463,8 +681,18 @@ impl ReserveLiquidity {
             absolute_referral_rate_sf: 0,
             market_price_last_updated_ts: 0,
             token_program: mint_token_program,
-            padding2: [0; 51],
-            padding3: [0; 32],
+            utilization_ema_last_updated_ts: 0,
+            price_ema_first_updated_ts: 0,
+            price_ema_last_updated_ts: 0,
+            price_circuit_breaker_tripped_ts: 0,
+            price_circuit_breaker_samples_ts: [0; PRICE_CIRCUIT_BREAKER_SAMPLES],
+            padding2: [0; 39],
+            utilization_ema_sf: 0,
+            price_ema_sf: 0,
+            price_circuit_breaker_samples_min_price_sf: [0; PRICE_CIRCUIT_BREAKER_SAMPLES],
+            price_circuit_breaker_samples_max_price_sf: [0; PRICE_CIRCUIT_BREAKER_SAMPLES],
+            market_price_confidence_sf: 0,
+            padding3: [0; 13],
         }
     }
 
@@ -487,6 +715,12 @@ impl ReserveLiquidity {
         Ok(())
     }
 
+    pub fn add_supply_fee(&mut self, fee_amount: u64) -> Result<()> {
+        self.deposit(fee_amount)?;
+        self.accumulated_protocol_fees_sf += Fraction::from(fee_amount).to_bits();
+        Ok(())
+    }
+
     pub fn withdraw(&mut self, liquidity_amount: u64) -> Result<()> {
         if liquidity_amount > self.available_amount {
             msg!("Withdraw amount cannot exceed available amount");
@@ -565,6 +799,24 @@ impl ReserveLiquidity {
         Ok(())
     }
 
+    pub fn reinvest_protocol_fees(&mut self, reinvest_amount: u64) -> Result<()> {
+        let accumulated_protocol_fees_f = Fraction::from_bits(self.accumulated_protocol_fees_sf);
+        let reinvest_amount_f = Fraction::from_num(reinvest_amount);
+        self.accumulated_protocol_fees_sf = accumulated_protocol_fees_f
+            .checked_sub(reinvest_amount_f)
+            .ok_or_else(|| {
+                msg!(
+                    "Accumulated protocol fees {} cannot be less than reinvest amount {}",
+                    accumulated_protocol_fees_f.to_display(),
+                    reinvest_amount_f.to_display()
+                );
+                error!(LendingError::MathOverflow)
+            })?
+            .to_bits();
+
+        Ok(())
+    }
+
     pub fn utilization_rate(&self) -> Fraction {
         let total_supply = self.total_supply();
         if total_supply == Fraction::ZERO {
@@ -573,6 +825,14 @@ impl ReserveLiquidity {
         Fraction::from_bits(self.borrowed_amount_sf) / total_supply
     }
 
+    pub fn utilization_ema(&self) -> Fraction {
+        Fraction::from_bits(self.utilization_ema_sf)
+    }
+
+    pub fn price_ema(&self) -> Fraction {
+        Fraction::from_bits(self.price_ema_sf)
+    }
+
     fn compound_interest(
         &mut self,
         current_borrow_rate: Fraction,
@@ -674,6 +934,20 @@ impl ReserveLiquidity {
     pub fn get_market_price_f(&self) -> Fraction {
         Fraction::from_bits(self.market_price_sf)
     }
+
+    pub fn get_market_price_confidence_f(&self) -> Fraction {
+        Fraction::from_bits(self.market_price_confidence_sf)
+    }
+
+    pub fn get_collateral_price_f(&self) -> Fraction {
+        self.get_market_price_f()
+            .saturating_sub(self.get_market_price_confidence_f())
+    }
+
+    pub fn get_debt_price_f(&self) -> Fraction {
+        self.get_market_price_f()
+            .saturating_add(self.get_market_price_confidence_f())
+    }
 }
 
 pub struct NewReserveLiquidityParams {
@@ -861,9 +1135,8 @@ pub struct ReserveConfig {
     #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
     #[derivative(Debug = "ignore")]
     pub reserved_2: [u8; 2],
-    #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
-    #[derivative(Debug = "ignore")]
-    pub reserved_3: [u8; 8],
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub required_price_status: ReserveRequiredPriceStatus,
     pub protocol_take_rate_pct: u8,
     pub protocol_liquidation_fee_pct: u8,
     pub loan_to_value_pct: u8,
@@ -901,6 +1174,21 @@ pub struct ReserveConfig {
     pub borrow_limit_against_this_collateral_in_elevation_group: [u64; 32],
 
     pub deleveraging_bonus_increase_bps_per_day: u64,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub borrow_rate_curve_extension: BorrowRateCurveExtension,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub utilization_ema_half_life_secs: u64,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub supply_fees: ReserveSupplyFees,
+
+    #[cfg_attr(feature = "serde", serde(with = "serde_string", default))]
+    pub transfer_hook_program: Pubkey,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub secondary_debt_borrow_limit_against_this_collateral_in_elevation_group: [u64; 32],
 }
 
 impl ReserveConfig {
@@ -922,6 +1210,89 @@ impl ReserveConfig {
     pub fn is_autodeleverage_enabled(&self) -> bool {
         self.autodeleverage_enabled != false as u8
     }
+
+    pub fn is_utilization_ema_enabled(&self) -> bool {
+        self.utilization_ema_half_life_secs != 0
+    }
+
+    pub fn get_borrow_limit_in_elevation_group(
+        &self,
+        elevation_group_index: usize,
+        debt_kind: ElevationGroupDebtKind,
+    ) -> u64 {
+        match debt_kind {
+            ElevationGroupDebtKind::Primary => {
+                self.borrow_limit_against_this_collateral_in_elevation_group[elevation_group_index]
+            }
+            ElevationGroupDebtKind::Secondary => {
+                self.secondary_debt_borrow_limit_against_this_collateral_in_elevation_group
+                    [elevation_group_index]
+            }
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default, PartialEq, Eq, Derivative)]
+#[derivative(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[zero_copy]
+#[repr(C)]
+pub struct ReserveRequiredPriceStatus {
+    #[cfg_attr(feature = "serde", serde(with = "serde_bool_u8"))]
+    pub enabled: u8,
+    pub deposit: u8,
+    pub withdraw: u8,
+    pub borrow: u8,
+    pub repay: u8,
+    pub liquidate: u8,
+    pub flash_loan: u8,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
+    #[derivative(Debug = "ignore")]
+    pub padding: [u8; 1],
+}
+
+impl ReserveRequiredPriceStatus {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled != false as u8
+    }
+
+    pub fn for_action(&self, action: ReserveAction) -> PriceStatusFlags {
+        if !self.is_enabled() {
+            return action.default_required_price_status();
+        }
+
+        let required = match action {
+            ReserveAction::Deposit => self.deposit,
+            ReserveAction::Withdraw => self.withdraw,
+            ReserveAction::Borrow => self.borrow,
+            ReserveAction::Repay => self.repay,
+            ReserveAction::Liquidate => self.liquidate,
+            ReserveAction::FlashLoan => self.flash_loan,
+        };
+        PriceStatusFlags::from_bits_truncate(required)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+
+        let minimum = PriceStatusFlags::PRICE_LOADED | PriceStatusFlags::PRICE_AGE_CHECKED;
+        [
+            self.deposit,
+            self.withdraw,
+            self.borrow,
+            self.repay,
+            self.liquidate,
+            self.flash_loan,
+        ]
+        .iter()
+        .all(|required| PriceStatusFlags::from_bits(*required).is_some())
+            && [self.withdraw, self.borrow, self.liquidate]
+                .iter()
+                .all(|required| PriceStatusFlags::from_bits_truncate(*required).contains(minimum))
+    }
 }
 
 #[repr(u8)]
@@ -1151,43 +1522,148 @@ impl ReserveFees {
         referral_fee_bps: u16,
         has_referrer: bool,
     ) -> Result<(u64, u64)> {
-        let borrow_fee_rate = Fraction::from_bits(fee_sf.into());
-        let referral_fee_rate = Fraction::from_bps(referral_fee_bps);
-        if borrow_fee_rate > Fraction::ZERO && amount > Fraction::ZERO {
-            let need_to_assess_referral_fee = referral_fee_rate > Fraction::ZERO && has_referrer;
-            let minimum_fee = 1u64;
-            let borrow_fee_amount = match fee_calculation {
-                FeeCalculation::Exclusive => amount.mul(borrow_fee_rate),
-                FeeCalculation::Inclusive => {
-                    let borrow_fee_rate = borrow_fee_rate.div(borrow_fee_rate.add(Fraction::ONE));
-                    amount.mul(borrow_fee_rate)
-                }
-            };
+        calculate_fees(
+            amount,
+            Fraction::from_bits(fee_sf.into()),
+            1,
+            fee_calculation,
+            referral_fee_bps,
+            has_referrer,
+        )
+        .ok_or_else(|| {
+            msg!("Borrow amount is too small to receive liquidity after fees");
+            error!(LendingError::BorrowTooSmall)
+        })
+    }
+}
 
-            let borrow_fee_f = borrow_fee_amount.max(minimum_fee.into());
-            if borrow_fee_f >= amount {
-                msg!("Borrow amount is too small to receive liquidity after fees");
-                return err!(LendingError::BorrowTooSmall);
+#[derive(BorshDeserialize, BorshSerialize, Default, PartialEq, Eq, Derivative)]
+#[derivative(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[zero_copy]
+#[repr(C)]
+pub struct ReserveSupplyFees {
+    pub deposit_fee_bps: u16,
+    pub withdrawal_fee_bps: u16,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
+    #[derivative(Debug = "ignore")]
+    pub padding: [u8; 4],
+    pub min_deposit_fee: u64,
+    pub min_withdrawal_fee: u64,
+}
+
+impl ReserveSupplyFees {
+    pub fn has_deposit_fee(&self) -> bool {
+        self.deposit_fee_bps > 0
+    }
+
+    pub fn has_withdrawal_fee(&self) -> bool {
+        self.withdrawal_fee_bps > 0
+    }
+
+    pub fn calculate_deposit_fees(
+        &self,
+        deposit_amount: u64,
+        fee_calculation: FeeCalculation,
+        referral_fee_bps: u16,
+        has_referrer: bool,
+    ) -> Result<(u64, u64)> {
+        Self::calculate_fees(
+            deposit_amount,
+            self.deposit_fee_bps,
+            self.min_deposit_fee,
+            fee_calculation,
+            referral_fee_bps,
+            has_referrer,
+        )
+        .ok_or_else(|| {
+            msg!("Deposit amount is too small to mint collateral after fees");
+            error!(LendingError::AmountTooSmallForSupplyFees)
+        })
+    }
+
+    pub fn calculate_withdrawal_fees(
+        &self,
+        withdrawal_amount: u64,
+        fee_calculation: FeeCalculation,
+        referral_fee_bps: u16,
+        has_referrer: bool,
+    ) -> Result<(u64, u64)> {
+        Self::calculate_fees(
+            withdrawal_amount,
+            self.withdrawal_fee_bps,
+            self.min_withdrawal_fee,
+            fee_calculation,
+            referral_fee_bps,
+            has_referrer,
+        )
+        .ok_or_else(|| {
+            msg!("Withdrawal amount is too small to receive liquidity after fees");
+            error!(LendingError::AmountTooSmallForSupplyFees)
+        })
+    }
+
+    fn calculate_fees(
+        amount: u64,
+        fee_bps: u16,
+        minimum_fee: u64,
+        fee_calculation: FeeCalculation,
+        referral_fee_bps: u16,
+        has_referrer: bool,
+    ) -> Option<(u64, u64)> {
+        calculate_fees(
+            Fraction::from(amount),
+            Fraction::from_bps(fee_bps),
+            max(minimum_fee, 1),
+            fee_calculation,
+            referral_fee_bps,
+            has_referrer,
+        )
+    }
+}
+
+fn calculate_fees(
+    amount: Fraction,
+    fee_rate: Fraction,
+    minimum_fee: u64,
+    fee_calculation: FeeCalculation,
+    referral_fee_bps: u16,
+    has_referrer: bool,
+) -> Option<(u64, u64)> {
+    let referral_fee_rate = Fraction::from_bps(referral_fee_bps);
+    if fee_rate > Fraction::ZERO && amount > Fraction::ZERO {
+        let need_to_assess_referral_fee = referral_fee_rate > Fraction::ZERO && has_referrer;
+        let fee_amount = match fee_calculation {
+            FeeCalculation::Exclusive => amount.mul(fee_rate),
+            FeeCalculation::Inclusive => {
+                let fee_rate = fee_rate.div(fee_rate.add(Fraction::ONE));
+                amount.mul(fee_rate)
             }
+        };
 
-            let borrow_fee: u64 = borrow_fee_f.to_round();
-            let referral_fee = if need_to_assess_referral_fee {
-                if referral_fee_bps == 10_000 {
-                    borrow_fee
-                } else {
-                    let referral_fee_f = borrow_fee_f * referral_fee_rate;
-                    referral_fee_f.to_floor::<u64>()
-                }
+        let fee_f = fee_amount.max(minimum_fee.into());
+        if fee_f >= amount {
+            return None;
+        }
+
+        let fee: u64 = fee_f.to_round();
+        let referral_fee = if need_to_assess_referral_fee {
+            if referral_fee_bps == 10_000 {
+                fee
             } else {
-                0
-            };
+                let referral_fee_f = fee_f * referral_fee_rate;
+                referral_fee_f.to_floor::<u64>()
+            }
+        } else {
+            0
+        };
 
-            let protocol_fee = borrow_fee - referral_fee;
+        let protocol_fee = fee - referral_fee;
 
-            Ok((protocol_fee, referral_fee))
-        } else {
-            Ok((0, 0))
-        }
+        Some((protocol_fee, referral_fee))
+    } else {
+        Some((0, 0))
     }
 }
 
@@ -1213,6 +1689,26 @@ pub enum AssetTier {
     IsolatedDebt = 2,
 }
 
+const LN_2: Fraction = fraction!(0.693147180559945309);
+
+pub fn approximate_half_life_decay(elapsed_secs: u64, half_life_secs: u64) -> Fraction {
+    let halvings = elapsed_secs / half_life_secs;
+    if halvings >= u64::from(Fraction::FRAC_NBITS) {
+        return Fraction::ZERO;
+    }
+
+    let x = Fraction::from_num(elapsed_secs % half_life_secs) / u128::from(half_life_secs) * LN_2;
+    let x_power_two = x * x;
+    let x_power_three = x_power_two * x;
+    let x_power_four = x_power_three * x;
+    let x_power_five = x_power_four * x;
+
+    let positive_terms = Fraction::ONE + x_power_two / 2 + x_power_four / 24;
+    let negative_terms = x + x_power_three / 6 + x_power_five / 120;
+
+    (positive_terms - negative_terms) >> halvings as u32
+}
+
 pub fn approximate_compounded_interest(rate: Fraction, elapsed_slots: u64) -> Fraction {
     let base = rate / u128::from(SLOTS_PER_YEAR);
     match elapsed_slots {
@@ -1242,3 +1738,86 @@ pub fn approximate_compounded_interest(rate: Fraction, elapsed_slots: u64) -> Fr
 
     Fraction::ONE + first_term + second_term + third_term
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reserve_with_price_circuit_breaker(window_secs: u64, max_move_bps: u64) -> Reserve {
+        let mut reserve = Reserve::default();
+        reserve.config.token_info.price_circuit_breaker_window_secs = window_secs;
+        reserve.config.token_info.price_circuit_breaker_max_move_bps = max_move_bps;
+        reserve
+    }
+
+    #[test]
+    fn test_price_circuit_breaker_disabled_resets_samples() {
+        let mut reserve = reserve_with_price_circuit_breaker(800, 500);
+        reserve.update_price_circuit_breaker(Fraction::from(100_u64), 1000);
+        reserve.update_price_circuit_breaker(Fraction::from(200_u64), 1100);
+        assert!(reserve.is_price_circuit_breaker_tripped());
+
+        reserve.config.token_info.price_circuit_breaker_max_move_bps = 0;
+        reserve.update_price_circuit_breaker(Fraction::from(300_u64), 1200);
+
+        assert!(!reserve.is_price_circuit_breaker_tripped());
+        assert_eq!(
+            reserve.liquidity.price_circuit_breaker_samples_ts,
+            [0; PRICE_CIRCUIT_BREAKER_SAMPLES]
+        );
+        assert_eq!(
+            reserve.liquidity.price_circuit_breaker_samples_min_price_sf,
+            [0; PRICE_CIRCUIT_BREAKER_SAMPLES]
+        );
+        assert_eq!(
+            reserve.liquidity.price_circuit_breaker_samples_max_price_sf,
+            [0; PRICE_CIRCUIT_BREAKER_SAMPLES]
+        );
+    }
+
+    #[test]
+    fn test_price_circuit_breaker_within_max_move() {
+        let mut reserve = reserve_with_price_circuit_breaker(800, 500);
+        reserve.update_price_circuit_breaker(Fraction::from(100_u64), 1000);
+        reserve.update_price_circuit_breaker(Fraction::from(103_u64), 1100);
+        reserve.update_price_circuit_breaker(Fraction::from(99_u64), 1200);
+
+        assert!(!reserve.is_price_circuit_breaker_tripped());
+    }
+
+    #[test]
+    fn test_price_circuit_breaker_trips_on_increase_and_clears_after_window() {
+        let mut reserve = reserve_with_price_circuit_breaker(800, 500);
+        reserve.update_price_circuit_breaker(Fraction::from(100_u64), 1000);
+        reserve.update_price_circuit_breaker(Fraction::from(104_u64), 1100);
+        assert!(!reserve.is_price_circuit_breaker_tripped());
+
+        reserve.update_price_circuit_breaker(Fraction::from(106_u64), 1150);
+        assert!(reserve.is_price_circuit_breaker_tripped());
+        assert_eq!(reserve.liquidity.price_circuit_breaker_tripped_ts, 1150);
+
+        reserve.update_price_circuit_breaker(Fraction::from(100_u64), 1500);
+        assert!(reserve.is_price_circuit_breaker_tripped());
+
+        reserve.update_price_circuit_breaker(Fraction::from(100_u64), 1950);
+        assert!(!reserve.is_price_circuit_breaker_tripped());
+    }
+
+    #[test]
+    fn test_price_circuit_breaker_trips_on_decrease() {
+        let mut reserve = reserve_with_price_circuit_breaker(800, 500);
+        reserve.update_price_circuit_breaker(Fraction::from(100_u64), 1000);
+        reserve.update_price_circuit_breaker(Fraction::from(94_u64), 1200);
+
+        assert!(reserve.is_price_circuit_breaker_tripped());
+    }
+
+    #[test]
+    fn test_price_circuit_breaker_ignores_samples_outside_window() {
+        let mut reserve = reserve_with_price_circuit_breaker(800, 500);
+        reserve.update_price_circuit_breaker(Fraction::from(100_u64), 1000);
+        reserve.update_price_circuit_breaker(Fraction::from(110_u64), 1900);
+
+        assert!(!reserve.is_price_circuit_breaker_tripped());
+    }
+}
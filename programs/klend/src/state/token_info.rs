@@ -2,13 +2,14 @@ use std::fmt::Formatter;
 
 use anchor_lang::prelude::*;
 use borsh::{BorshDeserialize, BorshSerialize};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 #[cfg(feature = "serde")]
 use serde;
 
 #[cfg(feature = "serde")]
 use super::serde_string;
 use crate::{
-    utils::{NULL_PUBKEY, TOKEN_INFO_SIZE},
+    utils::{prices::PriceAccounts, NULL_PUBKEY, TOKEN_INFO_SIZE},
     LendingError,
 };
 
@@ -41,11 +42,29 @@ pub struct TokenInfo {
 
     pub block_price_usage: u8,
 
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pyth_min_verification_signatures: u8,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pyth_confidence_adjustment_factor: u8,
+
     #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
-    pub reserved: [u8; 7],
+    pub reserved: [u8; 5],
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub oracle_slot: OracleSlotConfiguration,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub price_ema_half_life_secs: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub price_ema_min_sample_age_secs: u64,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub price_circuit_breaker_max_move_bps: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub price_circuit_breaker_window_secs: u64,
 
     #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
-    pub _padding: [u64; 19],
+    pub _padding: [u64; 4],
 }
 
 impl std::fmt::Debug for TokenInfo {
@@ -61,41 +80,69 @@ impl std::fmt::Debug for TokenInfo {
             .field("switchboard_configuration", &self.switchboard_configuration)
             .field("pyth_configuration", &self.pyth_configuration)
             .field("block_price_usage", &self.block_price_usage)
+            .field(
+                "pyth_min_verification_signatures",
+                &self.pyth_min_verification_signatures,
+            )
+            .field(
+                "pyth_confidence_adjustment_factor",
+                &self.pyth_confidence_adjustment_factor,
+            )
+            .field("oracle_slot", &self.oracle_slot)
+            .field("price_ema_half_life_secs", &self.price_ema_half_life_secs)
+            .field(
+                "price_ema_min_sample_age_secs",
+                &self.price_ema_min_sample_age_secs,
+            )
+            .field(
+                "price_circuit_breaker_max_move_bps",
+                &self.price_circuit_breaker_max_move_bps,
+            )
+            .field(
+                "price_circuit_breaker_window_secs",
+                &self.price_circuit_breaker_window_secs,
+            )
             .finish()
     }
 }
 
 impl TokenInfo {
-    pub fn validate_token_info_config(
-        &self,
-        pyth_info: Option<&AccountInfo>,
-        switchboard_price_info: Option<&AccountInfo>,
-        switchboard_twap_info: Option<&AccountInfo>,
-        scope_prices_info: Option<&AccountInfo>,
-    ) -> Result<()> {
+    pub fn validate_token_info_config(&self, price_accounts: &PriceAccounts) -> Result<()> {
         require!(self.is_valid(), LendingError::InvalidOracleConfig);
         require!(self.is_twap_config_valid(), LendingError::InvalidTwapConfig);
         require!(
-            self.check_pyth_acc_matches(pyth_info),
+            self.check_pyth_acc_matches(price_accounts.pyth_price),
             LendingError::InvalidPythPriceAccount
         );
         require!(
-            self.check_switchboard_acc_matches(switchboard_price_info, switchboard_twap_info),
+            self.check_switchboard_acc_matches(
+                price_accounts.switchboard_price,
+                price_accounts.switchboard_twap
+            ),
             LendingError::InvalidSwitchboardAccount
         );
         require!(
-            self.check_scope_acc_matches(scope_prices_info),
+            self.check_scope_acc_matches(price_accounts.scope_prices),
             LendingError::InvalidScopePriceAccount
         );
+        require!(
+            self.check_oracle_slot_acc_matches(
+                price_accounts.oracle_slot_price,
+                price_accounts.oracle_slot_secondary
+            ),
+            LendingError::InvalidOracleSlotAccount
+        );
         Ok(())
     }
 
     #[inline]
     pub fn is_valid(&self) -> bool {
         self.scope_configuration.is_valid()
+            && self.oracle_slot.is_valid()
             && (self.scope_configuration.is_enabled()
                 || self.switchboard_configuration.is_enabled()
-                || self.pyth_configuration.is_enabled())
+                || self.pyth_configuration.is_enabled()
+                || self.oracle_slot.is_enabled())
     }
 
     #[inline]
@@ -103,6 +150,16 @@ impl TokenInfo {
         self.max_twap_divergence_bps > 0
     }
 
+    #[inline]
+    pub fn is_price_ema_enabled(&self) -> bool {
+        self.price_ema_half_life_secs > 0
+    }
+
+    #[inline]
+    pub fn is_price_circuit_breaker_enabled(&self) -> bool {
+        self.price_circuit_breaker_max_move_bps > 0 && self.price_circuit_breaker_window_secs > 0
+    }
+
     #[inline]
     pub fn is_twap_config_valid(&self) -> bool {
         if !self.is_twap_enabled() {
@@ -113,6 +170,10 @@ impl TokenInfo {
             return false;
         }
 
+        if self.is_price_ema_enabled() {
+            return true;
+        }
+
         if self.scope_configuration.is_enabled() && !self.scope_configuration.has_twap() {
             return false;
         }
@@ -122,6 +183,10 @@ impl TokenInfo {
             return false;
         }
 
+        if self.oracle_slot.is_enabled() && !self.oracle_slot.has_twap() {
+            return false;
+        }
+
         true
     }
 
@@ -163,6 +228,31 @@ impl TokenInfo {
         }
     }
 
+    #[inline]
+    pub fn check_oracle_slot_acc_matches(
+        &self,
+        oracle_slot_price_info: Option<&AccountInfo>,
+        oracle_slot_secondary_info: Option<&AccountInfo>,
+    ) -> bool {
+        if self.oracle_slot.is_enabled() {
+            (if self.oracle_slot.requires_price_account() {
+                matches!(
+                    oracle_slot_price_info,
+                    Some(a) if *a.key == self.oracle_slot.price_account)
+            } else {
+                oracle_slot_price_info.is_none()
+            }) && (!self
+                .oracle_slot
+                .requires_secondary_account(self.is_twap_enabled())
+                || matches!(
+                    oracle_slot_secondary_info,
+                    Some(a) if *a.key == self.oracle_slot.secondary_account
+                ))
+        } else {
+            oracle_slot_price_info.is_none() && oracle_slot_secondary_info.is_none()
+        }
+    }
+
     pub fn symbol(&self) -> &str {
         std::str::from_utf8(&self.name)
             .unwrap_or("InvalidTokenName")
@@ -257,6 +347,170 @@ impl PythConfiguration {
     }
 }
 
+#[repr(u8)]
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    TryFromPrimitive,
+    IntoPrimitive,
+    PartialEq,
+    Eq,
+    Debug,
+    Clone,
+    Copy,
+)]
+pub enum OracleType {
+    None = 0,
+    Pyth = 1,
+    Switchboard = 2,
+    Scope = 3,
+    StakePool = 4,
+    FixedPrice = 5,
+    ReserveRatio = 6,
+}
+
+#[repr(u8)]
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    TryFromPrimitive,
+    IntoPrimitive,
+    PartialEq,
+    Eq,
+    Debug,
+    Clone,
+    Copy,
+)]
+pub enum StakePoolBasePriceSource {
+    Reserve = 0,
+    Pyth = 1,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[zero_copy]
+#[repr(C)]
+pub struct OracleSlotConfiguration {
+    #[cfg_attr(feature = "serde", serde(with = "serde_string", default))]
+    pub price_account: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "serde_string", default))]
+    pub secondary_account: Pubkey,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub params: [u8; 16],
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub oracle_type: u8,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
+    pub padding: [u8; 7],
+}
+
+impl OracleSlotConfiguration {
+    pub fn oracle_type(&self) -> Option<OracleType> {
+        OracleType::try_from(self.oracle_type).ok()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        match self.oracle_type() {
+            None | Some(OracleType::None) => false,
+            Some(OracleType::FixedPrice) => true,
+            Some(_) => self.price_account != Pubkey::default() && self.price_account != NULL_PUBKEY,
+        }
+    }
+
+    pub fn is_pegged(&self) -> bool {
+        matches!(
+            self.oracle_type(),
+            Some(OracleType::FixedPrice | OracleType::ReserveRatio)
+        )
+    }
+
+    pub fn requires_price_account(&self) -> bool {
+        self.oracle_type() != Some(OracleType::FixedPrice)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self.oracle_type() {
+            None => false,
+            Some(OracleType::Scope) => {
+                let (price_chain, _) = self.scope_chains();
+                !self.is_enabled() || (price_chain != [u16::MAX; 4] && price_chain != [0; 4])
+            }
+            Some(OracleType::StakePool) => {
+                !self.is_enabled()
+                    || (self.has_secondary_account() && self.stake_pool_base_source().is_some())
+            }
+            Some(OracleType::FixedPrice | OracleType::ReserveRatio) => {
+                let (peg_value, peg_exp) = self.peg_value_and_exp();
+                peg_value > 0 && peg_exp <= 18
+            }
+            Some(_) => true,
+        }
+    }
+
+    pub fn requires_secondary_account(&self, twap_enabled: bool) -> bool {
+        match self.oracle_type() {
+            Some(OracleType::Switchboard) => twap_enabled && self.has_secondary_account(),
+            Some(OracleType::StakePool) => true,
+            _ => false,
+        }
+    }
+
+    pub fn has_secondary_account(&self) -> bool {
+        self.secondary_account != Pubkey::default() && self.secondary_account != NULL_PUBKEY
+    }
+
+    pub fn has_twap(&self) -> bool {
+        match self.oracle_type() {
+            Some(OracleType::Pyth) => true,
+            Some(OracleType::Switchboard) => self.has_secondary_account(),
+            Some(OracleType::Scope) => {
+                let (_, twap_chain) = self.scope_chains();
+                twap_chain != [u16::MAX; 4] && twap_chain != [0; 4]
+            }
+            Some(OracleType::StakePool) => {
+                self.stake_pool_base_source() == Some(StakePoolBasePriceSource::Pyth)
+            }
+            Some(OracleType::FixedPrice | OracleType::ReserveRatio)
+            | Some(OracleType::None)
+            | None => false,
+        }
+    }
+
+    pub fn peg_value_and_exp(&self) -> (u64, u8) {
+        (
+            u64::from_le_bytes(self.params[..8].try_into().unwrap()),
+            self.params[8],
+        )
+    }
+
+    pub fn peg_max_depeg_bps(&self) -> u16 {
+        u16::from_le_bytes([self.params[9], self.params[10]])
+    }
+
+    pub fn stake_pool_base_source(&self) -> Option<StakePoolBasePriceSource> {
+        StakePoolBasePriceSource::try_from(self.params[0]).ok()
+    }
+
+    pub fn stake_pool_max_epochs_since_update(&self) -> u64 {
+        self.params[1].into()
+    }
+
+    pub fn stake_pool_max_rate_change_bps_per_epoch(&self) -> u16 {
+        u16::from_le_bytes([self.params[2], self.params[3]])
+    }
+
+    pub fn scope_chains(&self) -> ([u16; 4], [u16; 4]) {
+        let mut chains = [0_u16; 8];
+        for (chain_elem, bytes) in chains.iter_mut().zip(self.params.chunks_exact(2)) {
+            *chain_elem = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+        (
+            chains[..4].try_into().unwrap(),
+            chains[4..].try_into().unwrap(),
+        )
+    }
+}
+
 #[cfg(feature = "serde")]
 mod serde_name {
     use serde::{Deserialize, Deserializer, Serializer};
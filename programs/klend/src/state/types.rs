@@ -1,4 +1,4 @@
-use crate::{utils::Fraction, PriceStatusFlags};
+use crate::{utils::Fraction, ElevationGroupDebtKind, PriceStatusFlags};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DepositLiquidityResult {
@@ -46,6 +46,7 @@ pub struct LiquidateAndRedeemResult {
 pub struct LiquidationParams {
     pub user_ltv: Fraction,
     pub liquidation_bonus_rate: Fraction,
+    pub max_liquidatable_debt_value: Option<Fraction>,
 }
 
 pub struct RefreshObligationDepositsResult {
@@ -57,6 +58,7 @@ pub struct RefreshObligationDepositsResult {
     pub unhealthy_borrow_value_f: Fraction,
     pub prices_state: PriceStatusFlags,
     pub borrowing_disabled: bool,
+    pub borrowing_disabled_by_price_circuit_breaker: bool,
 }
 
 pub struct RefreshObligationBorrowsResult {
@@ -64,7 +66,29 @@ pub struct RefreshObligationBorrowsResult {
     pub borrowed_assets_market_value_f: Fraction,
     pub prices_state: PriceStatusFlags,
     pub highest_borrow_factor_pct: u64,
-    pub borrowed_amount_in_elevation_group: Option<u64>,
+    pub borrowed_amounts_in_elevation_group: Option<ElevationGroupDebtAmounts>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ElevationGroupDebtAmounts {
+    pub primary: u64,
+    pub secondary: u64,
+}
+
+impl ElevationGroupDebtAmounts {
+    pub fn get(&self, debt_kind: ElevationGroupDebtKind) -> u64 {
+        match debt_kind {
+            ElevationGroupDebtKind::Primary => self.primary,
+            ElevationGroupDebtKind::Secondary => self.secondary,
+        }
+    }
+
+    pub fn get_mut(&mut self, debt_kind: ElevationGroupDebtKind) -> &mut u64 {
+        match debt_kind {
+            ElevationGroupDebtKind::Primary => &mut self.primary,
+            ElevationGroupDebtKind::Secondary => &mut self.secondary,
+        }
+    }
 }
 
 pub enum LendingAction {
@@ -73,12 +97,40 @@ pub enum LendingAction {
     SubstractiveSigned(i64),
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReserveAction {
+    Deposit,
+    Withdraw,
+    Borrow,
+    Repay,
+    Liquidate,
+    FlashLoan,
+}
+
+impl ReserveAction {
+    pub fn default_required_price_status(self) -> PriceStatusFlags {
+        match self {
+            ReserveAction::Deposit | ReserveAction::Repay | ReserveAction::FlashLoan => {
+                PriceStatusFlags::NONE
+            }
+            ReserveAction::Withdraw | ReserveAction::Borrow => PriceStatusFlags::ALL_CHECKS,
+            ReserveAction::Liquidate => PriceStatusFlags::LIQUIDATION_CHECKS,
+        }
+    }
+}
+
 #[derive(PartialEq)]
 pub enum MaxReservesAsCollateralCheck {
     Perform,
     Skip,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LeverageAdjustment {
+    Increase { borrow_liquidity_amount: u64 },
+    Decrease { withdraw_collateral_amount: u64 },
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum LtvMaxWithdrawalCheck {
     MaxLtv,
@@ -5,12 +5,17 @@ use super::{FractionExtra, FULL_BPS};
 use crate::{utils::Fraction, LendingError};
 
 pub const MAX_UTILIZATION_RATE_BPS: u32 = FULL_BPS as u32;
+pub const BORROW_RATE_CURVE_POINTS: usize = 11;
+pub const BORROW_RATE_CURVE_EXTENSION_POINTS: usize = 21;
+pub const MAX_BORROW_RATE_CURVE_POINTS: usize =
+    BORROW_RATE_CURVE_POINTS + BORROW_RATE_CURVE_EXTENSION_POINTS;
+pub const MAX_BORROW_RATE_CURVE_SEGMENT_EXPONENT: u8 = 8;
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq, Eq)]
 #[zero_copy]
 #[repr(C)]
 pub struct BorrowRateCurve {
-    pub points: [CurvePoint; 11],
+    pub points: [CurvePoint; BORROW_RATE_CURVE_POINTS],
 }
 
 #[cfg(feature = "serde")]
@@ -30,19 +35,7 @@ impl serde::Serialize for BorrowRateCurve {
     where
         S: serde::Serializer,
     {
-        let mut end_reached = false;
-        let points = self
-            .points
-            .iter()
-            .take_while(|p| {
-                if end_reached {
-                    return false;
-                } else if p.utilization_rate_bps == MAX_UTILIZATION_RATE_BPS {
-                    end_reached = true;
-                }
-                true
-            })
-            .collect::<Vec<_>>();
+        let points = points_until_max_utilization(&self.points);
         serde::Serialize::serialize(&points, serializer)
     }
 }
@@ -53,6 +46,82 @@ impl Default for BorrowRateCurve {
     }
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, PartialEq, Eq)]
+#[zero_copy]
+#[repr(C)]
+pub struct BorrowRateCurveExtension {
+    pub points: [CurvePoint; BORROW_RATE_CURVE_EXTENSION_POINTS],
+    pub segment_exponents: [u8; MAX_BORROW_RATE_CURVE_POINTS - 1],
+    pub padding: [u8; 5],
+    pub max_borrow_rate_bps: u32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+struct BorrowRateCurveExtensionSerde {
+    #[serde(default)]
+    points: Vec<CurvePoint>,
+    #[serde(default)]
+    segment_exponents: Vec<u8>,
+    #[serde(default)]
+    max_borrow_rate_bps: u32,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BorrowRateCurveExtension {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<BorrowRateCurveExtension, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let ext = <BorrowRateCurveExtensionSerde as serde::Deserialize>::deserialize(deserializer)?;
+        BorrowRateCurveExtension::new(&ext.points, &ext.segment_exponents, ext.max_borrow_rate_bps)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BorrowRateCurveExtension {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let points = if self.has_extra_points() {
+            points_until_max_utilization(&self.points)
+        } else {
+            Vec::new()
+        };
+        let segments_len = self
+            .segment_exponents
+            .iter()
+            .rposition(|exponent| *exponent != 0)
+            .map_or(0, |pos| pos + 1);
+        let ext = BorrowRateCurveExtensionSerde {
+            points,
+            segment_exponents: self.segment_exponents[..segments_len].to_vec(),
+            max_borrow_rate_bps: self.max_borrow_rate_bps,
+        };
+        serde::Serialize::serialize(&ext, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn points_until_max_utilization(points: &[CurvePoint]) -> Vec<CurvePoint> {
+    let mut end_reached = false;
+    points
+        .iter()
+        .take_while(|p| {
+            if end_reached {
+                return false;
+            } else if p.utilization_rate_bps == MAX_UTILIZATION_RATE_BPS {
+                end_reached = true;
+            }
+            true
+        })
+        .copied()
+        .collect()
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CurveSegment {
     pub slope_nom: u32,
@@ -118,52 +187,100 @@ impl CurveSegment {
 
         Ok(base_rate + offset)
     }
+
+    pub(self) fn get_borrow_rate_with_exponent(
+        &self,
+        utilization_rate: Fraction,
+        exponent: u8,
+    ) -> Result<Fraction> {
+        if exponent <= 1 {
+            return self.get_borrow_rate(utilization_rate);
+        }
+
+        let start_utilization_rate = Fraction::from_bps(self.start_point.utilization_rate_bps);
+
+        let coef = utilization_rate
+            .checked_sub(start_utilization_rate)
+            .ok_or_else(|| error!(LendingError::InvalidUtilizationRate))?;
+
+        let progress = coef * u128::from(MAX_UTILIZATION_RATE_BPS) / u128::from(self.slope_denom);
+        let progress = progress
+            .checked_pow(exponent.into())
+            .ok_or_else(|| error!(LendingError::MathOverflow))?;
+
+        let base_rate = Fraction::from_bps(self.slope_nom) * progress;
+
+        let offset = Fraction::from_bps(self.start_point.borrow_rate_bps);
+
+        Ok(base_rate + offset)
+    }
 }
 
 impl BorrowRateCurve {
     pub fn validate(&self) -> Result<()> {
-        let pts = &self.points;
+        validate_curve_points(&self.points)
+    }
 
-        if pts[0].utilization_rate_bps != 0 {
-            msg!("First point of borrowing rate curve must have an utilization rate of 0");
-            return err!(LendingError::InvalidBorrowRateCurvePoint);
+    pub fn validate_extended(&self, extension: &BorrowRateCurveExtension) -> Result<()> {
+        if extension.has_extra_points() {
+            validate_curve_points(&self.extended_points(extension))?;
+        } else {
+            if extension
+                .points
+                .iter()
+                .any(|pt| *pt != CurvePoint::default())
+            {
+                msg!("Borrowing rate curve extension points must continue the base curve");
+                return err!(LendingError::InvalidBorrowRateCurvePoint);
+            }
+            self.validate()?;
         }
 
-        if pts[10].utilization_rate_bps != MAX_UTILIZATION_RATE_BPS {
-            msg!("Last point of borrowing rate curve must have an utilization rate of 1");
+        if extension
+            .segment_exponents
+            .iter()
+            .any(|exponent| *exponent > MAX_BORROW_RATE_CURVE_SEGMENT_EXPONENT)
+        {
+            msg!(
+                "Borrowing rate curve segment exponent cannot be bigger than {}",
+                MAX_BORROW_RATE_CURVE_SEGMENT_EXPONENT
+            );
             return err!(LendingError::InvalidBorrowRateCurvePoint);
         }
 
-        let mut last_pt = pts[0];
-        for pt in pts.iter().skip(1) {
-            if last_pt.utilization_rate_bps == MAX_UTILIZATION_RATE_BPS {
-                if pt.utilization_rate_bps != MAX_UTILIZATION_RATE_BPS {
-                    msg!(
-                        "Last point of borrowing rate curve must have an utilization rate of 1 but lower utilization \
-                        rate found after last point"
-                    );
-                    return err!(LendingError::InvalidBorrowRateCurvePoint);
-                }
-            } else if pt.utilization_rate_bps <= last_pt.utilization_rate_bps {
-                msg!("Borrowing rate curve points must be sorted by utilization rate");
-                return err!(LendingError::InvalidBorrowRateCurvePoint);
-            }
-            if pt.borrow_rate_bps < last_pt.borrow_rate_bps {
-                msg!("Borrowing rate must growing in the curve");
-                return err!(LendingError::InvalidBorrowRateCurvePoint);
-            }
-            last_pt = *pt;
+        if extension.is_borrow_rate_capped()
+            && extension.max_borrow_rate_bps <= self.points[0].borrow_rate_bps
+        {
+            msg!("Max borrow rate must be bigger than the borrow rate at 0% utilization");
+            return err!(LendingError::InvalidBorrowRateCurvePoint);
         }
+
         Ok(())
     }
 
+    pub fn extended_points(
+        &self,
+        extension: &BorrowRateCurveExtension,
+    ) -> [CurvePoint; MAX_BORROW_RATE_CURVE_POINTS] {
+        let last = self.points[BORROW_RATE_CURVE_POINTS - 1];
+        let mut points = [last; MAX_BORROW_RATE_CURVE_POINTS];
+        points[..BORROW_RATE_CURVE_POINTS].copy_from_slice(&self.points);
+        if extension.has_extra_points() {
+            points[BORROW_RATE_CURVE_POINTS..].copy_from_slice(&extension.points);
+        }
+        points
+    }
+
     pub fn from_points(pts: &[CurvePoint]) -> Result<Self> {
         if pts.len() < 2 {
             msg!("Borrowing rate curve must have at least 2 points");
             return err!(LendingError::InvalidBorrowRateCurvePoint);
         }
-        if pts.len() > 11 {
-            msg!("Borrowing rate curve must have at most 11 points");
+        if pts.len() > BORROW_RATE_CURVE_POINTS {
+            msg!(
+                "Borrowing rate curve must have at most {} points",
+                BORROW_RATE_CURVE_POINTS
+            );
             return err!(LendingError::InvalidBorrowRateCurvePoint);
         }
         let last = pts.last().unwrap();
@@ -171,7 +288,7 @@ impl BorrowRateCurve {
             msg!("Last point of borrowing rate curve must have an utilization rate of 1");
             return err!(LendingError::InvalidBorrowRateCurvePoint);
         }
-        let mut points = [*last; 11];
+        let mut points = [*last; BORROW_RATE_CURVE_POINTS];
 
         points[..pts.len()].copy_from_slice(pts);
 
@@ -180,6 +297,40 @@ impl BorrowRateCurve {
         Ok(curve)
     }
 
+    pub fn from_points_extended(
+        pts: &[CurvePoint],
+        segment_exponents: &[u8],
+        max_borrow_rate_bps: u32,
+    ) -> Result<(Self, BorrowRateCurveExtension)> {
+        if pts.len() > MAX_BORROW_RATE_CURVE_POINTS {
+            msg!(
+                "Borrowing rate curve must have at most {} points",
+                MAX_BORROW_RATE_CURVE_POINTS
+            );
+            return err!(LendingError::InvalidBorrowRateCurvePoint);
+        }
+
+        let (curve, extension) = if pts.len() <= BORROW_RATE_CURVE_POINTS {
+            (
+                Self::from_points(pts)?,
+                BorrowRateCurveExtension::new(&[], segment_exponents, max_borrow_rate_bps)?,
+            )
+        } else {
+            let points = pts[..BORROW_RATE_CURVE_POINTS].try_into().unwrap();
+            (
+                BorrowRateCurve { points },
+                BorrowRateCurveExtension::new(
+                    &pts[BORROW_RATE_CURVE_POINTS..],
+                    segment_exponents,
+                    max_borrow_rate_bps,
+                )?,
+            )
+        };
+
+        curve.validate_extended(&extension)?;
+        Ok((curve, extension))
+    }
+
     pub fn new_flat(borrow_rate_bps: u32) -> Self {
         let points = [
             CurvePoint {
@@ -252,38 +403,138 @@ impl BorrowRateCurve {
     }
 
     pub fn get_borrow_rate(&self, utilization_rate: Fraction) -> Result<Fraction> {
-        let utilization_rate = if utilization_rate > Fraction::ONE {
+        get_borrow_rate_from_points(&self.points, &[], utilization_rate)
+    }
+
+    pub fn get_borrow_rate_extended(
+        &self,
+        extension: &BorrowRateCurveExtension,
+        utilization_rate: Fraction,
+    ) -> Result<Fraction> {
+        get_borrow_rate_from_points(
+            &self.extended_points(extension),
+            &extension.segment_exponents,
+            utilization_rate,
+        )
+    }
+}
+
+impl BorrowRateCurveExtension {
+    pub fn new(
+        pts: &[CurvePoint],
+        segment_exponents: &[u8],
+        max_borrow_rate_bps: u32,
+    ) -> Result<Self> {
+        if pts.len() > BORROW_RATE_CURVE_EXTENSION_POINTS {
             msg!(
-                "Warning: utilization rate is greater than 100% (scaled): {}",
-                utilization_rate.to_bits()
+                "Borrowing rate curve extension must have at most {} points",
+                BORROW_RATE_CURVE_EXTENSION_POINTS
             );
-            Fraction::ONE
-        } else {
-            utilization_rate
+            return err!(LendingError::InvalidBorrowRateCurvePoint);
+        }
+        if segment_exponents.len() > MAX_BORROW_RATE_CURVE_POINTS - 1 {
+            msg!(
+                "Borrowing rate curve must have at most {} segments",
+                MAX_BORROW_RATE_CURVE_POINTS - 1
+            );
+            return err!(LendingError::InvalidBorrowRateCurvePoint);
+        }
+
+        let mut extension = Self {
+            max_borrow_rate_bps,
+            ..Default::default()
         };
+        if let Some(last) = pts.last() {
+            extension.points = [*last; BORROW_RATE_CURVE_EXTENSION_POINTS];
+            extension.points[..pts.len()].copy_from_slice(pts);
+        }
+        extension.segment_exponents[..segment_exponents.len()].copy_from_slice(segment_exponents);
+        Ok(extension)
+    }
 
-        let utilization_rate_bps: u32 = utilization_rate.to_bps().unwrap();
-
-        let (start_pt, end_pt) = self
-            .points
-            .windows(2)
-            .map(|seg| {
-                let [first, second]: &[CurvePoint; 2] = seg.try_into().unwrap();
-                (first, second)
-            })
-            .find(|(first, second)| {
-                utilization_rate_bps >= first.utilization_rate_bps
-                    && utilization_rate_bps <= second.utilization_rate_bps
-            })
-            .unwrap();
-        if utilization_rate_bps == start_pt.utilization_rate_bps {
-            return Ok(Fraction::from_bps(start_pt.borrow_rate_bps));
-        } else if utilization_rate_bps == end_pt.utilization_rate_bps {
-            return Ok(Fraction::from_bps(end_pt.borrow_rate_bps));
+    pub fn has_extra_points(&self) -> bool {
+        self.points[0].utilization_rate_bps != 0
+    }
+
+    pub fn is_borrow_rate_capped(&self) -> bool {
+        self.max_borrow_rate_bps != 0
+    }
+}
+
+fn validate_curve_points(pts: &[CurvePoint]) -> Result<()> {
+    if pts[0].utilization_rate_bps != 0 {
+        msg!("First point of borrowing rate curve must have an utilization rate of 0");
+        return err!(LendingError::InvalidBorrowRateCurvePoint);
+    }
+
+    if pts[pts.len() - 1].utilization_rate_bps != MAX_UTILIZATION_RATE_BPS {
+        msg!("Last point of borrowing rate curve must have an utilization rate of 1");
+        return err!(LendingError::InvalidBorrowRateCurvePoint);
+    }
+
+    let mut last_pt = pts[0];
+    for pt in pts.iter().skip(1) {
+        if last_pt.utilization_rate_bps == MAX_UTILIZATION_RATE_BPS {
+            if pt.utilization_rate_bps != MAX_UTILIZATION_RATE_BPS {
+                msg!(
+                    "Last point of borrowing rate curve must have an utilization rate of 1 but lower utilization \
+                    rate found after last point"
+                );
+                return err!(LendingError::InvalidBorrowRateCurvePoint);
+            }
+        } else if pt.utilization_rate_bps <= last_pt.utilization_rate_bps {
+            msg!("Borrowing rate curve points must be sorted by utilization rate");
+            return err!(LendingError::InvalidBorrowRateCurvePoint);
+        }
+        if pt.borrow_rate_bps < last_pt.borrow_rate_bps {
+            msg!("Borrowing rate must growing in the curve");
+            return err!(LendingError::InvalidBorrowRateCurvePoint);
         }
+        last_pt = *pt;
+    }
+    Ok(())
+}
 
-        let segment = CurveSegment::from_points(*start_pt, *end_pt)?;
+fn get_borrow_rate_from_points(
+    points: &[CurvePoint],
+    segment_exponents: &[u8],
+    utilization_rate: Fraction,
+) -> Result<Fraction> {
+    let utilization_rate = if utilization_rate > Fraction::ONE {
+        msg!(
+            "Warning: utilization rate is greater than 100% (scaled): {}",
+            utilization_rate.to_bits()
+        );
+        Fraction::ONE
+    } else {
+        utilization_rate
+    };
 
-        segment.get_borrow_rate(utilization_rate)
+    let utilization_rate_bps: u32 = utilization_rate.to_bps().unwrap();
+
+    let (segment_index, (start_pt, end_pt)) = points
+        .windows(2)
+        .map(|seg| {
+            let [first, second]: &[CurvePoint; 2] = seg.try_into().unwrap();
+            (first, second)
+        })
+        .enumerate()
+        .find(|(_, (first, second))| {
+            utilization_rate_bps >= first.utilization_rate_bps
+                && utilization_rate_bps <= second.utilization_rate_bps
+        })
+        .unwrap();
+    if utilization_rate_bps == start_pt.utilization_rate_bps {
+        return Ok(Fraction::from_bps(start_pt.borrow_rate_bps));
+    } else if utilization_rate_bps == end_pt.utilization_rate_bps {
+        return Ok(Fraction::from_bps(end_pt.borrow_rate_bps));
     }
+
+    let segment = CurveSegment::from_points(*start_pt, *end_pt)?;
+    let exponent = segment_exponents
+        .get(segment_index)
+        .copied()
+        .unwrap_or_default();
+
+    segment.get_borrow_rate_with_exponent(utilization_rate, exponent)
 }
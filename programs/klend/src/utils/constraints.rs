@@ -54,6 +54,7 @@ pub mod token_2022 {
     pub fn validate_liquidity_token_extensions(
         mint_acc_info: &AccountInfo,
         token_acc_info: &AccountInfo,
+        allowed_transfer_hook_program: &Pubkey,
     ) -> anchor_lang::Result<()> {
         if mint_acc_info.owner == &spl_token::id() {
             return Ok(());
@@ -78,26 +79,14 @@ pub mod token_2022 {
                 );
                 return err!(LendingError::UnsupportedTokenExtension);
             }
-            if mint_ext == ExtensionType::TransferFeeConfig {
-                let ext = mint
-                    .get_extension::<spl_token_2022::extension::transfer_fee::TransferFeeConfig>(
-                    )?;
-                if <u16>::from(ext.older_transfer_fee.transfer_fee_basis_points) != 0
-                    || <u16>::from(ext.newer_transfer_fee.transfer_fee_basis_points) != 0
-                {
-                    xmsg!(
-                        "Transfer fee must be 0 for liquidity tokens, got: {:?}",
-                        ext
-                    );
-                    return err!(LendingError::UnsupportedTokenExtension);
-                }
-            } else if mint_ext == ExtensionType::TransferHook {
+            if mint_ext == ExtensionType::TransferHook {
                 let ext =
                     mint.get_extension::<spl_token_2022::extension::transfer_hook::TransferHook>()?;
                 let hook_program_id: Option<Pubkey> = ext.program_id.into();
-                if hook_program_id.is_some() {
+                if hook_program_id.is_some_and(|id| id != *allowed_transfer_hook_program) {
                     xmsg!(
-                        "Transfer hook program id must not be set for liquidity tokens, got {:?}",
+                        "Transfer hook program id must match the reserve allowed hook program {}, got {:?}",
+                        allowed_transfer_hook_program,
                         ext
                     );
                     return err!(LendingError::UnsupportedTokenExtension);
@@ -136,4 +125,19 @@ pub mod token_2022 {
         }
         Ok(())
     }
+
+    pub fn get_transfer_hook_program_id(
+        mint_acc_info: &AccountInfo,
+    ) -> anchor_lang::Result<Option<Pubkey>> {
+        if mint_acc_info.owner == &spl_token::id() {
+            return Ok(None);
+        }
+
+        let mint_data = mint_acc_info.data.borrow();
+        let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+        Ok(mint
+            .get_extension::<spl_token_2022::extension::transfer_hook::TransferHook>()
+            .ok()
+            .and_then(|ext| ext.program_id.into()))
+    }
 }
@@ -37,6 +37,8 @@ pub const NO_DELEVERAGING_MARKER: u8 = u8::MAX;
 
 pub const MAX_OBLIGATION_RESERVES: u64 = 20;
 
+pub const MAX_OBLIGATION_FARM_RESERVES: usize = 16;
+
 pub const CLOSE_TO_INSOLVENCY_RISKY_LTV: u8 = 95;
 
 pub const MIN_INITIAL_DEPOSIT_AMOUNT: u64 = 1000;
@@ -52,7 +54,7 @@ pub const NULL_PUBKEY: solana_program::pubkey::Pubkey =
 pub const LENDING_MARKET_SIZE: usize = 4656;
 pub const RESERVE_SIZE: usize = 8616;
 pub const OBLIGATION_SIZE: usize = 3336;
-pub const RESERVE_CONFIG_SIZE: usize = 920;
+pub const RESERVE_CONFIG_SIZE: usize = 1448;
 pub const REFERRER_TOKEN_STATE_SIZE: usize = 352;
 pub const USER_METADATA_SIZE: usize = 1024;
 pub const REFERRER_STATE_SIZE: usize = 64;
@@ -65,8 +67,14 @@ pub const DEFAULT_BORROW_FACTOR_PCT: u64 = 100;
 
 pub const ELEVATION_GROUP_NONE: u8 = 0;
 
+pub const ELEVATION_GROUP_AUTO: u8 = u8::MAX;
+
 pub const MAX_NUM_ELEVATION_GROUPS: u8 = 32;
 
+pub const MAX_FEE_RECIPIENTS: usize = 4;
+
+pub const PRICE_CIRCUIT_BREAKER_SAMPLES: usize = 8;
+
 pub const USD_DECIMALS: u32 = 6;
 
 pub const MIN_NET_VALUE_IN_OBLIGATION: Fraction = fraction!(0.000001);
@@ -147,6 +155,9 @@ pub const EXPONENT_CORE_ID_MAINNET: Pubkey =
 pub const AGRO_ID_MAINNET: Pubkey = pubkey!("AgroFiE3bX7j4Tvfa7YAoFLqjjb35Bw6eed5BuYukPEn");
 pub const AGRO_STAGING_ID_MAINNET: Pubkey = pubkey!("E7jPY6J5s2uAxAjJQX5tqoASkmFr6TYxVoMm97hPLNZ1");
 
+pub const SPL_STAKE_POOL_PROGRAM_ID: Pubkey =
+    pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+
 pub const CPI_WHITELISTED_ACCOUNTS: [CpiWhitelistedAccount; 16] = [
     CpiWhitelistedAccount::new(FLEX_LEND_ID_MAINNET_PROD, 1),
     CpiWhitelistedAccount::new(SQUADS_PROGRAM_ID_V3_MAINNET_PROD, 1),
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use super::{
+    pyth::PythAdapter, scope::ScopeAdapter, stake_pool::StakePoolAdapter,
+    switchboard::SwitchboardAdapter, types::TimestampedPriceWithTwap,
+};
+use crate::{OracleType, TokenInfo};
+
+pub(super) trait OracleAdapter {
+    fn get_price_and_twap(
+        &self,
+        price_account: &AccountInfo,
+        secondary_account: Option<&AccountInfo>,
+        clock: &Clock,
+    ) -> Result<TimestampedPriceWithTwap>;
+}
+
+pub(super) fn get_oracle_slot_adapter(
+    token_info: &TokenInfo,
+    lending_market: Pubkey,
+) -> Option<Box<dyn OracleAdapter>> {
+    let oracle_slot = &token_info.oracle_slot;
+    if !oracle_slot.is_enabled() {
+        return None;
+    }
+
+    match oracle_slot.oracle_type()? {
+        OracleType::None | OracleType::FixedPrice | OracleType::ReserveRatio => None,
+        OracleType::Pyth => Some(Box::new(PythAdapter::from(token_info))),
+        OracleType::Switchboard => Some(Box::new(SwitchboardAdapter)),
+        OracleType::Scope => {
+            let (price_chain, twap_chain) = oracle_slot.scope_chains();
+            Some(Box::new(ScopeAdapter {
+                price_chain,
+                twap_chain,
+            }))
+        }
+        OracleType::StakePool => Some(Box::new(StakePoolAdapter {
+            base_price_source: oracle_slot.stake_pool_base_source()?,
+            max_epochs_since_update: oracle_slot.stake_pool_max_epochs_since_update(),
+            max_rate_change_bps_per_epoch: oracle_slot.stake_pool_max_rate_change_bps_per_epoch(),
+            base_pyth_adapter: PythAdapter::from(token_info),
+            lending_market,
+        })),
+    }
+}
@@ -15,7 +15,7 @@ pub(super) fn get_validated_price(
 
     let TimestampedPriceWithTwap { price, twap } = price_and_twap;
 
-    let mut price_status = PriceStatusFlags::empty();
+    let mut price_status = PriceStatusFlags::PEG_CHECKED;
     let price_label = token_info.symbol();
 
     let price_dec = match (price.price_load)() {
@@ -82,6 +82,7 @@ pub(super) fn get_validated_price(
 
     Some(GetPriceResult {
         price: price_dec,
+        confidence: price.confidence,
         timestamp: price.timestamp,
         status: price_status,
     })
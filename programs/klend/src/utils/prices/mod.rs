@@ -1,6 +1,9 @@
+mod adapter;
 mod checks;
+mod pegged;
 mod pyth;
 mod scope;
+mod stake_pool;
 mod switchboard;
 mod types;
 mod utils;
@@ -9,8 +12,13 @@ use anchor_lang::prelude::*;
 use types::{Price, TimestampedPrice};
 
 use self::{
-    checks::get_validated_price, pyth::get_pyth_price_and_twap, scope::get_scope_price_and_twap,
-    switchboard::get_switchboard_price_and_twap, types::TimestampedPriceWithTwap,
+    adapter::{get_oracle_slot_adapter, OracleAdapter},
+    checks::get_validated_price,
+    pegged::{apply_depeg_guard, get_pegged_price},
+    pyth::PythAdapter,
+    scope::ScopeAdapter,
+    switchboard::SwitchboardAdapter,
+    types::TimestampedPriceWithTwap,
 };
 use crate::{utils::Fraction, LendingError, PriceStatusFlags, TokenInfo};
 
@@ -21,68 +29,126 @@ const CONFIDENCE_FACTOR: u64 = 100 / MAX_CONFIDENCE_PERCENTAGE;
 #[derive(Debug, Clone)]
 pub struct GetPriceResult {
     pub price: Fraction,
+    pub confidence: Fraction,
     pub timestamp: u64,
     pub status: PriceStatusFlags,
 }
 
+pub struct PriceAccounts<'a, 'info> {
+    pub pyth_price: Option<&'a AccountInfo<'info>>,
+    pub switchboard_price: Option<&'a AccountInfo<'info>>,
+    pub switchboard_twap: Option<&'a AccountInfo<'info>>,
+    pub scope_prices: Option<&'a AccountInfo<'info>>,
+    pub oracle_slot_price: Option<&'a AccountInfo<'info>>,
+    pub oracle_slot_secondary: Option<&'a AccountInfo<'info>>,
+    pub lending_market: Pubkey,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReservePriceEma {
+    pub price: Fraction,
+    pub timestamp: u64,
+}
+
+struct OracleSource<'a, 'info> {
+    adapter: Box<dyn OracleAdapter>,
+    price_account: Option<&'a AccountInfo<'info>>,
+    secondary_account: Option<&'a AccountInfo<'info>>,
+}
+
 pub fn get_price(
     token_info: &TokenInfo,
-    pyth_price_account_info: Option<&AccountInfo>,
-    switchboard_price_feed_info: Option<&AccountInfo>,
-    switchboard_price_twap_info: Option<&AccountInfo>,
-    scope_prices_info: Option<&AccountInfo>,
+    price_accounts: &PriceAccounts,
+    price_ema: Option<ReservePriceEma>,
     clock: &Clock,
 ) -> Result<Option<GetPriceResult>> {
-    let price = get_most_recent_price_and_twap(
-        token_info,
-        pyth_price_account_info,
-        switchboard_price_feed_info,
-        switchboard_price_twap_info,
-        scope_prices_info,
-        clock,
-    )?;
-
-    Ok(get_validated_price(price, token_info, clock.unix_timestamp))
+    let (mut price, depegged) = match get_pegged_price(token_info, price_accounts, clock)? {
+        Some(pegged_price) => apply_depeg_guard(token_info, pegged_price, price_accounts, clock),
+        None => (
+            get_most_recent_price_and_twap(token_info, price_accounts, clock)?,
+            false,
+        ),
+    };
+
+    if token_info.is_price_ema_enabled() {
+        price.twap = price_ema.map(|ReservePriceEma { price, timestamp }| TimestampedPrice {
+            price_load: Box::new(move || Ok(price)),
+            timestamp,
+            confidence: Fraction::ZERO,
+        });
+    }
+
+    let mut price_result = get_validated_price(price, token_info, clock.unix_timestamp);
+    if depegged {
+        if let Some(price_result) = price_result.as_mut() {
+            price_result.status.remove(PriceStatusFlags::PEG_CHECKED);
+        }
+    }
+
+    Ok(price_result)
 }
 
-fn get_most_recent_price_and_twap(
+fn get_oracle_sources<'a, 'info>(
     token_info: &TokenInfo,
-    pyth_price_account_info: Option<&AccountInfo>,
-    switchboard_price_feed_info: Option<&AccountInfo>,
-    switchboard_price_twap_info: Option<&AccountInfo>,
-    scope_prices_info: Option<&AccountInfo>,
-    clock: &Clock,
-) -> Result<TimestampedPriceWithTwap> {
-    let pyth_price = if token_info.pyth_configuration.is_enabled() {
-        pyth_price_account_info.and_then(|a| get_pyth_price_and_twap(a).ok())
-    } else {
-        None
-    };
+    price_accounts: &PriceAccounts<'a, 'info>,
+) -> Vec<OracleSource<'a, 'info>> {
+    let twap_enabled = token_info.is_twap_enabled();
+    let mut sources: Vec<OracleSource> = Vec::with_capacity(4);
 
-    let switchboard_price_twap_info_opt = if token_info.is_twap_enabled() {
-        switchboard_price_twap_info
-    } else {
-        None
-    };
+    if token_info.pyth_configuration.is_enabled() {
+        sources.push(OracleSource {
+            adapter: Box::new(PythAdapter::from(token_info)),
+            price_account: price_accounts.pyth_price,
+            secondary_account: None,
+        });
+    }
 
-    let switchboard_price = if token_info.switchboard_configuration.is_enabled() {
-        switchboard_price_feed_info.and_then(|a| {
-            get_switchboard_price_and_twap(a, switchboard_price_twap_info_opt, clock).ok()
-        })
-    } else {
-        None
-    };
+    if token_info.switchboard_configuration.is_enabled() {
+        sources.push(OracleSource {
+            adapter: Box::new(SwitchboardAdapter),
+            price_account: price_accounts.switchboard_price,
+            secondary_account: price_accounts.switchboard_twap.filter(|_| twap_enabled),
+        });
+    }
 
-    let scope_price = if token_info.scope_configuration.is_enabled() {
-        scope_prices_info
-            .and_then(|a| get_scope_price_and_twap(a, &token_info.scope_configuration).ok())
-    } else {
-        None
-    };
+    if token_info.scope_configuration.is_enabled() {
+        sources.push(OracleSource {
+            adapter: Box::new(ScopeAdapter::from(&token_info.scope_configuration)),
+            price_account: price_accounts.scope_prices,
+            secondary_account: None,
+        });
+    }
+
+    if let Some(adapter) = get_oracle_slot_adapter(token_info, price_accounts.lending_market) {
+        sources.push(OracleSource {
+            adapter,
+            price_account: price_accounts.oracle_slot_price,
+            secondary_account: price_accounts.oracle_slot_secondary.filter(|_| {
+                token_info
+                    .oracle_slot
+                    .requires_secondary_account(twap_enabled)
+            }),
+        });
+    }
+
+    sources
+}
 
-    let most_recent_price = [pyth_price, switchboard_price, scope_price]
+fn get_most_recent_price_and_twap(
+    token_info: &TokenInfo,
+    price_accounts: &PriceAccounts,
+    clock: &Clock,
+) -> Result<TimestampedPriceWithTwap> {
+    let most_recent_price = get_oracle_sources(token_info, price_accounts)
         .into_iter()
-        .flatten()
+        .filter_map(|source| {
+            source.price_account.and_then(|price_account| {
+                source
+                    .adapter
+                    .get_price_and_twap(price_account, source.secondary_account, clock)
+                    .ok()
+            })
+        })
         .reduce(|current, candidate| {
             if candidate.price.timestamp > current.price.timestamp {
                 candidate
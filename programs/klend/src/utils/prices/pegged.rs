@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+
+use super::{
+    get_most_recent_price_and_twap,
+    types::{Price, TimestampedPrice, TimestampedPriceWithTwap},
+    utils::{get_reference_reserve_price, price_to_fraction},
+    PriceAccounts,
+};
+use crate::{
+    utils::{Fraction, FULL_BPS},
+    LendingError, OracleType, TokenInfo,
+};
+
+pub(super) fn get_pegged_price(
+    token_info: &TokenInfo,
+    price_accounts: &PriceAccounts,
+    clock: &Clock,
+) -> Result<Option<TimestampedPriceWithTwap>> {
+    let oracle_slot = &token_info.oracle_slot;
+    if !oracle_slot.is_enabled() || !oracle_slot.is_pegged() {
+        return Ok(None);
+    }
+
+    let (peg_value, peg_exp) = oracle_slot.peg_value_and_exp();
+    let peg = price_to_fraction(Price {
+        value: peg_value,
+        exp: peg_exp.into(),
+    });
+
+    let price = match oracle_slot.oracle_type() {
+        Some(OracleType::FixedPrice) => TimestampedPrice {
+            price_load: Box::new(move || Ok(peg)),
+            timestamp: u64::try_from(clock.unix_timestamp).unwrap(),
+            confidence: Fraction::ZERO,
+        },
+        Some(OracleType::ReserveRatio) => {
+            let reference_reserve_account = price_accounts.oracle_slot_price.ok_or_else(|| {
+                msg!("Pegged reference reserve account is missing");
+                error!(LendingError::InvalidOracleConfig)
+            })?;
+            let TimestampedPrice {
+                price_load,
+                timestamp,
+                confidence,
+            } = get_reference_reserve_price(
+                reference_reserve_account,
+                &price_accounts.lending_market,
+            )?;
+            TimestampedPrice {
+                price_load: Box::new(move || Ok(price_load()? * peg)),
+                timestamp,
+                confidence: confidence * peg,
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(TimestampedPriceWithTwap { price, twap: None }))
+}
+
+pub(super) fn apply_depeg_guard(
+    token_info: &TokenInfo,
+    pegged_price: TimestampedPriceWithTwap,
+    price_accounts: &PriceAccounts,
+    clock: &Clock,
+) -> (TimestampedPriceWithTwap, bool) {
+    let max_depeg_bps = token_info.oracle_slot.peg_max_depeg_bps();
+    if max_depeg_bps == 0 {
+        return (pegged_price, false);
+    }
+
+    let TimestampedPriceWithTwap {
+        price:
+            TimestampedPrice {
+                price_load,
+                timestamp,
+                confidence,
+            },
+        twap,
+    } = pegged_price;
+    let pegged_price_result = price_load();
+    let rewrap_pegged = |pegged_value: Result<Fraction>| TimestampedPriceWithTwap {
+        price: TimestampedPrice {
+            price_load: Box::new(move || pegged_value),
+            timestamp,
+            confidence,
+        },
+        twap,
+    };
+
+    let live_price = match get_most_recent_price_and_twap(token_info, price_accounts, clock) {
+        Ok(live_price) => live_price,
+        Err(_) => {
+            msg!("No live price available to check the peg, price marked as degraded");
+            return (rewrap_pegged(pegged_price_result), true);
+        }
+    };
+
+    let TimestampedPriceWithTwap {
+        price:
+            TimestampedPrice {
+                price_load: live_price_load,
+                timestamp: live_timestamp,
+                confidence: live_confidence,
+            },
+        twap: live_twap,
+    } = live_price;
+
+    let (pegged_value, live_value) = match (pegged_price_result, live_price_load()) {
+        (Ok(pegged_value), Ok(live_value)) => (pegged_value, live_value),
+        (pegged_price_result, _) => {
+            msg!("Live price could not be loaded to check the peg, price marked as degraded");
+            return (rewrap_pegged(pegged_price_result), true);
+        }
+    };
+
+    let divergence = Fraction::abs_diff(pegged_value, live_value);
+    if divergence * u128::from(FULL_BPS) <= pegged_value * u128::from(max_depeg_bps) {
+        return (rewrap_pegged(Ok(pegged_value)), false);
+    }
+
+    msg!(
+        "Live price {} diverges from pegged price {} by more than {} bps, falling back to live price",
+        live_value,
+        pegged_value,
+        max_depeg_bps
+    );
+
+    (
+        TimestampedPriceWithTwap {
+            price: TimestampedPrice {
+                price_load: Box::new(move || Ok(live_value)),
+                timestamp: live_timestamp,
+                confidence: live_confidence,
+            },
+            twap: live_twap,
+        },
+        true,
+    )
+}
@@ -6,28 +6,77 @@ use pyth_solana_receiver_sdk::price_update::{
 };
 
 use super::{
+    adapter::OracleAdapter,
     types::{Price, TimestampedPriceWithTwap},
     utils, TimestampedPrice,
 };
-use crate::LendingError;
+use crate::{utils::Fraction, LendingError, TokenInfo};
 
-pub(super) fn get_pyth_price_and_twap(
-    pyth_price_info: &AccountInfo,
-) -> Result<TimestampedPriceWithTwap> {
-    let price_feed = load_price_feed_from_account_info(pyth_price_info)?;
+pub(super) struct PythAdapter {
+    pub min_verification_level: VerificationLevel,
+    pub confidence_adjustment_factor: u8,
+}
+
+impl From<&TokenInfo> for PythAdapter {
+    fn from(token_info: &TokenInfo) -> Self {
+        let min_verification_level = if token_info.pyth_min_verification_signatures == 0 {
+            VerificationLevel::Full
+        } else {
+            VerificationLevel::Partial {
+                num_signatures: token_info.pyth_min_verification_signatures,
+            }
+        };
+        Self {
+            min_verification_level,
+            confidence_adjustment_factor: token_info.pyth_confidence_adjustment_factor,
+        }
+    }
+}
+
+impl OracleAdapter for PythAdapter {
+    fn get_price_and_twap(
+        &self,
+        price_account: &AccountInfo,
+        _secondary_account: Option<&AccountInfo>,
+        _clock: &Clock,
+    ) -> Result<TimestampedPriceWithTwap> {
+        let price_feed =
+            load_price_feed_from_account_info(price_account, self.min_verification_level)?;
+
+        let (price, twap) = into_pyth_price_and_twap(price_feed);
 
-    let (price, twap) = into_pyth_price_and_twap(price_feed);
+        if self.confidence_adjustment_factor == 0 {
+            validate_pyth_confidence(&price, super::CONFIDENCE_FACTOR)?;
+            validate_pyth_confidence(&twap, super::CONFIDENCE_FACTOR)?;
 
-    validate_pyth_confidence(&price, super::CONFIDENCE_FACTOR)?;
-    validate_pyth_confidence(&twap, super::CONFIDENCE_FACTOR)?;
+            Ok(TimestampedPriceWithTwap {
+                price: price.into(),
+                twap: Some(twap.into()),
+            })
+        } else {
+            if price.price <= 0 {
+                return err!(LendingError::PriceIsZero);
+            }
+            let confidence = get_adjusted_confidence(
+                price.conf,
+                price.exponent,
+                self.confidence_adjustment_factor,
+            );
+            let mut price = TimestampedPrice::from(price);
+            price.confidence = confidence;
 
-    Ok(TimestampedPriceWithTwap {
-        price: price.into(),
-        twap: Some(twap.into()),
-    })
+            Ok(TimestampedPriceWithTwap {
+                price,
+                twap: (twap.price > 0).then(|| twap.into()),
+            })
+        }
+    }
 }
 
-fn load_price_feed_from_account_info(pyth_price_info: &AccountInfo) -> Result<PriceFeedMessage> {
+fn load_price_feed_from_account_info(
+    pyth_price_info: &AccountInfo,
+    min_verification_level: VerificationLevel,
+) -> Result<PriceFeedMessage> {
     let price_update_data = pyth_price_info.data.borrow();
     let PriceUpdateV2 {
         write_authority: _,
@@ -35,12 +84,25 @@ fn load_price_feed_from_account_info(pyth_price_info: &AccountInfo) -> Result<Pr
         price_message,
         posted_slot: _,
     } = PriceUpdateV2::try_deserialize(&mut price_update_data.as_ref())?;
-    if !verification_level.gte(VerificationLevel::Full) {
+    if !verification_level.gte(min_verification_level) {
+        msg!(
+            "Pyth price verification level {:?} is below the required {:?}",
+            verification_level,
+            min_verification_level
+        );
         return err!(LendingError::PriceNotValid);
     }
     Ok(price_message)
 }
 
+fn get_adjusted_confidence(conf: u64, exponent: i32, confidence_adjustment_factor: u8) -> Fraction {
+    let scaled_conf = Price {
+        value: u128::from(conf) * u128::from(confidence_adjustment_factor),
+        exp: exponent.checked_abs().unwrap() as u32,
+    };
+    utils::price_to_fraction(scaled_conf)
+}
+
 fn into_pyth_price_and_twap(price_feed: PriceFeedMessage) -> (PythPrice, PythPrice) {
     let PriceFeedMessage {
         feed_id: _,
@@ -104,6 +166,7 @@ impl From<PythPrice> for TimestampedPrice {
         TimestampedPrice {
             price_load,
             timestamp,
+            confidence: Fraction::ZERO,
         }
     }
 }
@@ -4,29 +4,56 @@ use anchor_lang::{__private::bytemuck, prelude::*, Discriminator};
 pub use scope::OraclePrices as ScopePrices;
 
 use super::{
+    adapter::OracleAdapter,
     types::{TimestampedPrice, TimestampedPriceWithTwap},
     utils::price_to_fraction,
 };
 use crate::{
     dbg_msg,
-    utils::{prices::Price, MAX_PRICE_DECIMALS_U256, NULL_PUBKEY, TARGET_PRICE_DECIMALS, U256},
+    utils::{
+        prices::Price, Fraction, MAX_PRICE_DECIMALS_U256, NULL_PUBKEY, TARGET_PRICE_DECIMALS, U256,
+    },
     LendingError, Result, ScopeConfiguration,
 };
 
-pub(super) fn get_scope_price_and_twap(
-    scope_price_account: &AccountInfo,
-    conf: &ScopeConfiguration,
-) -> Result<TimestampedPriceWithTwap> {
-    let scope_prices = get_price_account(scope_price_account)?;
-    let price = get_price_usd(&scope_prices, conf.price_chain)?;
-    let twap = if conf.has_twap() {
-        get_price_usd(&scope_prices, conf.twap_chain)
-            .map_err(|e| msg!("No valid twap found for scope price, error: {:?}", e))
-            .ok()
-    } else {
-        None
-    };
-    Ok(TimestampedPriceWithTwap { price, twap })
+pub(super) struct ScopeAdapter {
+    pub price_chain: ScopeConversionChain,
+    pub twap_chain: ScopeConversionChain,
+}
+
+impl From<&ScopeConfiguration> for ScopeAdapter {
+    fn from(conf: &ScopeConfiguration) -> Self {
+        Self {
+            price_chain: conf.price_chain,
+            twap_chain: conf.twap_chain,
+        }
+    }
+}
+
+impl ScopeAdapter {
+    fn has_twap(&self) -> bool {
+        self.twap_chain != [u16::MAX; 4] && self.twap_chain != [0; 4]
+    }
+}
+
+impl OracleAdapter for ScopeAdapter {
+    fn get_price_and_twap(
+        &self,
+        price_account: &AccountInfo,
+        _secondary_account: Option<&AccountInfo>,
+        _clock: &Clock,
+    ) -> Result<TimestampedPriceWithTwap> {
+        let scope_prices = get_price_account(price_account)?;
+        let price = get_price_usd(&scope_prices, self.price_chain)?;
+        let twap = if self.has_twap() {
+            get_price_usd(&scope_prices, self.twap_chain)
+                .map_err(|e| msg!("No valid twap found for scope price, error: {:?}", e))
+                .ok()
+        } else {
+            None
+        };
+        Ok(TimestampedPriceWithTwap { price, twap })
+    }
 }
 
 type ScopePriceId = u16;
@@ -89,6 +116,7 @@ fn get_price_usd(
         return Ok(TimestampedPrice {
             price_load,
             timestamp: price.1,
+            confidence: Fraction::ZERO,
         });
     }
 
@@ -131,6 +159,7 @@ fn get_price_usd(
     Ok(TimestampedPrice {
         price_load,
         timestamp: oldest_timestamp,
+        confidence: Fraction::ZERO,
     })
 }
 
@@ -0,0 +1,179 @@
+use anchor_lang::prelude::*;
+
+use super::{
+    adapter::OracleAdapter,
+    pyth::PythAdapter,
+    types::{TimestampedPrice, TimestampedPriceWithTwap},
+    utils::get_reference_reserve_price,
+};
+use crate::{
+    utils::{Fraction, FULL_BPS, SPL_STAKE_POOL_PROGRAM_ID},
+    LendingError, StakePoolBasePriceSource,
+};
+
+const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+
+pub(super) struct StakePoolAdapter {
+    pub base_price_source: StakePoolBasePriceSource,
+    pub max_epochs_since_update: u64,
+    pub max_rate_change_bps_per_epoch: u16,
+    pub base_pyth_adapter: PythAdapter,
+    pub lending_market: Pubkey,
+}
+
+impl OracleAdapter for StakePoolAdapter {
+    fn get_price_and_twap(
+        &self,
+        price_account: &AccountInfo,
+        secondary_account: Option<&AccountInfo>,
+        clock: &Clock,
+    ) -> Result<TimestampedPriceWithTwap> {
+        let exchange_rate = self.get_exchange_rate(price_account, clock)?;
+
+        let base_price_account = secondary_account.ok_or_else(|| {
+            msg!("Stake pool base price account is missing");
+            error!(LendingError::InvalidOracleConfig)
+        })?;
+
+        let TimestampedPriceWithTwap { price, twap } = match self.base_price_source {
+            StakePoolBasePriceSource::Reserve => TimestampedPriceWithTwap {
+                price: get_reference_reserve_price(base_price_account, &self.lending_market)?,
+                twap: None,
+            },
+            StakePoolBasePriceSource::Pyth => {
+                self.base_pyth_adapter
+                    .get_price_and_twap(base_price_account, None, clock)?
+            }
+        };
+
+        Ok(TimestampedPriceWithTwap {
+            price: scale_price(price, exchange_rate),
+            twap: twap.map(|twap| scale_price(twap, exchange_rate)),
+        })
+    }
+}
+
+impl StakePoolAdapter {
+    fn get_exchange_rate(
+        &self,
+        stake_pool_account: &AccountInfo,
+        clock: &Clock,
+    ) -> Result<Fraction> {
+        if *stake_pool_account.owner != SPL_STAKE_POOL_PROGRAM_ID {
+            msg!("Stake pool account is not owned by the stake pool program");
+            return err!(LendingError::InvalidAccountOwner);
+        }
+
+        let data = stake_pool_account.try_borrow_data()?;
+        let stake_pool = StakePool::deserialize(&mut &data[..])?;
+        if stake_pool.account_type != STAKE_POOL_ACCOUNT_TYPE {
+            msg!("Stake pool account has an invalid account type");
+            return err!(LendingError::InvalidOracleConfig);
+        }
+
+        let epochs_since_update = clock.epoch.saturating_sub(stake_pool.last_update_epoch);
+        if epochs_since_update > self.max_epochs_since_update {
+            msg!(
+                "Stake pool was last updated {} epochs ago, max allowed {}",
+                epochs_since_update,
+                self.max_epochs_since_update
+            );
+            return err!(LendingError::PriceTooOld);
+        }
+
+        if stake_pool.pool_token_supply == 0 || stake_pool.total_lamports == 0 {
+            return err!(LendingError::PriceIsZero);
+        }
+        let exchange_rate =
+            Fraction::from(stake_pool.total_lamports) / u128::from(stake_pool.pool_token_supply);
+
+        if self.max_rate_change_bps_per_epoch > 0
+            && stake_pool.last_epoch_pool_token_supply > 0
+            && stake_pool.last_epoch_total_lamports > 0
+        {
+            let last_epoch_exchange_rate = Fraction::from(stake_pool.last_epoch_total_lamports)
+                / u128::from(stake_pool.last_epoch_pool_token_supply);
+            let rate_change = Fraction::abs_diff(exchange_rate, last_epoch_exchange_rate);
+            if rate_change * u128::from(FULL_BPS)
+                > last_epoch_exchange_rate * u128::from(self.max_rate_change_bps_per_epoch)
+            {
+                msg!(
+                    "Stake pool exchange rate moved from {} to {} in one epoch, max allowed {} bps",
+                    last_epoch_exchange_rate,
+                    exchange_rate,
+                    self.max_rate_change_bps_per_epoch
+                );
+                return err!(LendingError::PriceNotValid);
+            }
+        }
+
+        Ok(exchange_rate)
+    }
+}
+
+fn scale_price(price: TimestampedPrice, exchange_rate: Fraction) -> TimestampedPrice {
+    let TimestampedPrice {
+        price_load,
+        timestamp,
+        confidence,
+    } = price;
+    TimestampedPrice {
+        price_load: Box::new(move || Ok(price_load()? * exchange_rate)),
+        timestamp,
+        confidence: confidence * exchange_rate,
+    }
+}
+
+#[derive(AnchorDeserialize)]
+struct StakePoolFee {
+    _denominator: u64,
+    _numerator: u64,
+}
+
+#[derive(AnchorDeserialize)]
+enum StakePoolFutureEpochFee {
+    _None,
+    _One(StakePoolFee),
+    _Two(StakePoolFee),
+}
+
+#[derive(AnchorDeserialize)]
+struct StakePoolLockup {
+    _unix_timestamp: i64,
+    _epoch: u64,
+    _custodian: Pubkey,
+}
+
+#[derive(AnchorDeserialize)]
+struct StakePool {
+    account_type: u8,
+    _manager: Pubkey,
+    _staker: Pubkey,
+    _stake_deposit_authority: Pubkey,
+    _stake_withdraw_bump_seed: u8,
+    _validator_list: Pubkey,
+    _reserve_stake: Pubkey,
+    _pool_mint: Pubkey,
+    _manager_fee_account: Pubkey,
+    _token_program_id: Pubkey,
+    total_lamports: u64,
+    pool_token_supply: u64,
+    last_update_epoch: u64,
+    _lockup: StakePoolLockup,
+    _epoch_fee: StakePoolFee,
+    _next_epoch_fee: StakePoolFutureEpochFee,
+    _preferred_deposit_validator_vote_address: Option<Pubkey>,
+    _preferred_withdraw_validator_vote_address: Option<Pubkey>,
+    _stake_deposit_fee: StakePoolFee,
+    _stake_withdrawal_fee: StakePoolFee,
+    _next_stake_withdrawal_fee: StakePoolFutureEpochFee,
+    _stake_referral_fee: u8,
+    _sol_deposit_authority: Option<Pubkey>,
+    _sol_deposit_fee: StakePoolFee,
+    _sol_referral_fee: u8,
+    _sol_withdraw_authority: Option<Pubkey>,
+    _sol_withdrawal_fee: StakePoolFee,
+    _next_sol_withdrawal_fee: StakePoolFutureEpochFee,
+    last_epoch_pool_token_supply: u64,
+    last_epoch_total_lamports: u64,
+}
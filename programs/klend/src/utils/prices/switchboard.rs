@@ -9,16 +9,30 @@ use solana_program::clock::{Clock, DEFAULT_MS_PER_SLOT};
 use crate::{
     utils::{
         prices::{
+            adapter::OracleAdapter,
             types::{TimestampedPrice, TimestampedPriceWithTwap},
             utils::price_to_fraction,
             CONFIDENCE_FACTOR,
         },
-        FatAccountLoader, NULL_PUBKEY,
+        FatAccountLoader, Fraction, NULL_PUBKEY,
     },
     LendingError,
 };
 
-pub(super) fn get_switchboard_price_and_twap(
+pub(super) struct SwitchboardAdapter;
+
+impl OracleAdapter for SwitchboardAdapter {
+    fn get_price_and_twap(
+        &self,
+        price_account: &AccountInfo,
+        secondary_account: Option<&AccountInfo>,
+        clock: &Clock,
+    ) -> Result<TimestampedPriceWithTwap> {
+        get_switchboard_price_and_twap(price_account, secondary_account, clock)
+    }
+}
+
+fn get_switchboard_price_and_twap(
     switchboard_price_feed_info: &AccountInfo,
     switchboard_twap_feed_info: Option<&AccountInfo>,
     clock: &Clock,
@@ -92,6 +106,7 @@ fn get_switchboard_price(
     Ok(TimestampedPrice {
         price_load,
         timestamp,
+        confidence: Fraction::ZERO,
     })
 }
 
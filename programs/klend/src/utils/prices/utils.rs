@@ -1,9 +1,16 @@
-use super::types::Price;
+use anchor_lang::prelude::*;
+
+use super::types::{Price, TimestampedPrice};
 use crate::{
     fraction::BigFraction,
-    utils::{Fraction, U128, U256},
+    utils::{FatAccountLoader, Fraction, U128, U256},
+    LendingError, PriceStatusFlags, Reserve,
 };
 
+const REFERENCE_RESERVE_REQUIRED_PRICE_STATUS: PriceStatusFlags = PriceStatusFlags::PRICE_LOADED
+    .union(PriceStatusFlags::PRICE_AGE_CHECKED)
+    .union(PriceStatusFlags::HEURISTIC_CHECKED);
+
 pub(crate) fn price_to_fraction<T>(price: Price<T>) -> Fraction
 where
     T: Into<U256> + Copy,
@@ -19,6 +26,40 @@ where
         .expect("Failed to convert Price stored on BigFraction to Fraction")
 }
 
+pub(super) fn get_reference_reserve_price(
+    reference_reserve_account: &AccountInfo,
+    lending_market: &Pubkey,
+) -> Result<TimestampedPrice> {
+    let reference_reserve_loader =
+        FatAccountLoader::<Reserve>::try_from(reference_reserve_account)?;
+    let reference_reserve = reference_reserve_loader.load()?;
+
+    if reference_reserve.lending_market != *lending_market {
+        msg!("Reference reserve belongs to another lending market");
+        return err!(LendingError::InvalidAccountInput);
+    }
+
+    let price_status = reference_reserve.last_update.get_price_status();
+    if !price_status.contains(REFERENCE_RESERVE_REQUIRED_PRICE_STATUS) {
+        msg!(
+            "Reference reserve price is not valid, price status: {:08b}",
+            price_status.0
+        );
+        return err!(LendingError::PriceNotValid);
+    }
+
+    let reference_price = reference_reserve.liquidity.get_market_price_f();
+    if reference_price == Fraction::ZERO {
+        return err!(LendingError::PriceIsZero);
+    }
+
+    Ok(TimestampedPrice {
+        price_load: Box::new(move || Ok(reference_price)),
+        timestamp: reference_reserve.liquidity.market_price_last_updated_ts,
+        confidence: reference_reserve.liquidity.get_market_price_confidence_f(),
+    })
+}
+
 pub(super) fn ten_pow(exponent: u32) -> U128 {
     let value: u128 = match exponent {
         36 => 1_000_000_000_000_000_000_000_000_000_000_000_000,
@@ -7,6 +7,8 @@ pub const BASE_SEED_REFERRER_TOKEN_STATE: &[u8] = b"referrer_acc";
 pub const BASE_SEED_USER_METADATA: &[u8] = b"user_meta";
 pub const BASE_SEED_REFERRER_STATE: &[u8] = b"ref_state";
 pub const BASE_SEED_SHORT_URL: &[u8] = b"short_url";
+pub const NATIVE_LIQUIDITY: &[u8] = b"native_liq";
+pub const BASE_SEED_FARM_USER_STATE: &[u8] = b"user";
 
 pub mod pda {
     use anchor_lang::prelude::Pubkey;
@@ -67,6 +69,19 @@ pub mod pda {
         }
     }
 
+    pub fn obligation_farm_user_state(farm_state: &Pubkey, obligation: &Pubkey) -> Pubkey {
+        let (obligation_farm_user_state, _obligation_farm_user_state_bump) =
+            Pubkey::find_program_address(
+                &[
+                    BASE_SEED_FARM_USER_STATE,
+                    farm_state.as_ref(),
+                    obligation.as_ref(),
+                ],
+                &farms::ID,
+            );
+        obligation_farm_user_state
+    }
+
     pub fn referrer_token_state(referrer: Pubkey, reserve: Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(
             &[
@@ -1,10 +1,95 @@
 use anchor_lang::{
-    prelude::{AccountInfo, CpiContext},
-    Result,
+    err, error,
+    prelude::{msg, AccountInfo, Clock, CpiContext, SolanaSysvar},
+    system_program, Result,
+};
+use anchor_spl::{
+    token_2022::{self, spl_token_2022},
+    token_interface::{
+        self,
+        spl_token_2022::extension::{
+            transfer_fee::{TransferFee, TransferFeeConfig},
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+    },
 };
-use anchor_spl::token_interface;
 
 use super::spltoken;
+use crate::LendingError;
+
+pub fn get_transfer_fee(liquidity_mint: &AccountInfo, amount: u64) -> Result<u64> {
+    with_transfer_fee(liquidity_mint, |transfer_fee| {
+        transfer_fee
+            .calculate_fee(amount)
+            .ok_or_else(|| error!(LendingError::MathOverflow))
+    })
+}
+
+pub fn get_amount_after_transfer_fee(liquidity_mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let transfer_fee = get_transfer_fee(liquidity_mint, amount)?;
+    Ok(amount - transfer_fee)
+}
+
+pub fn get_amount_including_transfer_fee(
+    liquidity_mint: &AccountInfo,
+    amount_after_fee: u64,
+) -> Result<u64> {
+    if amount_after_fee == 0 {
+        return Ok(0);
+    }
+
+    let transfer_fee = with_transfer_fee(liquidity_mint, |transfer_fee| {
+        calculate_inverse_transfer_fee(transfer_fee, amount_after_fee)
+    })?;
+
+    amount_after_fee
+        .checked_add(transfer_fee)
+        .ok_or_else(|| error!(LendingError::MathOverflow))
+}
+
+fn calculate_inverse_transfer_fee(
+    transfer_fee: &TransferFee,
+    amount_after_fee: u64,
+) -> Result<u64> {
+    let inverse_fee = transfer_fee
+        .calculate_inverse_fee(amount_after_fee)
+        .ok_or_else(|| error!(LendingError::MathOverflow))?;
+    let amount = amount_after_fee
+        .checked_add(inverse_fee)
+        .ok_or_else(|| error!(LendingError::MathOverflow))?;
+    let fee = transfer_fee
+        .calculate_fee(amount)
+        .ok_or_else(|| error!(LendingError::MathOverflow))?;
+
+    if amount - fee != amount_after_fee {
+        msg!(
+            "Transfer fee {} for amount {} does not yield the expected amount {}",
+            fee,
+            amount,
+            amount_after_fee
+        );
+        return err!(LendingError::InvalidTransferFee);
+    }
+
+    Ok(inverse_fee)
+}
+
+fn with_transfer_fee(
+    liquidity_mint: &AccountInfo,
+    calculate_fee: impl FnOnce(&TransferFee) -> Result<u64>,
+) -> Result<u64> {
+    if liquidity_mint.owner != &token_2022::ID {
+        return Ok(0);
+    }
+
+    let mint_data = liquidity_mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+
+    calculate_fee(transfer_fee_config.get_epoch_fee(Clock::get()?.epoch))
+}
 
 pub fn deposit_obligation_collateral_transfer<'a>(
     from: AccountInfo<'a>,
@@ -44,18 +129,20 @@ pub fn deposit_reserve_liquidity_transfer<'a>(
     liquidity_deposit_amount: u64,
     liquidity_decimals: u8,
     collateral_mint_amount: u64,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
-    token_interface::transfer_checked(
-        CpiContext::new(
-            liquidity_token_program.clone(),
-            token_interface::TransferChecked {
-                from: source_liquidity_deposit,
-                to: destination_liquidity_deposit,
-                authority: user_authority,
-                mint: liquidity_mint,
-            },
-        ),
-        liquidity_deposit_amount,
+    let liquidity_transfer_amount =
+        get_amount_including_transfer_fee(&liquidity_mint, liquidity_deposit_amount)?;
+
+    transfer_checked(
+        liquidity_token_program.clone(),
+        source_liquidity_deposit,
+        liquidity_mint,
+        destination_liquidity_deposit,
+        user_authority,
+        &[],
+        transfer_hook_accounts,
+        liquidity_transfer_amount,
         liquidity_decimals,
     )?;
 
@@ -71,30 +158,33 @@ pub fn deposit_reserve_liquidity_transfer<'a>(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn deposit_initial_reserve_liquidity_transfer<'a>(
     source_liquidity_deposit: AccountInfo<'a>,
     destination_liquidity_deposit: AccountInfo<'a>,
-    admin_authority: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
     liquidity_mint: AccountInfo<'a>,
     liquidity_token_program: AccountInfo<'a>,
     liquidity_deposit_amount: u64,
     liquidity_decimals: u8,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
     if liquidity_deposit_amount == 0 {
         return Ok(());
     }
 
-    token_interface::transfer_checked(
-        CpiContext::new(
-            liquidity_token_program.clone(),
-            token_interface::TransferChecked {
-                from: source_liquidity_deposit,
-                to: destination_liquidity_deposit,
-                authority: admin_authority,
-                mint: liquidity_mint,
-            },
-        ),
-        liquidity_deposit_amount,
+    let liquidity_transfer_amount =
+        get_amount_including_transfer_fee(&liquidity_mint, liquidity_deposit_amount)?;
+
+    transfer_checked(
+        liquidity_token_program.clone(),
+        source_liquidity_deposit,
+        liquidity_mint,
+        destination_liquidity_deposit,
+        authority,
+        &[],
+        transfer_hook_accounts,
+        liquidity_transfer_amount,
         liquidity_decimals,
     )
 }
@@ -114,6 +204,7 @@ pub fn deposit_reserve_liquidity_and_obligation_collateral_transfer<'a>(
     liquidity_deposit_amount: u64,
     liquidity_decimals: u8,
     collateral_mint_amount: u64,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
     deposit_reserve_liquidity_transfer(
         source_liquidity_deposit,
@@ -129,6 +220,7 @@ pub fn deposit_reserve_liquidity_and_obligation_collateral_transfer<'a>(
         liquidity_deposit_amount,
         liquidity_decimals,
         collateral_mint_amount,
+        transfer_hook_accounts,
     )
 }
 
@@ -172,6 +264,7 @@ pub fn redeem_reserve_collateral_transfer<'a>(
     collateral_amount: u64,
     liquidity_amount: u64,
     liquidity_decimals: u8,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
     spltoken::burn(
         reserve_collateral_mint,
@@ -181,17 +274,14 @@ pub fn redeem_reserve_collateral_transfer<'a>(
         collateral_amount,
     )?;
 
-    token_interface::transfer_checked(
-        CpiContext::new_with_signer(
-            liquidity_token_program,
-            token_interface::TransferChecked {
-                from: reserve_liquidity_supply,
-                to: destination_liquidity,
-                authority: lending_market_authority,
-                mint: reserve_liquidity_mint,
-            },
-            &[authority_signer_seeds],
-        ),
+    transfer_checked(
+        liquidity_token_program,
+        reserve_liquidity_supply,
+        reserve_liquidity_mint,
+        destination_liquidity,
+        lending_market_authority,
+        &[authority_signer_seeds],
+        transfer_hook_accounts,
         liquidity_amount,
         liquidity_decimals,
     )?;
@@ -212,6 +302,7 @@ pub fn withdraw_and_redeem_reserve_collateral_transfer<'a>(
     collateral_amount: u64,
     liquidity_amount: u64,
     liquidity_decimals: u8,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
     spltoken::burn_with_signer(
         reserve_collateral_mint,
@@ -222,23 +313,21 @@ pub fn withdraw_and_redeem_reserve_collateral_transfer<'a>(
         &[authority_signer_seeds],
     )?;
 
-    token_interface::transfer_checked(
-        CpiContext::new_with_signer(
-            liquidity_token_program,
-            token_interface::TransferChecked {
-                from: reserve_liquidity_supply,
-                to: user_destination_liquidity,
-                authority: lending_market_authority,
-                mint: reserve_liquidity_mint,
-            },
-            &[authority_signer_seeds],
-        ),
+    transfer_checked(
+        liquidity_token_program,
+        reserve_liquidity_supply,
+        reserve_liquidity_mint,
+        user_destination_liquidity,
+        lending_market_authority,
+        &[authority_signer_seeds],
+        transfer_hook_accounts,
         liquidity_amount,
         liquidity_decimals,
     )?;
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn repay_obligation_liquidity_transfer<'a>(
     token_program: AccountInfo<'a>,
     liquidity_mint: AccountInfo<'a>,
@@ -247,18 +336,19 @@ pub fn repay_obligation_liquidity_transfer<'a>(
     user_authority: AccountInfo<'a>,
     repay_amount: u64,
     decimals: u8,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
-    token_interface::transfer_checked(
-        CpiContext::new(
-            token_program,
-            token_interface::TransferChecked {
-                from: user_liquidity,
-                to: reserve_liquidity,
-                authority: user_authority,
-                mint: liquidity_mint,
-            },
-        ),
-        repay_amount,
+    let repay_transfer_amount = get_amount_including_transfer_fee(&liquidity_mint, repay_amount)?;
+
+    transfer_checked(
+        token_program,
+        user_liquidity,
+        liquidity_mint,
+        reserve_liquidity,
+        user_authority,
+        &[],
+        transfer_hook_accounts,
+        repay_transfer_amount,
         decimals,
     )?;
 
@@ -275,18 +365,16 @@ pub fn borrow_obligation_liquidity_transfer<'a>(
     authority_signer_seeds: &[&[u8]],
     liquidity_amount: u64,
     liquidity_decimals: u8,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
-    token_interface::transfer_checked(
-        CpiContext::new_with_signer(
-            token_program,
-            token_interface::TransferChecked {
-                from: reserve_liquidity,
-                to: user_liquidity,
-                authority: lending_market_authority,
-                mint: liquidity_mint,
-            },
-            &[authority_signer_seeds],
-        ),
+    transfer_checked(
+        token_program,
+        reserve_liquidity,
+        liquidity_mint,
+        user_liquidity,
+        lending_market_authority,
+        &[authority_signer_seeds],
+        transfer_hook_accounts,
         liquidity_amount,
         liquidity_decimals,
     )?;
@@ -294,6 +382,7 @@ pub fn borrow_obligation_liquidity_transfer<'a>(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn pay_borrowing_fees_transfer<'a>(
     token_program: AccountInfo<'a>,
     liquidity_mint: AccountInfo<'a>,
@@ -302,18 +391,19 @@ pub fn pay_borrowing_fees_transfer<'a>(
     user_authority: AccountInfo<'a>,
     fee: u64,
     decimals: u8,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
-    token_interface::transfer_checked(
-        CpiContext::new(
-            token_program,
-            token_interface::TransferChecked {
-                from: user_liquidity,
-                to: fee_collector,
-                authority: user_authority,
-                mint: liquidity_mint,
-            },
-        ),
-        fee,
+    let fee_transfer_amount = get_amount_including_transfer_fee(&liquidity_mint, fee)?;
+
+    transfer_checked(
+        token_program,
+        user_liquidity,
+        liquidity_mint,
+        fee_collector,
+        user_authority,
+        &[],
+        transfer_hook_accounts,
+        fee_transfer_amount,
         decimals,
     )?;
 
@@ -330,18 +420,16 @@ pub fn send_origination_fees_transfer<'a>(
     authority_signer_seeds: &[&[u8]],
     fee: u64,
     decimals: u8,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
-    token_interface::transfer_checked(
-        CpiContext::new_with_signer(
-            token_program,
-            token_interface::TransferChecked {
-                to: fee_receiver,
-                from: reserve_liquidity,
-                authority: lending_market_authority,
-                mint: reserve_liquidity_mint,
-            },
-            &[authority_signer_seeds],
-        ),
+    transfer_checked(
+        token_program,
+        reserve_liquidity,
+        reserve_liquidity_mint,
+        fee_receiver,
+        lending_market_authority,
+        &[authority_signer_seeds],
+        transfer_hook_accounts,
         fee,
         decimals,
     )?;
@@ -359,21 +447,183 @@ pub fn withdraw_fees_from_reserve<'a>(
     authority_signer_seeds: &[&[u8]],
     withdraw_amount: u64,
     mint_decimals: u8,
+    transfer_hook_accounts: &[AccountInfo<'a>],
 ) -> Result<()> {
-    token_interface::transfer_checked(
-        CpiContext::new_with_signer(
-            token_program,
-            token_interface::TransferChecked {
-                from: reserve_supply_liquidity,
-                to: fee_receiver,
-                authority: lending_market_authority,
-                mint: reserve_liquidity_mint,
-            },
-            &[authority_signer_seeds],
-        ),
+    transfer_checked(
+        token_program,
+        reserve_supply_liquidity,
+        reserve_liquidity_mint,
+        fee_receiver,
+        lending_market_authority,
+        &[authority_signer_seeds],
+        transfer_hook_accounts,
         withdraw_amount,
         mint_decimals,
     )?;
 
     Ok(())
 }
+
+pub fn wrap_native_liquidity_transfer<'a>(
+    system_program: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    user_source_lamports: AccountInfo<'a>,
+    native_liquidity_account: AccountInfo<'a>,
+    amount: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program,
+            system_program::Transfer {
+                from: user_source_lamports,
+                to: native_liquidity_account.clone(),
+            },
+        ),
+        amount,
+    )?;
+
+    token_interface::sync_native(CpiContext::new(
+        token_program,
+        token_interface::SyncNative {
+            account: native_liquidity_account,
+        },
+    ))
+}
+
+pub fn close_native_liquidity_account<'a>(
+    token_program: AccountInfo<'a>,
+    native_liquidity_account: AccountInfo<'a>,
+    user_destination_lamports: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program,
+        token_interface::CloseAccount {
+            account: native_liquidity_account,
+            destination: user_destination_lamports,
+            authority,
+        },
+        signer_seeds,
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked<'a>(
+    token_program: AccountInfo<'a>,
+    from: AccountInfo<'a>,
+    mint: AccountInfo<'a>,
+    to: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+    transfer_hook_accounts: &[AccountInfo<'a>],
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    if transfer_hook_accounts.is_empty() {
+        return token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                token_program,
+                token_interface::TransferChecked {
+                    from,
+                    mint,
+                    to,
+                    authority,
+                },
+                signer_seeds,
+            ),
+            amount,
+            decimals,
+        );
+    }
+
+    spl_token_2022::onchain::invoke_transfer_checked(
+        token_program.key,
+        from,
+        mint,
+        to,
+        authority,
+        transfer_hook_accounts,
+        amount,
+        decimals,
+        signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::Pubkey;
+    use anchor_spl::token::spl_token;
+
+    use super::*;
+
+    fn transfer_fee(transfer_fee_basis_points: u16, maximum_fee: u64) -> TransferFee {
+        TransferFee {
+            epoch: 0.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: transfer_fee_basis_points.into(),
+        }
+    }
+
+    #[test]
+    fn test_calculate_inverse_transfer_fee() {
+        assert_eq!(
+            calculate_inverse_transfer_fee(&transfer_fee(100, u64::MAX), 9_900).unwrap(),
+            100
+        );
+        assert_eq!(
+            calculate_inverse_transfer_fee(&transfer_fee(100, 50), 9_900).unwrap(),
+            50
+        );
+        assert_eq!(
+            calculate_inverse_transfer_fee(&transfer_fee(0, u64::MAX), 9_900).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_calculate_inverse_transfer_fee_round_trips() {
+        for transfer_fee in [
+            transfer_fee(1, u64::MAX),
+            transfer_fee(33, u64::MAX),
+            transfer_fee(250, 1_000),
+        ] {
+            for amount_after_fee in (1..=5_000).chain([1_000_000_007, 123_456_789_012]) {
+                let inverse_fee =
+                    calculate_inverse_transfer_fee(&transfer_fee, amount_after_fee).unwrap();
+                let amount = amount_after_fee + inverse_fee;
+                assert_eq!(
+                    amount - transfer_fee.calculate_fee(amount).unwrap(),
+                    amount_after_fee
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_amount_including_transfer_fee_without_token_2022() {
+        let key = Pubkey::new_unique();
+        let owner = spl_token::ID;
+        let mut lamports = 0;
+        let mut data: [u8; 0] = [];
+        let mint = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        assert_eq!(get_amount_including_transfer_fee(&mint, 0).unwrap(), 0);
+        assert_eq!(
+            get_amount_including_transfer_fee(&mint, 1_000).unwrap(),
+            1_000
+        );
+        assert_eq!(get_transfer_fee(&mint, 1_000).unwrap(), 0);
+    }
+}